@@ -0,0 +1,282 @@
+//
+// Forward-transform timings for every plan strategy that can handle each
+// size. Run with `cargo bench --bench fft` (add `--features use_fftw` to
+// include FFTW).
+//
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fftanalyzer::fft::improved::{
+    DFTImproved, Kernel10, Kernel12, Kernel15, Kernel16, Kernel8, Kernel9,
+};
+use fftanalyzer::fft::iterative::DFTIterativeP2;
+use fftanalyzer::fft::orig::{DFTBluestein, DFTRader, DFTRadix};
+use fftanalyzer::fft::prime_cache;
+use fftanalyzer::fft::prime_power::DFTPrimePower;
+use fftanalyzer::fft::split_radix::DFTSplitRadix;
+use fftanalyzer::{find_dft, DFTBase, Direction};
+use num_complex::Complex32;
+use std::sync::Arc;
+
+const SIZES: [usize; 7] = [256, 1000, 1024, 2048, 4096, 4099, 4095];
+
+/// Deterministic, non-trivial input so no strategy hits a fast path.
+fn signal(n: usize) -> Vec<Complex32> {
+    (0..n)
+        .map(|i| {
+            let t = i as f32;
+            Complex32::new((0.37 * t).sin(), (0.11 * t).cos())
+        })
+        .collect()
+}
+
+fn bench_plan(c: &mut Criterion, group: &str, plans: Vec<(&str, Arc<dyn DFTBase>)>, n: usize) {
+    let input = signal(n);
+    let mut output = vec![Complex32::default(); n];
+    let mut group = c.benchmark_group(group);
+    for (strategy, plan) in plans {
+        group.bench_with_input(BenchmarkId::new(strategy, n), &input, |b, input| {
+            b.iter(|| plan.xform(black_box(input), &mut output))
+        });
+    }
+    group.finish();
+}
+
+/// Cooley–Tukey steps that always peel off the smallest prime, down to
+/// the SIMD kernels, as the planner built composite plans before preferring
+/// radix 4/8 and split-radix. `DFTRadix::new` picks its own factor and asks
+/// the planner for the rest, so the chain is wired up by hand.
+fn smallest_prime_chain(n: usize) -> Arc<dyn DFTBase> {
+    let (factors, count) = prime_cache::get_factors_all(n);
+    if n <= 9 || n == 16 || count < 2 {
+        return find_dft(n, Direction::Forward);
+    }
+    let p = factors[0];
+    Arc::new(DFTRadix::with_sub_plans(
+        n,
+        Direction::Forward,
+        find_dft(p, Direction::Forward),
+        smallest_prime_chain(n / p),
+    ))
+}
+
+fn forward(c: &mut Criterion) {
+    for n in SIZES {
+        let (_factors, count) = prime_cache::get_factors_all(n);
+        let nb = (2 * n - 1).next_power_of_two();
+
+        //
+        // `find_dft` shows what the planner picks; the rest force a strategy.
+        // Radix needs a composite size and Rader a prime one.
+        //
+        let mut plans: Vec<(&str, Arc<dyn DFTBase>)> = vec![
+            ("find_dft", find_dft(n, Direction::Forward)),
+            (
+                "bluestein",
+                Arc::new(DFTBluestein::new(n, nb, Direction::Forward)),
+            ),
+        ];
+        if n.is_power_of_two() {
+            plans.push((
+                "split_radix",
+                Arc::new(DFTSplitRadix::new(n, Direction::Forward)),
+            ));
+            plans.push((
+                "iterative_p2",
+                Arc::new(DFTIterativeP2::new(n, Direction::Forward)),
+            ));
+            plans.push(("radix", Arc::new(DFTRadix::new(n, Direction::Forward))));
+            plans.push(("smallest_prime", smallest_prime_chain(n)));
+        } else if count >= 2 {
+            plans.push(("radix", Arc::new(DFTRadix::new(n, Direction::Forward))));
+            plans.push(("smallest_prime", smallest_prime_chain(n)));
+        } else {
+            plans.push(("rader", Arc::new(DFTRader::new(n, Direction::Forward))));
+        }
+        #[cfg(feature = "use_fftw")]
+        plans.push((
+            "fftw",
+            Arc::new(
+                fftanalyzer::fft::fftw::DFT_FFTW::new(n, Direction::Forward).expect("FFTW plan"),
+            ),
+        ));
+
+        bench_plan(c, "forward", plans, n);
+    }
+}
+
+fn improved_kernels(c: &mut Criterion) {
+    //
+    // The SIMD kernels only exist at small sizes; time them against the
+    // generic strategies at the same size.
+    //
+    let sizes: [(usize, Arc<dyn DFTBase>); 6] = [
+        (
+            8,
+            Arc::new(DFTImproved::<f32, Kernel8, 8>::new(Direction::Forward)),
+        ),
+        (
+            9,
+            Arc::new(DFTImproved::<f32, Kernel9, 9>::new(Direction::Forward)),
+        ),
+        (
+            10,
+            Arc::new(DFTImproved::<f32, Kernel10, 10>::new(Direction::Forward)),
+        ),
+        (
+            12,
+            Arc::new(DFTImproved::<f32, Kernel12, 12>::new(Direction::Forward)),
+        ),
+        (
+            15,
+            Arc::new(DFTImproved::<f32, Kernel15, 15>::new(Direction::Forward)),
+        ),
+        (
+            16,
+            Arc::new(DFTImproved::<f32, Kernel16, 16>::new(Direction::Forward)),
+        ),
+    ];
+    for (n, improved) in sizes {
+        let nb = (2 * n - 1).next_power_of_two();
+        let plans: Vec<(&str, Arc<dyn DFTBase>)> = vec![
+            ("improved", improved),
+            ("radix", Arc::new(DFTRadix::new(n, Direction::Forward))),
+            (
+                "bluestein",
+                Arc::new(DFTBluestein::new(n, nb, Direction::Forward)),
+            ),
+        ];
+        bench_plan(c, "small", plans, n);
+    }
+}
+
+fn simd_lanes(c: &mut Criterion) {
+    //
+    // A batch of small transforms, as radix steps hand to the kernels, at
+    // each SIMD width `DFTImproved` can use. `max_lanes` picks the widest
+    // one the CPU supports.
+    //
+    let count = 256;
+    let input = signal(16 * count);
+    let mut output = vec![Complex32::default(); 16 * count];
+
+    let mut group = c.benchmark_group("lanes");
+    for lanes in [4, 8, 16] {
+        let plans: [(&str, usize, Arc<dyn DFTBase>); 2] = [
+            (
+                "kernel8",
+                8,
+                Arc::new(DFTImproved::<f32, Kernel8, 8>::with_lanes(
+                    Direction::Forward,
+                    lanes,
+                )),
+            ),
+            (
+                "kernel16",
+                16,
+                Arc::new(DFTImproved::<f32, Kernel16, 16>::with_lanes(
+                    Direction::Forward,
+                    lanes,
+                )),
+            ),
+        ];
+        for (kernel, n, plan) in plans {
+            group.bench_function(BenchmarkId::new(kernel, lanes), |b| {
+                b.iter(|| {
+                    plan.xform_many(
+                        black_box(&input[..n * count]),
+                        &mut output[..n * count],
+                        1,
+                        n,
+                        1,
+                        n,
+                        count,
+                    )
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn prime_powers(c: &mut Criterion) {
+    //
+    // The iterative prime-power plan against the radix-3 chain down to the
+    // 9-point kernel that the planner used for these sizes before.
+    //
+    for n in [81, 243, 2187] {
+        let plans: Vec<(&str, Arc<dyn DFTBase>)> = vec![
+            (
+                "prime_power",
+                Arc::new(DFTPrimePower::new(n, Direction::Forward)),
+            ),
+            ("smallest_prime", smallest_prime_chain(n)),
+        ];
+        bench_plan(c, "prime_power", plans, n);
+    }
+}
+
+fn bluestein_convolution_sizes(c: &mut Criterion) {
+    //
+    // Prime sizes through Bluestein with the power-of-two convolution the
+    // planner used before, against the smallest 7-smooth one.
+    //
+    for n in [1009usize, 2053, 4099, 5003, 10007, 65537] {
+        let pow2 = (2 * n - 1).next_power_of_two();
+        let smooth = prime_cache::next_7_smooth(2 * n - 1);
+        let plans: Vec<(&str, Arc<dyn DFTBase>)> = vec![
+            (
+                "pow2",
+                Arc::new(DFTBluestein::new(n, pow2, Direction::Forward)),
+            ),
+            (
+                "smooth7",
+                Arc::new(DFTBluestein::new(n, smooth, Direction::Forward)),
+            ),
+        ];
+        bench_plan(c, "bluestein_nb", plans, n);
+    }
+}
+
+// Bluestein batch size for the steady-state group, below the `parallel`
+// feature's threshold of eight so the batch stays on one thread.
+const BLUESTEIN_BATCH: usize = 4;
+
+fn bluestein_steady_state(c: &mut Criterion) {
+    //
+    // Repeated single transforms on one plan, the realtime case, against a
+    // small batch. Both reuse the plan's scratch after the first call. The
+    // `alloc` baseline also allocates and zeroes the two padded blocks on
+    // every call, as Bluestein did before it kept its scratch.
+    //
+    let n = 2048usize;
+    let nb = (2 * n - 1).next_power_of_two();
+    let plan = DFTBluestein::new(n, nb, Direction::Forward);
+    let input = signal(BLUESTEIN_BATCH * n);
+    let mut output = vec![Complex32::default(); BLUESTEIN_BATCH * n];
+
+    let mut group = c.benchmark_group("bluestein");
+    group.bench_function(BenchmarkId::new("alloc", 1), |b| {
+        b.iter(|| {
+            let buf = vec![Complex32::default(); 2 * nb];
+            plan.xform(black_box(&input[..n]), &mut output[..n]);
+            black_box(buf)
+        })
+    });
+    group.bench_function(BenchmarkId::new("count", 1), |b| {
+        b.iter(|| plan.xform(black_box(&input[..n]), &mut output[..n]))
+    });
+    group.bench_function(BenchmarkId::new("count", BLUESTEIN_BATCH), |b| {
+        b.iter(|| plan.xform_many(black_box(&input), &mut output, 1, n, 1, n, BLUESTEIN_BATCH))
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    forward,
+    improved_kernels,
+    simd_lanes,
+    prime_powers,
+    bluestein_convolution_sizes,
+    bluestein_steady_state
+);
+criterion_main!(benches);
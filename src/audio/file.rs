@@ -0,0 +1,286 @@
+use super::{ChannelMode, SampleConsumer, RING_BUFFER_MULTIPLIER};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+//
+// Samples are released in blocks of this duration.
+//
+const TICK: Duration = Duration::from_millis(10);
+
+/// Plays a decoded WAV file into the sample ring buffer at real-time pace.
+/// Dropping the player stops the feeder thread.
+pub struct FilePlayer {
+    sample_rate: u32,
+    stop: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    dropped: Arc<AtomicUsize>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FilePlayer {
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Samples discarded so far because a ring buffer was full.
+    pub fn dropped_samples(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// True once every sample in the file has been pushed.
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for FilePlayer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Decodes a PCM or float WAV file and streams it, downmixed to mono, into
+/// a ring buffer exactly like live capture.
+pub fn start_file_source(
+    path: &Path,
+    buffer_size: usize,
+) -> Result<(FilePlayer, SampleConsumer), hound::Error> {
+    let (player, mut consumers) = start_file_source_with(path, ChannelMode::Mono, buffer_size)?;
+    Ok((player, consumers.swap_remove(0)))
+}
+
+/// Like `start_file_source`, but splits the file's channels into the
+/// streams chosen by `mode`, one consumer per stream.
+pub fn start_file_source_with(
+    path: &Path,
+    mode: ChannelMode,
+    buffer_size: usize,
+) -> Result<(FilePlayer, Vec<SampleConsumer>), hound::Error> {
+    let (spec, samples) = decode(path)?;
+    let channels = spec.channels.max(1) as usize;
+
+    //
+    // Same headroom as live capture.
+    //
+    let (mut producers, consumers) = mode.ring_buffers(buffer_size * RING_BUFFER_MULTIPLIER);
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let finished = Arc::new(AtomicBool::new(false));
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let frames_per_tick = ((spec.sample_rate as f64 * TICK.as_secs_f64()) as usize).max(1);
+    let tick = Duration::from_secs_f64(frames_per_tick as f64 / spec.sample_rate as f64);
+
+    let thread = {
+        let stop = stop.clone();
+        let finished = finished.clone();
+        let dropped = dropped.clone();
+        thread::spawn(move || {
+            //
+            // Schedule against absolute deadlines so sleep jitter does not
+            // accumulate into drift.
+            //
+            let mut deadline = Instant::now();
+            for block in samples.chunks(frames_per_tick * channels) {
+                if stop.load(Ordering::Acquire) {
+                    return;
+                }
+                let lost = mode.push(&mut producers, block, channels);
+                if lost > 0 {
+                    dropped.fetch_add(lost, Ordering::Relaxed);
+                }
+                deadline += tick;
+                if let Some(wait) = deadline.checked_duration_since(Instant::now()) {
+                    thread::sleep(wait);
+                }
+            }
+            finished.store(true, Ordering::Release);
+        })
+    };
+
+    Ok((
+        FilePlayer {
+            sample_rate: spec.sample_rate,
+            stop,
+            finished,
+            dropped,
+            thread: Some(thread),
+        },
+        consumers,
+    ))
+}
+
+/// Like `start_file_source_with`, but puts the whole file into the ring
+/// buffers at once instead of at real-time pace, for offline analysis.
+/// Returns the file's sample rate with the consumers.
+pub fn read_file_source(
+    path: &Path,
+    mode: ChannelMode,
+) -> Result<(u32, Vec<SampleConsumer>), hound::Error> {
+    let (spec, samples) = decode(path)?;
+    let channels = spec.channels.max(1) as usize;
+    let (mut producers, consumers) = mode.ring_buffers((samples.len() / channels).max(1));
+    mode.push(&mut producers, &samples, channels);
+    Ok((spec.sample_rate, consumers))
+}
+
+/// Reads the format and every interleaved sample of a WAV file, scaled to
+/// full scale at 1.0.
+fn decode(path: &Path) -> Result<(hound::WavSpec, Vec<f32>), hound::Error> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+
+    //
+    // Decode everything up front so format errors surface here.
+    //
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 * scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    let channels = spec.channels.max(1) as usize;
+    log::info!(
+        "File source: {} @ {}Hz, Channels: {}, {:.1}s",
+        path.display(),
+        spec.sample_rate,
+        channels,
+        samples.len() as f32 / channels as f32 / spec.sample_rate as f32
+    );
+    Ok((spec, samples))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stereo_wav_is_downmixed_in_real_time() {
+        let path = std::env::temp_dir().join(format!("fftanalyzer_{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..400 {
+            writer.write_sample(16384i16).unwrap();
+            writer.write_sample(-8192i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        //
+        // 400 frames at 8 kHz take 50 ms to play out.
+        //
+        let started = Instant::now();
+        let (player, mut consumer) = start_file_source(&path, 1024).unwrap();
+        while !player.is_finished() {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(started.elapsed() >= Duration::from_millis(40));
+
+        let mono: Vec<f32> = std::iter::from_fn(|| consumer.pop()).collect();
+        assert_eq!(mono.len(), 400);
+        assert!(mono.iter().all(|&s| (s - 0.125).abs() < 1e-6));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn stereo_mode_keeps_channels_apart() {
+        let path =
+            std::env::temp_dir().join(format!("fftanalyzer_stereo_{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..80 {
+            writer.write_sample(16384i16).unwrap();
+            writer.write_sample(-8192i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let (player, mut consumers) =
+            start_file_source_with(&path, ChannelMode::Stereo, 1024).unwrap();
+        while !player.is_finished() {
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        let expected = [0.5, -0.25];
+        assert_eq!(consumers.len(), 2);
+        for (consumer, level) in consumers.iter_mut().zip(expected) {
+            let samples: Vec<f32> = std::iter::from_fn(|| consumer.pop()).collect();
+            assert_eq!(samples.len(), 80);
+            assert!(samples.iter().all(|&s| s == level));
+        }
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn reading_queues_the_whole_file_at_once() {
+        let path =
+            std::env::temp_dir().join(format!("fftanalyzer_read_{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..20000 {
+            writer.write_sample(16384i16).unwrap();
+            writer.write_sample(-8192i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        //
+        // 2.5 s of audio, available without waiting for playback.
+        //
+        let (sample_rate, mut consumers) = read_file_source(&path, ChannelMode::Stereo).unwrap();
+        assert_eq!(sample_rate, 8000);
+        for (consumer, level) in consumers.iter_mut().zip([0.5, -0.25]) {
+            let samples: Vec<f32> = std::iter::from_fn(|| consumer.pop()).collect();
+            assert_eq!(samples.len(), 20000);
+            assert!(samples.iter().all(|&s| s == level));
+        }
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn overrun_is_counted_when_the_reader_stalls() {
+        let path =
+            std::env::temp_dir().join(format!("fftanalyzer_overrun_{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..200 {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let (player, _consumer) = start_file_source(&path, 10).unwrap();
+        while !player.is_finished() {
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        assert_eq!(player.dropped_samples(), 200 - 10 * RING_BUFFER_MULTIPLIER);
+        let _ = std::fs::remove_file(path);
+    }
+}
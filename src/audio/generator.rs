@@ -0,0 +1,300 @@
+use super::{ChannelMode, SampleConsumer, SampleProducer, RING_BUFFER_MULTIPLIER};
+use parking_lot::Mutex;
+use std::f64::consts::TAU;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+//
+// Samples are released in blocks of this duration.
+//
+const TICK: Duration = Duration::from_millis(10);
+
+/// Shape of the generated test signal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Sine,
+    Square,
+    WhiteNoise,
+    /// Linear sweep from `frequency` to `sweep_end`, restarting every
+    /// `sweep_secs`.
+    Sweep,
+}
+
+impl Waveform {
+    pub const ALL: [Waveform; 4] = [
+        Waveform::Sine,
+        Waveform::Square,
+        Waveform::WhiteNoise,
+        Waveform::Sweep,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Waveform::Sine => "Sine",
+            Waveform::Square => "Square",
+            Waveform::WhiteNoise => "White noise",
+            Waveform::Sweep => "Sweep",
+        }
+    }
+}
+
+/// Test-signal parameters. Frequencies are in Hz and `amplitude` is the
+/// peak level, 1.0 being full scale.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeneratorSettings {
+    pub waveform: Waveform,
+    pub frequency: f32,
+    pub amplitude: f32,
+    pub sweep_end: f32,
+    pub sweep_secs: f32,
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        Self {
+            waveform: Waveform::Sine,
+            frequency: 1000.0,
+            amplitude: 0.5,
+            sweep_end: 20000.0,
+            sweep_secs: 10.0,
+        }
+    }
+}
+
+/// Phase-continuous synthesis of `GeneratorSettings`, so frequency and
+/// waveform changes between blocks never click.
+pub struct Oscillator {
+    sample_rate: f64,
+
+    //
+    // Phase in cycles, in [0, 1), and seconds into the current sweep.
+    //
+    phase: f64,
+    sweep_pos: f64,
+
+    //
+    // xorshift32 state for white noise.
+    //
+    noise: u32,
+}
+
+impl Oscillator {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate: sample_rate as f64,
+            phase: 0.0,
+            sweep_pos: 0.0,
+            noise: 0x9e37_79b9,
+        }
+    }
+
+    /// Writes the next `out.len()` samples of the signal.
+    pub fn fill(&mut self, settings: &GeneratorSettings, out: &mut [f32]) {
+        let dt = 1.0 / self.sample_rate;
+        let amplitude = settings.amplitude;
+        let sweep_secs = (settings.sweep_secs as f64).max(dt);
+        for sample in out {
+            let value = match settings.waveform {
+                Waveform::Sine | Waveform::Sweep => (TAU * self.phase).sin() as f32,
+                Waveform::Square => {
+                    if self.phase < 0.5 {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+                Waveform::WhiteNoise => {
+                    self.noise ^= self.noise << 13;
+                    self.noise ^= self.noise >> 17;
+                    self.noise ^= self.noise << 5;
+                    (self.noise as f64 / u32::MAX as f64 * 2.0 - 1.0) as f32
+                }
+            };
+            *sample = value * amplitude;
+
+            //
+            // Advance by the instantaneous frequency; a sweep moves it
+            // linearly across the span and wraps back to the start.
+            //
+            let hz = match settings.waveform {
+                Waveform::Sweep => {
+                    let t = self.sweep_pos / sweep_secs;
+                    self.sweep_pos += dt;
+                    if self.sweep_pos >= sweep_secs {
+                        self.sweep_pos = 0.0;
+                    }
+                    settings.frequency as f64
+                        + (settings.sweep_end as f64 - settings.frequency as f64) * t
+                }
+                _ => settings.frequency as f64,
+            };
+            self.phase = (self.phase + hz * dt).fract();
+        }
+    }
+}
+
+/// Feeds a synthesized test signal into the sample ring buffers at real-time
+/// pace. Settings can be changed while it runs; dropping it stops the
+/// feeder thread.
+pub struct SignalGenerator {
+    sample_rate: u32,
+    settings: Arc<Mutex<GeneratorSettings>>,
+    stop: Arc<AtomicBool>,
+    dropped: Arc<AtomicUsize>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SignalGenerator {
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Samples discarded so far because a ring buffer was full.
+    pub fn dropped_samples(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn settings(&self) -> GeneratorSettings {
+        *self.settings.lock()
+    }
+
+    /// Takes effect from the next block, within one tick.
+    pub fn set_settings(&self, settings: GeneratorSettings) {
+        *self.settings.lock() = settings;
+    }
+}
+
+impl Drop for SignalGenerator {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Starts generating `settings` at `sample_rate`. The signal is mono and is
+/// copied into every stream `mode` produces, one consumer per stream, with
+/// the same ring-buffer sizing as live capture.
+pub fn start_generator(
+    settings: GeneratorSettings,
+    sample_rate: u32,
+    mode: ChannelMode,
+    buffer_size: usize,
+) -> (SignalGenerator, Vec<SampleConsumer>) {
+    log::info!(
+        "Signal generator: {} {:.1}Hz @ {}Hz",
+        settings.waveform.name(),
+        settings.frequency,
+        sample_rate
+    );
+    let (mut producers, consumers) = mode.ring_buffers(buffer_size * RING_BUFFER_MULTIPLIER);
+
+    let settings = Arc::new(Mutex::new(settings));
+    let stop = Arc::new(AtomicBool::new(false));
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let frames_per_tick = ((sample_rate as f64 * TICK.as_secs_f64()) as usize).max(1);
+    let tick = Duration::from_secs_f64(frames_per_tick as f64 / sample_rate as f64);
+
+    let thread = {
+        let settings = settings.clone();
+        let stop = stop.clone();
+        let dropped = dropped.clone();
+        thread::spawn(move || {
+            let mut oscillator = Oscillator::new(sample_rate);
+            let mut block = vec![0.0; frames_per_tick];
+
+            //
+            // Schedule against absolute deadlines so sleep jitter does not
+            // accumulate into drift.
+            //
+            let mut deadline = Instant::now();
+            while !stop.load(Ordering::Acquire) {
+                let current = *settings.lock();
+                let lost = feed_block(&mut oscillator, &current, &mut block, mode, &mut producers);
+                if lost > 0 {
+                    dropped.fetch_add(lost, Ordering::Relaxed);
+                }
+                deadline += tick;
+                if let Some(wait) = deadline.checked_duration_since(Instant::now()) {
+                    thread::sleep(wait);
+                }
+            }
+        })
+    };
+
+    (
+        SignalGenerator {
+            sample_rate,
+            settings,
+            stop,
+            dropped,
+            thread: Some(thread),
+        },
+        consumers,
+    )
+}
+
+/// Synthesizes the next `block.len()` samples of `settings` and copies them
+/// into every stream of `mode`, returning how many did not fit.
+fn feed_block(
+    oscillator: &mut Oscillator,
+    settings: &GeneratorSettings,
+    block: &mut [f32],
+    mode: ChannelMode,
+    producers: &mut [SampleProducer],
+) -> usize {
+    oscillator.fill(settings, block);
+    mode.push(producers, block, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft::find_rdft;
+    use num_complex::Complex32;
+
+    #[test]
+    fn sine_lands_in_its_bin() {
+        //
+        // 1 kHz at 48 kHz sits at bin 1000 * 2048 / 48000 ≈ 42.7.
+        //
+        let n = 2048;
+        let mut samples = vec![0.0; n];
+        Oscillator::new(48000).fill(&GeneratorSettings::default(), &mut samples);
+        assert!(samples.iter().all(|s| s.abs() <= 0.5 + 1e-6));
+
+        let mut spectrum = vec![Complex32::default(); n / 2 + 1];
+        find_rdft(n).xform_real(&samples, &mut spectrum);
+        let peak = (0..=n / 2)
+            .max_by(|&a, &b| spectrum[a].norm().total_cmp(&spectrum[b].norm()))
+            .unwrap();
+        assert!(peak == 42 || peak == 43, "peak at bin {}", peak);
+    }
+
+    #[test]
+    fn stereo_streams_receive_the_same_signal() {
+        let settings = GeneratorSettings {
+            waveform: Waveform::Square,
+            ..GeneratorSettings::default()
+        };
+        let mode = ChannelMode::Stereo;
+        let (mut producers, mut consumers) = mode.ring_buffers(1024);
+        let mut oscillator = Oscillator::new(8000);
+        let mut block = vec![0.0; 80];
+        for _ in 0..2 {
+            let lost = feed_block(&mut oscillator, &settings, &mut block, mode, &mut producers);
+            assert_eq!(lost, 0);
+        }
+
+        let streams: Vec<Vec<f32>> = consumers
+            .iter_mut()
+            .map(|c| std::iter::from_fn(|| c.pop()).collect())
+            .collect();
+        assert_eq!(streams[0].len(), 160);
+        assert_eq!(streams[0], streams[1]);
+        assert!(streams[0].iter().all(|&s| s.abs() == 0.5));
+    }
+}
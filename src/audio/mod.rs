@@ -1,31 +1,486 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Sample, SampleFormat};
-use ringbuf::{Consumer, HeapRb};
+use cpal::{SampleFormat, SupportedBufferSize};
+use ringbuf::{Consumer, HeapRb, Producer};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
-/// Starts audio capture on the default input device.
-/// Supports f32, i16, and u16 formats and performs stereo-to-mono downmixing.
-pub fn start_capture(buffer_size: usize) -> (cpal::Stream, Consumer<f32, Arc<HeapRb<f32>>>) {
-    let host = cpal::default_host();
+mod file;
+mod generator;
+mod pcm;
+
+pub use file::{read_file_source, start_file_source, start_file_source_with, FilePlayer};
+pub use generator::{start_generator, GeneratorSettings, Oscillator, SignalGenerator, Waveform};
+pub use pcm::{read_pcm_source, start_pcm_source, start_stdin_source, PcmFormat, PcmSource};
+
+/// Receiving end of the capture ring buffer, yielding mono f32 samples.
+pub type SampleConsumer = Consumer<f32, Arc<HeapRb<f32>>>;
+type SampleProducer = Producer<f32, Arc<HeapRb<f32>>>;
+
+/// Ring buffers hold this many times the requested `buffer_size`, giving
+/// the reader that much slack before input is dropped.
+pub const RING_BUFFER_MULTIPLIER: usize = 4;
+
+/// A running capture stream. Dropping it stops capture.
+pub struct LiveCapture {
+    stream: cpal::Stream,
+    sample_rate: u32,
+    buffer_frames: Option<u32>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl LiveCapture {
+    pub fn stream(&self) -> &cpal::Stream {
+        &self.stream
+    }
+
+    /// Rate the device was opened at, which is not necessarily the one
+    /// requested.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Fixed device buffer size in frames, or `None` when the device
+    /// chose its own.
+    pub fn buffer_frames(&self) -> Option<u32> {
+        self.buffer_frames
+    }
+
+    /// Samples discarded so far because a ring buffer was full, i.e. the
+    /// reader fell behind by more than the buffer holds.
+    pub fn dropped_samples(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Keeps a sample source running; dropping it stops capture or playback.
+pub enum AudioSource {
+    Live(LiveCapture),
+    File(FilePlayer),
+    Generator(SignalGenerator),
+    /// Raw PCM read from standard input.
+    Pipe(PcmSource),
+    /// Setup failed; the reason is kept so it can be shown to the user.
+    Unavailable(String),
+}
+
+impl AudioSource {
+    /// A source that never produces samples, paired with an empty consumer,
+    /// so the analyzer can keep running after setup fails.
+    pub fn unavailable(reason: impl fmt::Display) -> (Self, SampleConsumer) {
+        let (_, consumer) = HeapRb::<f32>::new(1).split();
+        (AudioSource::Unavailable(reason.to_string()), consumer)
+    }
+
+    /// Rate samples arrive at, or `None` if there is no source.
+    pub fn sample_rate(&self) -> Option<u32> {
+        match self {
+            AudioSource::Live(capture) => Some(capture.sample_rate()),
+            AudioSource::File(player) => Some(player.sample_rate()),
+            AudioSource::Generator(generator) => Some(generator.sample_rate()),
+            AudioSource::Pipe(pipe) => Some(pipe.sample_rate()),
+            AudioSource::Unavailable(_) => None,
+        }
+    }
+
+    /// Samples lost to ring-buffer overruns since the source started.
+    pub fn dropped_samples(&self) -> usize {
+        match self {
+            AudioSource::Live(capture) => capture.dropped_samples(),
+            AudioSource::File(player) => player.dropped_samples(),
+            AudioSource::Generator(generator) => generator.dropped_samples(),
+            AudioSource::Pipe(_) | AudioSource::Unavailable(_) => 0,
+        }
+    }
+
+    /// Why no samples are arriving, if setup failed.
+    pub fn error(&self) -> Option<&str> {
+        match self {
+            AudioSource::Unavailable(reason) => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+impl From<LiveCapture> for AudioSource {
+    fn from(capture: LiveCapture) -> Self {
+        AudioSource::Live(capture)
+    }
+}
+
+impl From<FilePlayer> for AudioSource {
+    fn from(player: FilePlayer) -> Self {
+        AudioSource::File(player)
+    }
+}
+
+impl From<SignalGenerator> for AudioSource {
+    fn from(generator: SignalGenerator) -> Self {
+        AudioSource::Generator(generator)
+    }
+}
+
+impl From<PcmSource> for AudioSource {
+    fn from(pipe: PcmSource) -> Self {
+        AudioSource::Pipe(pipe)
+    }
+}
+
+/// How interleaved input channels become the sample streams handed to the
+/// analyzer.
+///
+/// Each stream gets its own ring buffer of `4 × buffer_size` samples, so
+/// `Stereo` doubles the capture memory (128 KiB per stream at the default
+/// 8192-sample sizing) along with the per-channel history the analyzer keeps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChannelMode {
+    /// One stream: stereo averaged, wider layouts reduced to their first
+    /// channel. The same as `Downmix(DownmixMode::Average)`.
+    #[default]
+    Mono,
+    /// One stream, with several channels combined as the `DownmixMode` says.
+    Downmix(DownmixMode),
+    /// Two streams, left and right. Mono input is duplicated into both.
+    Stereo,
+    /// One stream carrying only the given channel (0-based), clamped to the
+    /// channels the input actually has.
+    Channel(usize),
+}
+
+impl ChannelMode {
+    /// Number of sample streams this mode produces.
+    pub fn streams(self) -> usize {
+        match self {
+            ChannelMode::Stereo => 2,
+            ChannelMode::Mono | ChannelMode::Downmix(_) | ChannelMode::Channel(_) => 1,
+        }
+    }
+
+    /// One ring buffer per stream, each holding `capacity` samples.
+    fn ring_buffers(self, capacity: usize) -> (Vec<SampleProducer>, Vec<SampleConsumer>) {
+        (0..self.streams())
+            .map(|_| HeapRb::<f32>::new(capacity).split())
+            .unzip()
+    }
+
+    /// Routes interleaved `data` with `channels` channels into `producers`
+    /// and returns how many samples did not fit.
+    fn push(self, producers: &mut [SampleProducer], data: &[f32], channels: usize) -> usize {
+        match self {
+            ChannelMode::Mono => push_mono(&mut producers[0], data, channels, DownmixMode::Average),
+            ChannelMode::Downmix(downmix) => push_mono(&mut producers[0], data, channels, downmix),
+            ChannelMode::Channel(index) => {
+                let index = index.min(channels - 1);
+                data.chunks_exact(channels)
+                    .filter(|chunk| producers[0].push(chunk[index]).is_err())
+                    .count()
+            }
+            ChannelMode::Stereo => {
+                let mut dropped = 0;
+                for chunk in data.chunks_exact(channels) {
+                    let left = chunk[0];
+                    let right = chunk.get(1).copied().unwrap_or(left);
+                    dropped += producers[0].push(left).is_err() as usize;
+                    dropped += producers[1].push(right).is_err() as usize;
+                }
+                dropped
+            }
+        }
+    }
+}
+
+/// How `ChannelMode::Downmix` combines several input channels into one.
+///
+/// The scaling matters for level readings. When left and right carry the
+/// same signal at amplitude A, `Average` reads A, `EnergyPreserving` reads
+/// √2·A (+3 dB) and `Sum` reads 2A (+6 dB), so `Sum` can exceed full scale.
+/// When the two channels are uncorrelated and equally loud, `Average` reads
+/// 3 dB below either channel, `EnergyPreserving` matches them and `Sum`
+/// reads 3 dB above. Signals in opposite phase cancel in every mode. Mono
+/// input is always passed through unchanged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DownmixMode {
+    /// `(L + R) / 2` for stereo; wider layouts use their first channel.
+    #[default]
+    Average,
+    /// `L + R` for stereo; wider layouts use their first channel.
+    Sum,
+    /// `(L + R) / √2` for stereo; wider layouts use their first channel.
+    EnergyPreserving,
+    /// Mean of every channel, whatever the layout.
+    AverageAll,
+}
+
+impl DownmixMode {
+    pub const ALL: [DownmixMode; 4] = [
+        DownmixMode::Average,
+        DownmixMode::Sum,
+        DownmixMode::EnergyPreserving,
+        DownmixMode::AverageAll,
+    ];
+
+    /// Short name as accepted by `from_name`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DownmixMode::Average => "average",
+            DownmixMode::Sum => "sum",
+            DownmixMode::EnergyPreserving => "energy",
+            DownmixMode::AverageAll => "average-all",
+        }
+    }
+
+    /// Looks up a mode by `name()`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Which input device `start_capture` should open.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeviceSelector {
+    /// The host's default input device.
+    Default,
+    /// Position in `host.input_devices()`, as printed in the device log.
+    Index(usize),
+    /// Exact device name, as printed in the device log.
+    Name(String),
+}
+
+/// Which cpal host (audio backend) capture goes through, e.g. JACK instead
+/// of ALSA on Linux or ASIO instead of WASAPI on Windows. Hosts other than
+/// the platform default exist only when cpal is built with them (the
+/// `jack` and `asio` features).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HostBackend {
+    /// `cpal::default_host()`.
+    #[default]
+    Default,
+    /// A specific host; capture falls back to the default host with a
+    /// warning if it is unavailable.
+    Id(cpal::HostId),
+}
+
+impl HostBackend {
+    /// Looks up a host by its cpal name, ignoring case ("ALSA", "jack",
+    /// "WASAPI", ...), or "default". Only hosts compiled into this build
+    /// are found.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("default") {
+            return Some(HostBackend::Default);
+        }
+        cpal::ALL_HOSTS
+            .iter()
+            .find(|id| id.name().eq_ignore_ascii_case(name))
+            .map(|&id| HostBackend::Id(id))
+    }
+
+    /// Names of the hosts compiled into this build.
+    pub fn names() -> Vec<&'static str> {
+        cpal::ALL_HOSTS.iter().map(|id| id.name()).collect()
+    }
+
+    /// Opens the host, falling back to the default one if it cannot.
+    fn open(self) -> cpal::Host {
+        match self {
+            HostBackend::Default => cpal::default_host(),
+            HostBackend::Id(id) => cpal::host_from_id(id).unwrap_or_else(|err| {
+                log::warn!(
+                    "Audio host {} is unavailable ({}); using the default host",
+                    id.name(),
+                    err
+                );
+                cpal::default_host()
+            }),
+        }
+    }
+}
+
+/// Where captured audio comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CaptureSource {
+    /// An input device such as a microphone or line-in.
+    Device(DeviceSelector),
+    /// Whatever is playing on the default output device, where the host
+    /// backend supports loopback capture (WASAPI on Windows).
+    Loopback,
+}
+
+/// Reasons audio capture could not be started.
+#[derive(Debug)]
+pub enum CaptureError {
+    /// No device matched the selector.
+    DeviceNotFound(DeviceSelector),
+    /// Loopback was requested but there is no default output device.
+    NoOutputDevice,
+    /// Loopback was requested on a host backend that cannot provide it.
+    LoopbackUnsupported(cpal::HostId),
+    Devices(cpal::DevicesError),
+    Config(cpal::DefaultStreamConfigError),
+    UnsupportedFormat(SampleFormat),
+    Build(cpal::BuildStreamError),
+    Play(cpal::PlayStreamError),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::DeviceNotFound(DeviceSelector::Default) => {
+                write!(f, "no default audio input device")
+            }
+            CaptureError::DeviceNotFound(DeviceSelector::Index(i)) => {
+                write!(f, "no audio input device at index {}", i)
+            }
+            CaptureError::DeviceNotFound(DeviceSelector::Name(name)) => {
+                write!(f, "no audio input device named {:?}", name)
+            }
+            CaptureError::NoOutputDevice => write!(f, "no default audio output device"),
+            CaptureError::LoopbackUnsupported(host) => write!(
+                f,
+                "loopback capture is not supported by the {} audio host",
+                host.name()
+            ),
+            CaptureError::Devices(err) => write!(f, "failed to list input devices: {}", err),
+            CaptureError::Config(err) => write!(f, "failed to get default input config: {}", err),
+            CaptureError::UnsupportedFormat(format) => {
+                write!(f, "unsupported audio sample format: {:?}", format)
+            }
+            CaptureError::Build(err) => write!(f, "failed to build audio stream: {}", err),
+            CaptureError::Play(err) => write!(f, "failed to start audio stream: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+/// What `start_capture` opens and how. Start from `CaptureOptions::new`
+/// and override fields with struct update syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureOptions {
+    /// The cpal host `source` is resolved on.
+    pub host: HostBackend,
+    pub source: CaptureSource,
+    /// How the input is split into streams; one consumer is returned per
+    /// stream, in channel order.
+    pub channels: ChannelMode,
+    /// Rate to open the device at when it supports it. Otherwise, or with
+    /// `None`, the device's default rate is used;
+    /// `LiveCapture::sample_rate` reports the outcome.
+    pub sample_rate: Option<u32>,
+    /// Fixed buffer of this many frames per callback, used when the
+    /// device's configuration supports it. Otherwise, or with `None`, the
+    /// device picks its own size, which can add unpredictable latency;
+    /// `LiveCapture::buffer_frames` reports the outcome.
+    pub latency_hint: Option<u32>,
+    /// Samples the ring buffers are sized for, before headroom.
+    pub buffer_size: usize,
+}
+
+impl CaptureOptions {
+    /// The default input device on the default host, downmixed to mono at
+    /// its default rate and buffer size.
+    pub fn new(buffer_size: usize) -> Self {
+        Self {
+            host: HostBackend::Default,
+            source: CaptureSource::Device(DeviceSelector::Default),
+            channels: ChannelMode::Mono,
+            sample_rate: None,
+            latency_hint: None,
+            buffer_size,
+        }
+    }
+}
+
+/// Starts audio capture as described by `options`. Supports f32 and 8-,
+/// 16- and 32-bit integer formats.
+pub fn start_capture(
+    options: CaptureOptions,
+) -> Result<(LiveCapture, Vec<SampleConsumer>), CaptureError> {
+    let CaptureOptions {
+        host: backend,
+        source,
+        channels: mode,
+        sample_rate,
+        latency_hint,
+        buffer_size,
+    } = options;
 
     //
-    // Log all available input devices for debugging.
+    // Log the hosts this build can use next to the device list.
     //
-    log::info!("--- AVAILABLE INPUT DEVICS ---");
-    if let Ok(devices) = host.input_devices() {
-        for (i, dev) in devices.enumerate() {
-            let name = dev.name().unwrap_or("Unknown".into());
-            log::info!("  [{}]: {}", i, name);
-        }
+    let host = backend.open();
+    log::info!("--- AVAILABLE AUDIO HOSTS ---");
+    for id in cpal::available_hosts() {
+        let marker = if id == host.id() { " (selected)" } else { "" };
+        log::info!("  {}{}", id.name(), marker);
     }
-    log::info!("-------------------------------");
+    log::info!("-----------------------------");
+
+    let loopback = matches!(source, CaptureSource::Loopback);
+
+    let (device, supported_config) = match source {
+        CaptureSource::Device(device_selector) => {
+            //
+            // Log all available input devices for debugging.
+            //
+            log::info!("--- AVAILABLE INPUT DEVICS ---");
+            if let Ok(devices) = host.input_devices() {
+                for (i, dev) in devices.enumerate() {
+                    let name = dev.name().unwrap_or("Unknown".into());
+                    log::info!("  [{}]: {}", i, name);
+                }
+            }
+            log::info!("-------------------------------");
+
+            //
+            // Resolve the requested audio input device.
+            //
+            let device = match &device_selector {
+                DeviceSelector::Default => host.default_input_device(),
+                DeviceSelector::Index(index) => host
+                    .input_devices()
+                    .map_err(CaptureError::Devices)?
+                    .nth(*index),
+                DeviceSelector::Name(name) => host
+                    .input_devices()
+                    .map_err(CaptureError::Devices)?
+                    .find(|dev| dev.name().is_ok_and(|n| n == *name)),
+            }
+            .ok_or(CaptureError::DeviceNotFound(device_selector))?;
+
+            //
+            // Retrieve the device's default input configuration.
+            //
+            let supported_config = device
+                .default_input_config()
+                .map_err(CaptureError::Config)?;
+            (device, supported_config)
+        }
+        CaptureSource::Loopback => loopback_device(&host)?,
+    };
 
     //
-    // Select the default audio input device.
+    // A requested rate replaces the default one if any supported
+    // configuration covers it.
     //
-    let device = host
-        .default_input_device()
-        .expect("No audio input device found. Please check system settings.");
+    let supported_config = match sample_rate {
+        Some(rate) => {
+            let ranges: Vec<_> = if loopback {
+                device
+                    .supported_output_configs()
+                    .map(Iterator::collect)
+                    .unwrap_or_default()
+            } else {
+                device
+                    .supported_input_configs()
+                    .map(Iterator::collect)
+                    .unwrap_or_default()
+            };
+            negotiate_config(ranges, supported_config, rate)
+        }
+        None => supported_config,
+    };
 
     log::info!(
         "Selected audio device: {}",
@@ -33,20 +488,17 @@ pub fn start_capture(buffer_size: usize) -> (cpal::Stream, Consumer<f32, Arc<Hea
     );
 
     //
-    // Create ring buffer (4× buffer size to reduce risk of underruns).
-    //
-    let (mut producer, consumer) = HeapRb::<f32>::new(buffer_size * 4).split();
-
-    //
-    // Retrieve and log the device's default input configuration.
+    // Create ring buffers with headroom so a briefly stalled reader does
+    // not lose input.
     //
-    let supported_config = device
-        .default_input_config()
-        .expect("Failed to get default input config");
+    let (mut producers, consumers) = mode.ring_buffers(buffer_size * RING_BUFFER_MULTIPLIER);
 
     let sample_format = supported_config.sample_format();
-    let config: cpal::StreamConfig = supported_config.into();
+    let buffer = choose_buffer_size(supported_config.buffer_size(), latency_hint);
+    let mut config: cpal::StreamConfig = supported_config.into();
+    config.buffer_size = buffer;
     let channels = config.channels as usize;
+    let sample_rate = config.sample_rate.0;
 
     log::info!(
         "Audio config: {:?} @ {}Hz, Channels: {}",
@@ -54,32 +506,33 @@ pub fn start_capture(buffer_size: usize) -> (cpal::Stream, Consumer<f32, Arc<Hea
         config.sample_rate.0,
         channels
     );
+    let buffer_frames = match buffer {
+        cpal::BufferSize::Fixed(frames) => {
+            log::info!(
+                "Audio buffer: {} frames ({:.1} ms)",
+                frames,
+                frames as f64 * 1000.0 / sample_rate as f64
+            );
+            Some(frames)
+        }
+        cpal::BufferSize::Default => {
+            log::info!("Audio buffer: device default");
+            None
+        }
+    };
 
     let err_fn = |err| eprintln!("Audio input error: {}", err);
 
     //
-    // Push mono samples into the buffer (downmix if necessary).
+    // Count what does not fit instead of failing silently; the reader
+    // reports it.
     //
-    let mut push_mono = move |data: &[f32]| {
-        if channels == 1 {
-            let _ = producer.push_slice(data);
-        } else if channels == 2 {
-            //
-            // Downmix stereo to mono using averaged samples.
-            //
-            for chunk in data.chunks_exact(2) {
-                let mono = (chunk[0] + chunk[1]) * 0.5;
-                let _ = producer.push(mono);
-            }
-        } else {
-            //
-            // Downmix multi-channel audio by selecting the first channel.
-            //
-            for chunk in data.chunks_exact(channels) {
-                if let Some(&sample) = chunk.first() {
-                    let _ = producer.push(sample);
-                }
-            }
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let counter = dropped.clone();
+    let mut push = move |data: &[f32]| {
+        let lost = mode.push(&mut producers, data, channels);
+        if lost > 0 {
+            counter.fetch_add(lost, Ordering::Relaxed);
         }
     };
 
@@ -87,7 +540,7 @@ pub fn start_capture(buffer_size: usize) -> (cpal::Stream, Consumer<f32, Arc<Hea
         SampleFormat::F32 => device.build_input_stream(
             &config,
             move |data: &[f32], _: &_| {
-                push_mono(data);
+                push(data);
             },
             err_fn,
             None,
@@ -99,7 +552,7 @@ pub fn start_capture(buffer_size: usize) -> (cpal::Stream, Consumer<f32, Arc<Hea
                 // Convert i16 samples to f32 before processing.
                 //
                 let f32_data: Vec<f32> = data.iter().map(|&s| (s as f32) / 32768.0).collect();
-                push_mono(&f32_data);
+                push(&f32_data);
             },
             err_fn,
             None,
@@ -114,19 +567,301 @@ pub fn start_capture(buffer_size: usize) -> (cpal::Stream, Consumer<f32, Arc<Hea
                     .iter()
                     .map(|&s| (s as f32 - 32768.0) / 32768.0)
                     .collect();
-                push_mono(&f32_data);
+                push(&f32_data);
+            },
+            err_fn,
+            None,
+        ),
+        SampleFormat::I32 => device.build_input_stream(
+            &config,
+            move |data: &[i32], _: &_| {
+                //
+                // Convert i32 samples to f32, scaling in f64 to keep precision.
+                //
+                let f32_data: Vec<f32> = data
+                    .iter()
+                    .map(|&s| (s as f64 / 2147483648.0) as f32)
+                    .collect();
+                push(&f32_data);
+            },
+            err_fn,
+            None,
+        ),
+        SampleFormat::U32 => device.build_input_stream(
+            &config,
+            move |data: &[u32], _: &_| {
+                //
+                // Convert u32 samples to signed f32 before processing.
+                //
+                let f32_data: Vec<f32> = data
+                    .iter()
+                    .map(|&s| ((s as f64 - 2147483648.0) / 2147483648.0) as f32)
+                    .collect();
+                push(&f32_data);
             },
             err_fn,
             None,
         ),
-        _ => panic!("Unsupported audio sample format: {:?}", sample_format),
+        SampleFormat::I8 => device.build_input_stream(
+            &config,
+            move |data: &[i8], _: &_| {
+                //
+                // Convert i8 samples to f32 before processing.
+                //
+                let f32_data: Vec<f32> = data.iter().map(|&s| (s as f32) / 128.0).collect();
+                push(&f32_data);
+            },
+            err_fn,
+            None,
+        ),
+        SampleFormat::U8 => device.build_input_stream(
+            &config,
+            move |data: &[u8], _: &_| {
+                //
+                // Convert u8 samples to signed f32 before processing.
+                //
+                let f32_data: Vec<f32> = data.iter().map(|&s| (s as f32 - 128.0) / 128.0).collect();
+                push(&f32_data);
+            },
+            err_fn,
+            None,
+        ),
+        _ => return Err(CaptureError::UnsupportedFormat(sample_format)),
     }
-    .expect("Failed to build audio stream");
+    .map_err(CaptureError::Build)?;
 
     //
     // Start audio input stream.
     //
-    stream.play().expect("Failed to start audio stream");
+    stream.play().map_err(CaptureError::Play)?;
+
+    Ok((
+        LiveCapture {
+            stream,
+            sample_rate,
+            buffer_frames,
+            dropped,
+        },
+        consumers,
+    ))
+}
+
+/// Default output device and its mix format for loopback capture. WASAPI
+/// opens an input stream on a render device in loopback mode.
+#[cfg(target_os = "windows")]
+fn loopback_device(
+    host: &cpal::Host,
+) -> Result<(cpal::Device, cpal::SupportedStreamConfig), CaptureError> {
+    let device = host
+        .default_output_device()
+        .ok_or(CaptureError::NoOutputDevice)?;
+    let supported_config = device
+        .default_output_config()
+        .map_err(CaptureError::Config)?;
+    Ok((device, supported_config))
+}
+
+/// Other cpal backends have no loopback mode; on Linux a PulseAudio or
+/// PipeWire monitor source can be selected as an ordinary input instead.
+#[cfg(not(target_os = "windows"))]
+fn loopback_device(
+    host: &cpal::Host,
+) -> Result<(cpal::Device, cpal::SupportedStreamConfig), CaptureError> {
+    Err(CaptureError::LoopbackUnsupported(host.id()))
+}
 
-    (stream, consumer)
+/// Picks a configuration from `ranges` running at `rate`, preferring the
+/// sample format and then the channel count of `default`. Falls back to
+/// `default` if no range covers `rate`.
+fn negotiate_config(
+    ranges: impl IntoIterator<Item = cpal::SupportedStreamConfigRange>,
+    default: cpal::SupportedStreamConfig,
+    rate: u32,
+) -> cpal::SupportedStreamConfig {
+    let best = ranges
+        .into_iter()
+        .filter_map(|range| range.try_with_sample_rate(cpal::SampleRate(rate)))
+        .min_by_key(|config| {
+            (
+                config.sample_format() != default.sample_format(),
+                config.channels() != default.channels(),
+            )
+        });
+    match best {
+        Some(config) => config,
+        None => {
+            log::warn!(
+                "Device does not support {}Hz; using {}Hz",
+                rate,
+                default.sample_rate().0
+            );
+            default
+        }
+    }
+}
+
+/// Fixed buffer of `hint` frames if `supported` covers it, else the device
+/// default. Hosts that do not report a range get the default too, since a
+/// fixed size they cannot honor would fail to open.
+fn choose_buffer_size(supported: &SupportedBufferSize, hint: Option<u32>) -> cpal::BufferSize {
+    let Some(frames) = hint else {
+        return cpal::BufferSize::Default;
+    };
+    match *supported {
+        SupportedBufferSize::Range { min, max } if (min..=max).contains(&frames) => {
+            cpal::BufferSize::Fixed(frames)
+        }
+        SupportedBufferSize::Range { min, max } => {
+            log::warn!(
+                "Device buffers are {}-{} frames, not {}; using its default",
+                min,
+                max,
+                frames
+            );
+            cpal::BufferSize::Default
+        }
+        SupportedBufferSize::Unknown => {
+            log::warn!(
+                "Device does not report buffer sizes; ignoring the {}-frame request",
+                frames
+            );
+            cpal::BufferSize::Default
+        }
+    }
+}
+
+/// Pushes interleaved samples into the ring buffer as mono (downmix if
+/// necessary) and returns how many mono samples did not fit.
+fn push_mono(
+    producer: &mut SampleProducer,
+    data: &[f32],
+    channels: usize,
+    downmix: DownmixMode,
+) -> usize {
+    if channels == 1 {
+        data.len() - producer.push_slice(data)
+    } else if downmix == DownmixMode::AverageAll {
+        let scale = 1.0 / channels as f32;
+        data.chunks_exact(channels)
+            .filter(|chunk| producer.push(chunk.iter().sum::<f32>() * scale).is_err())
+            .count()
+    } else if channels == 2 {
+        //
+        // Downmix stereo to mono with the gain the mode calls for.
+        //
+        let scale = match downmix {
+            DownmixMode::Sum => 1.0,
+            DownmixMode::EnergyPreserving => std::f32::consts::FRAC_1_SQRT_2,
+            _ => 0.5,
+        };
+        data.chunks_exact(2)
+            .filter(|chunk| producer.push((chunk[0] + chunk[1]) * scale).is_err())
+            .count()
+    } else {
+        //
+        // Downmix multi-channel audio by selecting the first channel.
+        //
+        data.chunks_exact(channels)
+            .filter(|chunk| producer.push(chunk[0]).is_err())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cpal::{SampleRate, SupportedStreamConfig, SupportedStreamConfigRange};
+
+    #[test]
+    fn host_names_are_matched_ignoring_case() {
+        assert_eq!(
+            HostBackend::from_name("Default"),
+            Some(HostBackend::Default)
+        );
+        for &id in cpal::ALL_HOSTS {
+            let upper = id.name().to_uppercase();
+            assert_eq!(HostBackend::from_name(&upper), Some(HostBackend::Id(id)));
+        }
+        assert_eq!(HostBackend::from_name("no-such-host"), None);
+    }
+
+    #[test]
+    fn downmix_modes_scale_as_documented() {
+        let mono = |data: &[f32], channels, downmix| {
+            let (mut producer, mut consumer) = HeapRb::<f32>::new(16).split();
+            assert_eq!(push_mono(&mut producer, data, channels, downmix), 0);
+            std::iter::from_fn(|| consumer.pop()).collect::<Vec<f32>>()
+        };
+        let stereo = [0.5, 0.5, 0.4, -0.2];
+        assert_eq!(mono(&stereo, 2, DownmixMode::Average), [0.5, 0.1]);
+        assert_eq!(mono(&stereo, 2, DownmixMode::Sum), [1.0, 0.2]);
+        let energy = mono(&stereo, 2, DownmixMode::EnergyPreserving);
+        assert!((energy[0] - 0.5 * 2f32.sqrt()).abs() < 1e-6);
+        assert_eq!(mono(&stereo, 2, DownmixMode::AverageAll), [0.5, 0.1]);
+
+        let quad = [0.75, 0.5, 0.0, -0.25];
+        assert_eq!(mono(&quad, 4, DownmixMode::Average), [0.75]);
+        assert_eq!(mono(&quad, 4, DownmixMode::AverageAll), [0.25]);
+        assert_eq!(mono(&[0.3, 0.7], 1, DownmixMode::Sum), [0.3, 0.7]);
+        assert_eq!(
+            DownmixMode::from_name("Energy"),
+            Some(DownmixMode::EnergyPreserving)
+        );
+    }
+
+    #[test]
+    fn latency_hint_is_used_only_within_the_supported_range() {
+        let range = SupportedBufferSize::Range { min: 64, max: 4096 };
+        assert_eq!(
+            choose_buffer_size(&range, Some(256)),
+            cpal::BufferSize::Fixed(256)
+        );
+        assert_eq!(
+            choose_buffer_size(&range, Some(32)),
+            cpal::BufferSize::Default
+        );
+        assert_eq!(choose_buffer_size(&range, None), cpal::BufferSize::Default);
+        assert_eq!(
+            choose_buffer_size(&SupportedBufferSize::Unknown, Some(256)),
+            cpal::BufferSize::Default
+        );
+    }
+
+    #[test]
+    fn requested_rate_is_negotiated_against_supported_ranges() {
+        let default = SupportedStreamConfig::new(
+            2,
+            SampleRate(44100),
+            SupportedBufferSize::Unknown,
+            SampleFormat::F32,
+        );
+        let range = |channels, min, max, format| {
+            SupportedStreamConfigRange::new(
+                channels,
+                SampleRate(min),
+                SampleRate(max),
+                SupportedBufferSize::Unknown,
+                format,
+            )
+        };
+        let ranges = [
+            range(1, 8000, 48000, SampleFormat::F32),
+            range(2, 8000, 48000, SampleFormat::I16),
+            range(2, 44100, 96000, SampleFormat::F32),
+        ];
+
+        let config = negotiate_config(ranges, default.clone(), 48000);
+        assert_eq!(config.sample_rate(), SampleRate(48000));
+        assert_eq!(config.sample_format(), SampleFormat::F32);
+        assert_eq!(config.channels(), 2);
+
+        let config = negotiate_config(ranges, default.clone(), 16000);
+        assert_eq!(config.sample_rate(), SampleRate(16000));
+        assert_eq!(
+            (config.sample_format(), config.channels()),
+            (SampleFormat::F32, 1)
+        );
+
+        assert_eq!(negotiate_config(ranges, default.clone(), 192000), default);
+    }
 }
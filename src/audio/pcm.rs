@@ -0,0 +1,428 @@
+use super::{ChannelMode, SampleConsumer, RING_BUFFER_MULTIPLIER};
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+//
+// Bytes requested from the input per read, and how long the reader waits
+// for a full ring buffer to drain before looking again.
+//
+const READ_BLOCK: usize = 16 * 1024;
+const FULL_WAIT: Duration = Duration::from_millis(2);
+
+//
+// Decoded input is released in blocks of this duration.
+//
+const TICK: Duration = Duration::from_millis(10);
+
+/// Encoding of headerless PCM input, as named by sox and ffmpeg.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PcmFormat {
+    F32Le,
+    F32Be,
+    S16Le,
+    S16Be,
+    S24Le,
+    S32Le,
+    /// Unsigned 8-bit, centered on 128.
+    U8,
+}
+
+impl PcmFormat {
+    pub const ALL: [PcmFormat; 7] = [
+        PcmFormat::F32Le,
+        PcmFormat::F32Be,
+        PcmFormat::S16Le,
+        PcmFormat::S16Be,
+        PcmFormat::S24Le,
+        PcmFormat::S32Le,
+        PcmFormat::U8,
+    ];
+
+    /// Short name as accepted by `from_name`.
+    pub fn name(self) -> &'static str {
+        match self {
+            PcmFormat::F32Le => "f32le",
+            PcmFormat::F32Be => "f32be",
+            PcmFormat::S16Le => "s16le",
+            PcmFormat::S16Be => "s16be",
+            PcmFormat::S24Le => "s24le",
+            PcmFormat::S32Le => "s32le",
+            PcmFormat::U8 => "u8",
+        }
+    }
+
+    /// Looks up a format by `name()`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn bytes_per_sample(self) -> usize {
+        match self {
+            PcmFormat::F32Le | PcmFormat::F32Be | PcmFormat::S32Le => 4,
+            PcmFormat::S24Le => 3,
+            PcmFormat::S16Le | PcmFormat::S16Be => 2,
+            PcmFormat::U8 => 1,
+        }
+    }
+
+    /// Decodes one sample from its `bytes_per_sample` bytes, scaled to
+    /// full scale at 1.0 like the WAV source.
+    fn decode(self, b: &[u8]) -> f32 {
+        match self {
+            PcmFormat::F32Le => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            PcmFormat::F32Be => f32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+            PcmFormat::S16Le => i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+            PcmFormat::S16Be => i16::from_be_bytes([b[0], b[1]]) as f32 / 32768.0,
+            PcmFormat::S24Le => {
+                //
+                // Place the 24 bits at the top of an i32 to sign-extend.
+                //
+                (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0
+            }
+            PcmFormat::S32Le => {
+                (i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64 / 2_147_483_648.0) as f32
+            }
+            PcmFormat::U8 => (b[0] as f32 - 128.0) / 128.0,
+        }
+    }
+}
+
+/// Reads raw PCM from a pipe or other byte stream into the sample ring
+/// buffers on a background thread.
+///
+/// Samples are released no faster than `sample_rate`, so input that
+/// arrives faster than real time, such as a file piped through sox, plays
+/// at its own speed as a WAV file does, rather than flashing past in
+/// bursts. Unlike the file and capture sources it never drops input: when
+/// a ring buffer is full the thread waits, which stalls the writer at the
+/// other end of the pipe. The thread ends at end of input, or at its next
+/// read once the source is dropped; a read blocked on an idle pipe keeps
+/// it alive until then.
+pub struct PcmSource {
+    sample_rate: u32,
+    stop: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+}
+
+impl PcmSource {
+    /// Rate given for the input; raw PCM carries none of its own.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// True once the input has ended or failed and every whole frame read
+    /// has been pushed.
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for PcmSource {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+    }
+}
+
+/// Streams interleaved `format` samples with `channels` channels from
+/// standard input, split into the streams chosen by `mode`, e.g.
+/// `arecord -f FLOAT_LE -r 48000 -t raw | fftanalyzer --raw f32le --rate 48000`.
+pub fn start_stdin_source(
+    format: PcmFormat,
+    sample_rate: u32,
+    channels: usize,
+    mode: ChannelMode,
+    buffer_size: usize,
+) -> (PcmSource, Vec<SampleConsumer>) {
+    start_pcm_source(
+        io::stdin(),
+        format,
+        sample_rate,
+        channels,
+        mode,
+        buffer_size,
+    )
+}
+
+/// Like `start_stdin_source`, but reads from any byte stream.
+pub fn start_pcm_source(
+    mut reader: impl Read + Send + 'static,
+    format: PcmFormat,
+    sample_rate: u32,
+    channels: usize,
+    mode: ChannelMode,
+    buffer_size: usize,
+) -> (PcmSource, Vec<SampleConsumer>) {
+    let channels = channels.max(1);
+    let capacity = buffer_size * RING_BUFFER_MULTIPLIER;
+    let (mut producers, consumers) = mode.ring_buffers(capacity);
+    let stop = Arc::new(AtomicBool::new(false));
+    let finished = Arc::new(AtomicBool::new(false));
+
+    {
+        let stop = stop.clone();
+        let finished = finished.clone();
+        thread::spawn(move || {
+            let mut decoder = PcmDecoder::new(format, channels);
+            let mut block = vec![0; READ_BLOCK];
+            let tick_frames =
+                ((sample_rate as f64 * TICK.as_secs_f64()) as usize).clamp(1, capacity);
+
+            //
+            // Each block is due once its last frame would have been
+            // captured live, counted from the first read so sleep jitter
+            // does not accumulate into drift.
+            //
+            let mut started = None;
+            let mut released = 0u64;
+            loop {
+                let read = match reader.read(&mut block) {
+                    Ok(0) => break,
+                    Ok(read) => read,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => {
+                        log::warn!("PCM input failed: {}", err);
+                        break;
+                    }
+                };
+
+                //
+                // Push a tick's worth at a time, each once it is due and
+                // every stream has room for all of it.
+                //
+                let started = *started.get_or_insert_with(Instant::now);
+                for chunk in decoder
+                    .decode(&block[..read])
+                    .chunks(tick_frames * channels)
+                {
+                    let frames = chunk.len() / channels;
+                    released += frames as u64;
+                    let due = started
+                        + Duration::from_secs_f64(released as f64 / sample_rate.max(1) as f64);
+                    if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                        thread::sleep(wait);
+                    }
+                    while producers.iter().any(|p| p.free_len() < frames) {
+                        if stop.load(Ordering::Acquire) {
+                            return;
+                        }
+                        thread::sleep(FULL_WAIT);
+                    }
+                    mode.push(&mut producers, chunk, channels);
+                }
+                if stop.load(Ordering::Acquire) {
+                    return;
+                }
+            }
+            finished.store(true, Ordering::Release);
+        });
+    }
+
+    log::info!(
+        "PCM source: {} @ {}Hz, Channels: {}",
+        format.name(),
+        sample_rate,
+        channels
+    );
+    (
+        PcmSource {
+            sample_rate,
+            stop,
+            finished,
+        },
+        consumers,
+    )
+}
+
+/// Reads `reader` to its end and puts every sample into the ring buffers
+/// at once, for offline analysis, as `read_file_source` does for WAV.
+pub fn read_pcm_source(
+    mut reader: impl Read,
+    format: PcmFormat,
+    channels: usize,
+    mode: ChannelMode,
+) -> io::Result<Vec<SampleConsumer>> {
+    let channels = channels.max(1);
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let samples = PcmDecoder::new(format, channels).decode(&bytes).to_vec();
+    let (mut producers, consumers) = mode.ring_buffers((samples.len() / channels).max(1));
+    mode.push(&mut producers, &samples, channels);
+    Ok(consumers)
+}
+
+/// Turns byte blocks of arbitrary length into whole interleaved frames,
+/// carrying a partial frame over to the next block.
+struct PcmDecoder {
+    format: PcmFormat,
+    frame_bytes: usize,
+    pending: Vec<u8>,
+    samples: Vec<f32>,
+}
+
+impl PcmDecoder {
+    fn new(format: PcmFormat, channels: usize) -> Self {
+        Self {
+            format,
+            frame_bytes: format.bytes_per_sample() * channels,
+            pending: Vec::new(),
+            samples: Vec::new(),
+        }
+    }
+
+    /// Decodes the whole frames available after appending `bytes`.
+    fn decode(&mut self, bytes: &[u8]) -> &[f32] {
+        self.pending.extend_from_slice(bytes);
+        let whole = self.pending.len() / self.frame_bytes * self.frame_bytes;
+        self.samples.clear();
+        self.samples.extend(
+            self.pending[..whole]
+                .chunks_exact(self.format.bytes_per_sample())
+                .map(|b| self.format.decode(b)),
+        );
+        self.pending.drain(..whole);
+        &self.samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::time::Instant;
+
+    /// Hands out at most `step` bytes per read, so frames straddle reads.
+    struct Trickle {
+        data: Cursor<Vec<u8>>,
+        step: usize,
+    }
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.step);
+            self.data.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn every_format_decodes_to_full_scale() {
+        let cases: [(PcmFormat, Vec<u8>, f32); 7] = [
+            (PcmFormat::F32Le, 0.5f32.to_le_bytes().to_vec(), 0.5),
+            (PcmFormat::F32Be, (-0.25f32).to_be_bytes().to_vec(), -0.25),
+            (PcmFormat::S16Le, (-16384i16).to_le_bytes().to_vec(), -0.5),
+            (PcmFormat::S16Be, 8192i16.to_be_bytes().to_vec(), 0.25),
+            (PcmFormat::S24Le, vec![0x00, 0x00, 0xc0], -0.5),
+            (
+                PcmFormat::S32Le,
+                (i32::MIN / 4).to_le_bytes().to_vec(),
+                -0.25,
+            ),
+            (PcmFormat::U8, vec![192], 0.5),
+        ];
+        for (format, bytes, expected) in cases {
+            assert_eq!(bytes.len(), format.bytes_per_sample());
+            assert_eq!(format.decode(&bytes), expected, "{}", format.name());
+            assert_eq!(
+                PcmFormat::from_name(&format.name().to_uppercase()),
+                Some(format)
+            );
+        }
+        assert_eq!(PcmFormat::from_name("wav"), None);
+    }
+
+    #[test]
+    fn frames_split_across_reads_reach_each_stream() {
+        //
+        // 300 stereo s16le frames, 3 bytes per read so no read holds a
+        // whole frame boundary.
+        //
+        let mut bytes = Vec::new();
+        for _ in 0..300 {
+            bytes.extend_from_slice(&16384i16.to_le_bytes());
+            bytes.extend_from_slice(&(-8192i16).to_le_bytes());
+        }
+        let reader = Trickle {
+            data: Cursor::new(bytes),
+            step: 3,
+        };
+        let (source, mut consumers) =
+            start_pcm_source(reader, PcmFormat::S16Le, 8000, 2, ChannelMode::Stereo, 1024);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !source.is_finished() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(source.sample_rate(), 8000);
+        for (consumer, level) in consumers.iter_mut().zip([0.5, -0.25]) {
+            let samples: Vec<f32> = std::iter::from_fn(|| consumer.pop()).collect();
+            assert_eq!(samples.len(), 300);
+            assert!(samples.iter().all(|&s| s == level));
+        }
+    }
+
+    #[test]
+    fn full_buffers_hold_the_reader_back_instead_of_dropping() {
+        //
+        // Ten times what the ring holds, drained slowly.
+        //
+        let total = 10 * 16 * RING_BUFFER_MULTIPLIER;
+        let bytes: Vec<u8> = (0..total).flat_map(|i| (i as f32).to_le_bytes()).collect();
+        let (source, mut consumers) = start_pcm_source(
+            Cursor::new(bytes),
+            PcmFormat::F32Le,
+            8000,
+            1,
+            ChannelMode::Mono,
+            16,
+        );
+
+        let mut received = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while received.len() < total {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(1));
+            received.extend(std::iter::from_fn(|| consumers[0].pop()));
+        }
+        assert!(source.is_finished());
+        assert!(received.iter().enumerate().all(|(i, &s)| s == i as f32));
+    }
+
+    #[test]
+    fn input_is_released_at_the_sample_rate() {
+        //
+        // 800 frames at 8 kHz take 100 ms to release, however fast they
+        // can be read.
+        //
+        let bytes: Vec<u8> = [128u8].repeat(800);
+        let started = Instant::now();
+        let (source, _consumers) = start_pcm_source(
+            Cursor::new(bytes),
+            PcmFormat::U8,
+            8000,
+            1,
+            ChannelMode::Mono,
+            1024,
+        );
+        let deadline = started + Duration::from_secs(5);
+        while !source.is_finished() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(started.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[test]
+    fn reading_decodes_the_whole_input_at_once() {
+        let bytes: Vec<u8> = [64u8, 128, 192, 0].repeat(1000);
+        let mut consumers =
+            read_pcm_source(Cursor::new(bytes), PcmFormat::U8, 1, ChannelMode::Mono).unwrap();
+        let samples: Vec<f32> = std::iter::from_fn(|| consumers[0].pop()).collect();
+        assert_eq!(samples.len(), 4000);
+        assert_eq!(samples[..4], [-0.5, 0.0, 0.5, -1.0]);
+    }
+}
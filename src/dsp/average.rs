@@ -0,0 +1,141 @@
+use std::collections::VecDeque;
+
+/// How successive spectra are combined before display.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AveragingMode {
+    None,
+    /// Exponential moving average; `alpha` is the weight of the newest frame.
+    Exponential(f32),
+    /// Plain mean over the most recent `count` frames.
+    LinearN(usize),
+}
+
+/// Smooths per-bin power across FFT frames.
+///
+/// Averaging is done on linear power rather than dB so that a steady tone
+/// keeps its level and only the noise floor tightens.
+pub struct SpectrumAverager {
+    mode: AveragingMode,
+
+    //
+    // Running state for `Exponential`.
+    //
+    ema: Vec<f32>,
+
+    //
+    // Recent frames and their per-bin sum for `LinearN`.
+    //
+    frames: VecDeque<Vec<f32>>,
+    sum: Vec<f32>,
+}
+
+impl SpectrumAverager {
+    pub fn new(mode: AveragingMode) -> Self {
+        Self {
+            mode,
+            ema: Vec::new(),
+            frames: VecDeque::new(),
+            sum: Vec::new(),
+        }
+    }
+
+    pub fn mode(&self) -> AveragingMode {
+        self.mode
+    }
+
+    /// Switches mode, discarding any history kept for the previous one.
+    pub fn set_mode(&mut self, mode: AveragingMode) {
+        if mode != self.mode {
+            self.mode = mode;
+            self.reset();
+        }
+    }
+
+    /// Forgets all previous frames.
+    pub fn reset(&mut self) {
+        self.ema.clear();
+        self.frames.clear();
+        self.sum.clear();
+    }
+
+    /// Folds `power` into the running average and overwrites it with the
+    /// averaged spectrum. A change in length restarts the average.
+    pub fn apply(&mut self, power: &mut [f32]) {
+        match self.mode {
+            AveragingMode::None => {}
+            AveragingMode::Exponential(alpha) => {
+                let alpha = alpha.clamp(0.0, 1.0);
+                if self.ema.len() != power.len() {
+                    self.ema = power.to_vec();
+                    return;
+                }
+                for (avg, p) in self.ema.iter_mut().zip(power.iter_mut()) {
+                    *avg += alpha * (*p - *avg);
+                    *p = *avg;
+                }
+            }
+            AveragingMode::LinearN(count) => {
+                let count = count.max(1);
+                if self.sum.len() != power.len() {
+                    self.frames.clear();
+                    self.sum = vec![0.0; power.len()];
+                }
+
+                //
+                // Slide the window: drop the oldest frames, add the newest.
+                // Once the window is full, the dropped frame's storage is
+                // reused for the new one.
+                //
+                let mut recycled = None;
+                while self.frames.len() >= count {
+                    if let Some(old) = self.frames.pop_front() {
+                        for (s, o) in self.sum.iter_mut().zip(&old) {
+                            *s -= o;
+                        }
+                        recycled = Some(old);
+                    }
+                }
+                for (s, &p) in self.sum.iter_mut().zip(power.iter()) {
+                    *s += p;
+                }
+                let mut frame = recycled.unwrap_or_default();
+                frame.clear();
+                frame.extend_from_slice(power);
+                self.frames.push_back(frame);
+
+                let scale = 1.0 / self.frames.len() as f32;
+                for (p, &s) in power.iter_mut().zip(&self.sum) {
+                    //
+                    // Guard against rounding leaving a tiny negative sum.
+                    //
+                    *p = (s * scale).max(0.0);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_and_exponential_converge_on_a_steady_spectrum() {
+        let frames = [[1.0, 4.0], [3.0, 0.0], [5.0, 2.0]];
+
+        let mut linear = SpectrumAverager::new(AveragingMode::LinearN(2));
+        let mut out = [0.0; 2];
+        for frame in frames {
+            out = frame;
+            linear.apply(&mut out);
+        }
+        assert_eq!(out, [4.0, 1.0]);
+
+        let mut ema = SpectrumAverager::new(AveragingMode::Exponential(0.5));
+        for _ in 0..64 {
+            out = [2.0, 8.0];
+            ema.apply(&mut out);
+        }
+        assert!((out[0] - 2.0).abs() < 1e-6 && (out[1] - 8.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,285 @@
+use crate::fft::hz_to_bin;
+use std::io::{self, Write};
+use std::ops::Range;
+
+/// Fractional-octave band width.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BandResolution {
+    Octave,
+    ThirdOctave,
+}
+
+//
+// ISO 266 nominal center frequencies.
+//
+const OCTAVE_NOMINAL: [f32; 10] = [
+    31.5, 63.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
+];
+const THIRD_OCTAVE_NOMINAL: [f32; 30] = [
+    25.0, 31.5, 40.0, 50.0, 63.0, 80.0, 100.0, 125.0, 160.0, 200.0, 250.0, 315.0, 400.0, 500.0,
+    630.0, 800.0, 1000.0, 1250.0, 1600.0, 2000.0, 2500.0, 3150.0, 4000.0, 5000.0, 6300.0, 8000.0,
+    10000.0, 12500.0, 16000.0, 20000.0,
+];
+
+/// One fractional-octave band and the FFT bins assigned to it.
+#[derive(Clone, Debug)]
+pub struct Band {
+    pub nominal_hz: f32,
+    pub center_hz: f32,
+    pub lower_hz: f32,
+    pub upper_hz: f32,
+    pub bins: Range<usize>,
+}
+
+/// Groups FFT bins into ISO octave or third-octave bands.
+///
+/// Exact centers use the base-10 series `1000 * 10^(k/10)` (third-octave)
+/// or `1000 * 10^(3k/10)` (octave). A bin belongs to the band whose
+/// `[lower, upper)` edge range contains its center frequency. Bands above
+/// Nyquist or too narrow to hold any bin at this FFT size are omitted.
+pub struct BandAnalyzer {
+    resolution: BandResolution,
+    sample_rate: u32,
+    n: usize,
+    bands: Vec<Band>,
+}
+
+impl BandAnalyzer {
+    pub fn new(resolution: BandResolution, sample_rate: u32, n: usize) -> Self {
+        let (nominal, first_k, step): (&[f32], i32, f32) = match resolution {
+            BandResolution::Octave => (&OCTAVE_NOMINAL, -5, 3.0),
+            BandResolution::ThirdOctave => (&THIRD_OCTAVE_NOMINAL, -16, 1.0),
+        };
+
+        let bin_count = n / 2 + 1;
+        let mut bands = Vec::with_capacity(nominal.len());
+
+        for (i, &nominal_hz) in nominal.iter().enumerate() {
+            let k = first_k + i as i32;
+            let center_hz = 1000.0 * 10f32.powf(step * k as f32 / 10.0);
+            let half_width = 10f32.powf(step / 20.0);
+            let lower_hz = center_hz / half_width;
+            let upper_hz = center_hz * half_width;
+
+            //
+            // First bin at or above the lower edge, first bin at or above the upper edge.
+            //
+            let start = (hz_to_bin(lower_hz, sample_rate, n).ceil() as usize).min(bin_count);
+            let end = (hz_to_bin(upper_hz, sample_rate, n).ceil() as usize).min(bin_count);
+            if start >= end {
+                continue;
+            }
+
+            bands.push(Band {
+                nominal_hz,
+                center_hz,
+                lower_hz,
+                upper_hz,
+                bins: start..end,
+            });
+        }
+
+        Self {
+            resolution,
+            sample_rate,
+            n,
+            bands,
+        }
+    }
+
+    pub fn resolution(&self) -> BandResolution {
+        self.resolution
+    }
+
+    /// Sample rate and FFT size the bins were assigned for.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn dft_size(&self) -> usize {
+        self.n
+    }
+
+    pub fn bands(&self) -> &[Band] {
+        &self.bands
+    }
+
+    /// Sums per-bin power within each band. Bins beyond the end of `power`
+    /// are ignored.
+    pub fn powers(&self, power: &[f32]) -> Vec<f32> {
+        self.bands
+            .iter()
+            .map(|band| {
+                let end = band.bins.end.min(power.len());
+                let start = band.bins.start.min(end);
+                power[start..end].iter().sum()
+            })
+            .collect()
+    }
+
+    /// Band power as the RTA view shows it: each bin weighted by its
+    /// `weighting_db` gain (0 dB past the end of it) and each sum divided
+    /// by the window's ENBW in bins, so a tone inside a band reads at its
+    /// own level however many bins the window spreads it over.
+    pub fn calibrated_powers(
+        &self,
+        power: &[f32],
+        weighting_db: &[f32],
+        enbw_bins: f32,
+    ) -> Vec<f32> {
+        let weighted: Vec<f32> = power
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| {
+                let gain = weighting_db.get(i).copied().unwrap_or(0.0);
+                p * 10f32.powf(gain / 10.0) / enbw_bins
+            })
+            .collect();
+        self.powers(&weighted)
+    }
+
+    /// `calibrated_powers` in dB, as exported and logged.
+    pub fn levels_db(&self, power: &[f32], weighting_db: &[f32], enbw_bins: f32) -> Vec<f32> {
+        self.calibrated_powers(power, weighting_db, enbw_bins)
+            .into_iter()
+            .map(|sum| 10.0 * sum.max(1e-18).log10())
+            .collect()
+    }
+}
+
+/// Writes band levels as CSV with `center_hz,level_db` columns,
+/// using the nominal ISO center frequencies.
+pub fn write_csv<W: Write>(mut writer: W, bands: &[Band], levels_db: &[f32]) -> io::Result<()> {
+    writeln!(writer, "center_hz,level_db")?;
+    for (band, level) in bands.iter().zip(levels_db) {
+        writeln!(writer, "{},{:.2}", band.nominal_hz, level)?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::engine::SpectrumEngine;
+    use crate::dsp::weighting::Weighting;
+    use ringbuf::HeapRb;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn exact_centers_follow_the_nominal_series() {
+        for (resolution, count, ratio) in [
+            (BandResolution::Octave, 10, 10f32.powf(0.3)),
+            (BandResolution::ThirdOctave, 30, 10f32.powf(0.1)),
+        ] {
+            //
+            // A rate and size fine enough to keep every band down to 25 Hz.
+            //
+            let analyzer = BandAnalyzer::new(resolution, 48000, 1 << 16);
+            let bands = analyzer.bands();
+            assert_eq!(bands.len(), count);
+            for band in bands {
+                assert!((band.center_hz / band.nominal_hz - 1.0).abs() < 0.02);
+                assert!((band.upper_hz / band.lower_hz - ratio).abs() < 1e-3);
+            }
+            for pair in bands.windows(2) {
+                assert!((pair[0].upper_hz / pair[1].lower_hz - 1.0).abs() < 1e-4);
+                assert_eq!(pair[0].bins.end, pair[1].bins.start);
+            }
+            let kilo = bands.iter().find(|b| b.nominal_hz == 1000.0).unwrap();
+            assert!((kilo.center_hz - 1000.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn bins_go_to_the_band_containing_their_center() {
+        //
+        // 10 Hz bins. The 1 kHz third-octave band spans 891.3 Hz to
+        // 1122.0 Hz, so it starts at the 900 Hz bin and ends at 1120 Hz.
+        //
+        let analyzer = BandAnalyzer::new(BandResolution::ThirdOctave, 48000, 4800);
+        let band_of = |bin: usize| {
+            analyzer
+                .bands()
+                .iter()
+                .find(|b| b.bins.contains(&bin))
+                .map(|b| b.nominal_hz)
+        };
+        assert_eq!(band_of(89), Some(800.0));
+        assert_eq!(band_of(90), Some(1000.0));
+        assert_eq!(band_of(112), Some(1000.0));
+        assert_eq!(band_of(113), Some(1250.0));
+
+        //
+        // Bands narrower than a bin at the bottom are left out, as are
+        // bands starting above Nyquist.
+        //
+        let analyzer = BandAnalyzer::new(BandResolution::ThirdOctave, 8000, 256);
+        let bands = analyzer.bands();
+        assert!(bands.iter().all(|b| !b.bins.is_empty()));
+        assert!(bands[0].nominal_hz > 25.0);
+        assert!(bands.iter().all(|b| b.lower_hz < 4000.0));
+        assert_eq!(bands.last().unwrap().bins.end, 129);
+    }
+
+    #[test]
+    fn band_power_sums_its_bins() {
+        let analyzer = BandAnalyzer::new(BandResolution::Octave, 48000, 4800);
+        let power = vec![1e-3; 2401];
+        for (band, (&sum, &db)) in
+            analyzer
+                .bands()
+                .iter()
+                .zip(
+                    analyzer
+                        .powers(&power)
+                        .iter()
+                        .zip(&analyzer.levels_db(&power, &[], 1.0)),
+                )
+        {
+            let expected = band.bins.len() as f32 * 1e-3;
+            assert!((sum - expected).abs() < 1e-6 * band.bins.len() as f32);
+            assert!((db - 10.0 * expected.log10()).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn calibrated_levels_read_a_tone_at_its_own_level() {
+        //
+        // A full-scale 100 Hz sine on bin 10 of 10 Hz bins, through the
+        // engine's Hann window. The window spreads it over bins 9..=11,
+        // all inside the 100 Hz third-octave band, and the ENBW divides
+        // that spread back out; A weighting then takes its 100 Hz gain.
+        // The view draws `calibrated_powers`, which exports as `levels_db`.
+        //
+        let (rate, n) = (48000, 4800);
+        let (mut producer, consumer) = HeapRb::<f32>::new(2 * n).split();
+        let mut engine = SpectrumEngine::new(consumer, rate, n);
+        for i in 0..n {
+            let _ = producer.push((2.0 * PI * 100.0 * i as f32 / rate as f32).sin());
+        }
+        engine.process(|_| {});
+
+        let analyzer = BandAnalyzer::new(BandResolution::ThirdOctave, rate, n);
+        let band = analyzer
+            .bands()
+            .iter()
+            .position(|b| b.nominal_hz == 100.0)
+            .unwrap();
+        let enbw_bins = engine.enbw_hz() / engine.bin_width_hz();
+        for weighting in [Weighting::None, Weighting::A] {
+            engine.set_weighting(weighting);
+            let levels = analyzer.levels_db(engine.power(), engine.weighting_db(), enbw_bins);
+            let shown =
+                analyzer.calibrated_powers(engine.power(), engine.weighting_db(), enbw_bins);
+            assert_eq!(levels[band], 10.0 * shown[band].log10());
+            let expected = weighting.gain_db(100.0);
+            assert!(
+                (levels[band] - expected).abs() < 0.3,
+                "{:?}: {} dB, expected {} dB",
+                weighting,
+                levels[band],
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,1132 @@
+use super::average::{AveragingMode, SpectrumAverager};
+use super::goertzel::Goertzel;
+use super::meter::LevelMeter;
+use super::noise_floor::NoiseFloor;
+use super::peak::interpolate_peak;
+use super::silence::SilenceDetector;
+use super::weighting::Weighting;
+use super::window::{WindowFunction, WindowGains};
+use crate::audio::SampleConsumer;
+use crate::fft::{self, find_rdft, RDFTBase};
+use num_complex::Complex32;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Release time constant of the input level meter.
+const METER_RELEASE_SECS: f32 = 0.3;
+
+// How fast the noise floor behind `DspStats::noise_floor_db` may rise, the
+// same as the GUI's noise floor display.
+const STATS_FLOOR_RISE_DB_PER_SEC: f32 = 6.0;
+
+/// Input totals since the last `take_input_stats` call. More totals may be
+/// added, so it is only built by the engine.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct InputStats {
+    pub samples: usize,
+    pub peak: f32,
+    /// Frames computed from those samples.
+    pub frames: usize,
+}
+
+/// The engine's user-facing settings in one value, from
+/// `SpectrumEngine::settings`, so a copy can be edited away from the engine
+/// and handed back to `apply_settings`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct EngineSettings {
+    pub dft_size: usize,
+    pub hop: usize,
+    pub window_function: WindowFunction,
+    pub weighting: Weighting,
+    pub single_sided: bool,
+    pub low_cut_hz: Option<f32>,
+    pub averaging: AveragingMode,
+    pub input_gain_db: f32,
+    /// `SilenceDetector::enter_db`, `exit_db` and `timeout`.
+    pub silence_enter_db: f32,
+    pub silence_exit_db: f32,
+    pub silence_timeout: Duration,
+}
+
+/// Processing statistics over one reporting interval, from
+/// `SpectrumEngine::take_dsp_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DspStats {
+    pub samples_per_sec: f32,
+    pub frames_per_sec: f32,
+    /// Largest absolute input sample, as `InputStats::peak`.
+    pub max_peak: f32,
+    /// Median of the first channel's per-bin `NoiseFloor`, in dB of
+    /// `power`: a broadband noise floor that tones barely move.
+    pub noise_floor_db: f32,
+    /// Samples the input lost during the interval. The engine cannot see
+    /// these; it reports 0 for the caller to fill in from its capture.
+    pub dropped_samples: usize,
+    pub silent: bool,
+}
+
+/// Per-channel input history and spectrum.
+struct Channel {
+    consumer: SampleConsumer,
+
+    //
+    // Sliding input window.
+    //
+    time_domain_buf: VecDeque<f32>,
+    averager: SpectrumAverager,
+
+    //
+    // Latest frame: per-bin power, averaged, and per-bin phase, not.
+    //
+    power: Vec<f32>,
+    phase: Vec<f32>,
+}
+
+impl Channel {
+    fn new(consumer: SampleConsumer, dft_size: usize) -> Self {
+        Self {
+            consumer,
+            time_domain_buf: VecDeque::from(vec![0.0; dft_size]),
+            averager: SpectrumAverager::new(AveragingMode::None),
+            power: vec![0.0; dft_size / 2],
+            phase: vec![0.0; dft_size / 2],
+        }
+    }
+}
+
+/// Turns one or more sample streams into a sequence of power spectra.
+///
+/// The engine owns the ring-buffer consumers, the analysis window and the
+/// real FFT plan. It runs one frame every `hop` samples (50% overlap by
+/// default), so no input is skipped however rarely it is polled, and
+/// exposes each frame's `dft_size / 2` bins of per-bin power after
+/// windowing, optional single-sided scaling and frame averaging. Power is relative to full
+/// scale: with single-sided scaling (the default), a full-scale sinusoid
+/// centered on a bin reads 1.0 there, i.e. 0 dBFS. It has no GUI
+/// dependency and can be driven headlessly.
+///
+/// With several channels every frame transforms all of them over the same
+/// span of samples; `power` is the first channel and `channel_power` the
+/// others. Each channel keeps its own `dft_size` history, spectrum and
+/// averaging state, so memory grows linearly with the channel count.
+pub struct SpectrumEngine {
+    channels: Vec<Channel>,
+    plan: Arc<dyn RDFTBase>,
+    sample_rate: u32,
+
+    //
+    // Frame size, samples between frames, and the count of samples since
+    // the last frame.
+    //
+    dft_size: usize,
+    hop: usize,
+    samples_since_frame: usize,
+
+    //
+    // Input trim in dB and as a linear factor on every ingested sample.
+    //
+    input_gain_db: f32,
+    input_gain: f32,
+
+    window_fn: WindowFunction,
+    window: Vec<f32>,
+    single_sided: bool,
+
+    //
+    // Coherent gain and ENBW of `window`, measured from its coefficients
+    // whenever they change; `amplitude_scale` derives from them.
+    //
+    window_gains: WindowGains,
+
+    //
+    // Windowed input and its half-spectrum, shared scratch reused by every
+    // channel and frame.
+    //
+    windowed: Vec<f32>,
+    spectrum: Vec<Complex32>,
+
+    //
+    // Silence detection over each drained batch of input.
+    //
+    silence: SilenceDetector,
+    batch_peak: f32,
+    stats: InputStats,
+
+    //
+    // Noise floor of the first channel, followed on every frame.
+    //
+    noise_floor: NoiseFloor,
+
+    //
+    // Per-sample input level, independent of the periodic stats reset.
+    //
+    meter: LevelMeter,
+
+    //
+    // Target tones measured on every frame of the first channel, and
+    // their power in the units of `power`.
+    //
+    tones: Option<Goertzel>,
+    tone_power: Vec<f32>,
+
+    //
+    // Display weighting and its per-bin gain in dB, rebuilt with the FFT
+    // size.
+    //
+    weighting: Weighting,
+    weighting_db: Vec<f32>,
+
+    //
+    // Cutoff below which bins are zeroed, and the count of bins it covers
+    // at the current FFT size.
+    //
+    low_cut_hz: Option<f32>,
+    low_cut_bins: usize,
+}
+
+impl SpectrumEngine {
+    pub fn new(consumer: SampleConsumer, sample_rate: u32, dft_size: usize) -> Self {
+        Self::with_channels(vec![consumer], sample_rate, dft_size)
+    }
+
+    /// Analyzes each consumer as a separate channel, in order.
+    pub fn with_channels(
+        consumers: Vec<SampleConsumer>,
+        sample_rate: u32,
+        dft_size: usize,
+    ) -> Self {
+        assert!(
+            !consumers.is_empty(),
+            "SpectrumEngine needs at least one channel"
+        );
+        check_dft_size(dft_size);
+        let window = WindowFunction::Hann.coefficients(dft_size);
+        Self {
+            channels: consumers
+                .into_iter()
+                .map(|consumer| Channel::new(consumer, dft_size))
+                .collect(),
+            plan: find_rdft(dft_size),
+            sample_rate,
+            dft_size,
+            hop: dft_size / 2,
+            samples_since_frame: 0,
+            input_gain_db: 0.0,
+            input_gain: 1.0,
+            window_fn: WindowFunction::Hann,
+            window_gains: WindowGains::of(&window),
+            window,
+            single_sided: true,
+            windowed: vec![0.0; dft_size],
+            spectrum: vec![Complex32::default(); dft_size / 2 + 1],
+            silence: SilenceDetector::new(),
+            batch_peak: 0.0,
+            stats: InputStats::default(),
+            noise_floor: NoiseFloor::new(STATS_FLOOR_RISE_DB_PER_SEC),
+            meter: LevelMeter::new(sample_rate, METER_RELEASE_SECS),
+            tones: None,
+            tone_power: Vec::new(),
+            weighting: Weighting::None,
+            weighting_db: Weighting::None.bin_gains_db(sample_rate, dft_size),
+            low_cut_hz: None,
+            low_cut_bins: 0,
+        }
+    }
+
+    /// Consumes input until the next frame completes, returning `true` with
+    /// `power` updated. Returns `false` once the ring buffers are drained,
+    /// after updating silence detection for the drained batch. Channels
+    /// advance in lockstep, one sample each, while every ring has input.
+    pub fn next_frame(&mut self) -> bool {
+        while self.ingest() {
+            self.samples_since_frame += 1;
+            if self.samples_since_frame >= self.hop() {
+                self.samples_since_frame = 0;
+                self.transform();
+                let dt = self.hop() as f32 / self.sample_rate as f32;
+                self.noise_floor.update(&self.channels[0].power, dt);
+                self.stats.frames += 1;
+                return true;
+            }
+        }
+        self.end_batch();
+        false
+    }
+
+    /// Drains all pending input without computing any frames, e.g. while
+    /// the display is frozen. The sample history, meter and silence state
+    /// stay current, so the first frame after resuming holds only live
+    /// input and the latest `power` is left untouched until then.
+    pub fn skip_input(&mut self) {
+        while self.ingest() {}
+        self.end_batch();
+    }
+
+    /// Moves one sample from every channel's ring into its history, or
+    /// returns `false` if any ring is empty. The meter sees the loudest
+    /// channel once per sample period, so its release runs at the sample
+    /// rate whatever the channel count.
+    fn ingest(&mut self) -> bool {
+        if self.channels.iter().any(|c| c.consumer.is_empty()) {
+            return false;
+        }
+        let mut tick_peak = 0.0f32;
+        for channel in &mut self.channels {
+            let Some(sample) = channel.consumer.pop() else {
+                continue;
+            };
+            let sample = sample * self.input_gain;
+            channel.time_domain_buf.pop_front();
+            channel.time_domain_buf.push_back(sample);
+
+            let abs_sample = sample.abs();
+            self.stats.peak = self.stats.peak.max(abs_sample);
+            self.batch_peak = self.batch_peak.max(abs_sample);
+            tick_peak = tick_peak.max(abs_sample);
+        }
+        self.meter.update(tick_peak);
+        self.stats.samples += 1;
+        true
+    }
+
+    /// Silence detection with separate enter/exit thresholds, once per
+    /// drained batch.
+    fn end_batch(&mut self) {
+        self.silence.update(self.batch_peak);
+        self.batch_peak = 0.0;
+    }
+
+    /// Drains all pending input, calling `observer` with the power spectrum
+    /// of every completed frame.
+    pub fn process(&mut self, mut observer: impl FnMut(&[f32])) {
+        while self.next_frame() {
+            observer(&self.channels[0].power);
+        }
+    }
+
+    /// Windows the newest `dft_size` samples of every channel and computes
+    /// their power. Works entirely in buffers sized by `set_dft_size`, so
+    /// steady-state frames do not allocate.
+    fn transform(&mut self) {
+        let amplitude_scale = self.amplitude_scale();
+        for (index, channel) in self.channels.iter_mut().enumerate() {
+            for ((out, &x), &w) in self
+                .windowed
+                .iter_mut()
+                .zip(&channel.time_domain_buf)
+                .zip(&self.window)
+            {
+                *out = x * w;
+            }
+
+            //
+            // Target tones see the same windowed frame as the FFT.
+            //
+            if let (0, Some(tones)) = (index, &mut self.tones) {
+                let scale = if self.single_sided { 2.0 } else { 1.0 } * amplitude_scale;
+                for (p, &mag) in self
+                    .tone_power
+                    .iter_mut()
+                    .zip(tones.process(&self.windowed))
+                {
+                    *p = (mag * scale) * (mag * scale);
+                }
+            }
+
+            //
+            // Execute real-input FFT; only the lower half-spectrum is produced.
+            //
+            self.plan.xform_real(&self.windowed, &mut self.spectrum);
+
+            for (phase, bin) in channel.phase.iter_mut().zip(&self.spectrum) {
+                *phase = bin.arg();
+            }
+            for (i, (p, bin)) in channel.power.iter_mut().zip(&self.spectrum).enumerate() {
+                let mut mag = bin.norm() * amplitude_scale;
+
+                //
+                // Single-sided spectrum: double every bin except DC. The
+                // Nyquist bin, which would also stay single, is not kept.
+                //
+                if self.single_sided && i != 0 {
+                    mag *= 2.0;
+                }
+                *p = mag * mag;
+            }
+            channel.power[..self.low_cut_bins].fill(0.0);
+
+            //
+            // Smooth power across frames.
+            //
+            channel.averager.apply(&mut channel.power);
+        }
+    }
+
+    /// Per-bin power of the latest frame, first channel.
+    pub fn power(&self) -> &[f32] {
+        &self.channels[0].power
+    }
+
+    /// Per-bin power of the latest frame for channel `index`.
+    pub fn channel_power(&self, index: usize) -> &[f32] {
+        &self.channels[index].power
+    }
+
+    /// Per-bin phase in radians, in (-π, π], of the latest frame of the
+    /// first channel. Phase is relative to the start of the frame and is
+    /// not averaged across frames.
+    pub fn phase(&self) -> &[f32] {
+        &self.channels[0].phase
+    }
+
+    /// Per-bin phase of the latest frame for channel `index`.
+    pub fn channel_phase(&self, index: usize) -> &[f32] {
+        &self.channels[index].phase
+    }
+
+    pub fn channel_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Switches every channel to a new stream, e.g. a different source at
+    /// the same sample rate, and returns the previous consumers in order.
+    /// Samples already queued in `consumers` are discarded and the history
+    /// and averages start over, so no frame mixes the two sources.
+    pub fn replace_inputs(&mut self, consumers: Vec<SampleConsumer>) -> Vec<SampleConsumer> {
+        assert_eq!(
+            consumers.len(),
+            self.channels.len(),
+            "replace_inputs needs one consumer per channel"
+        );
+        let mut previous = Vec::with_capacity(consumers.len());
+        for (channel, mut consumer) in self.channels.iter_mut().zip(consumers) {
+            consumer.clear();
+            previous.push(std::mem::replace(&mut channel.consumer, consumer));
+            channel.time_domain_buf.iter_mut().for_each(|s| *s = 0.0);
+            channel.power.fill(0.0);
+            channel.phase.fill(0.0);
+            channel.averager.reset();
+        }
+        self.noise_floor.reset();
+        self.samples_since_frame = 0;
+        previous
+    }
+
+    /// Frequency of the strongest bin in the latest frame, refined between
+    /// bins by parabolic interpolation of its level in dB.
+    pub fn dominant_frequency(&self) -> Option<f32> {
+        let levels: Vec<f32> = self
+            .power()
+            .iter()
+            .map(|&p| 10.0 * p.max(1e-18).log10())
+            .collect();
+        interpolate_peak(&levels).map(|bin| self.bin_to_hz(bin))
+    }
+
+    pub fn weighting(&self) -> Weighting {
+        self.weighting
+    }
+
+    /// Selects the frequency weighting whose gains `weighting_db` reports.
+    /// `power` itself stays unweighted.
+    pub fn set_weighting(&mut self, weighting: Weighting) {
+        self.weighting = weighting;
+        self.weighting_db = weighting.bin_gains_db(self.sample_rate, self.dft_size);
+    }
+
+    /// Gain in dB to add to each bin's level for the selected weighting.
+    pub fn weighting_db(&self) -> &[f32] {
+        &self.weighting_db
+    }
+
+    /// Measures `targets` (Hz) on every frame with a `Goertzel` detector,
+    /// without averaging. An empty list stops tone measurement.
+    pub fn set_tones(&mut self, targets: &[f32]) {
+        if targets.is_empty() {
+            self.tones = None;
+            self.tone_power.clear();
+        } else {
+            self.tones = Some(Goertzel::new(targets, self.sample_rate));
+            self.tone_power = vec![0.0; targets.len()];
+        }
+    }
+
+    /// Frequencies passed to `set_tones`.
+    pub fn tones(&self) -> &[f32] {
+        self.tones.as_ref().map_or(&[], |tones| tones.targets())
+    }
+
+    /// Power at each target tone in the latest frame, first channel, in the
+    /// same units as `power`.
+    pub fn tone_power(&self) -> &[f32] {
+        &self.tone_power
+    }
+
+    /// Samples between successive frames, `dft_size / 2` (50% overlap)
+    /// unless set otherwise.
+    pub fn hop(&self) -> usize {
+        self.hop
+    }
+
+    /// Runs a frame every `hop` samples, clamped to `1..=dft_size`. Each
+    /// frame still windows the newest `dft_size` samples, so frames overlap
+    /// by `dft_size - hop`; smaller hops give more frames, and smoother
+    /// averages and waterfalls, for proportionally more CPU. A frame that
+    /// was already due under a larger hop runs on the next sample.
+    pub fn set_hop(&mut self, hop: usize) {
+        self.hop = hop.clamp(1, self.dft_size);
+    }
+
+    pub fn dft_size(&self) -> usize {
+        self.dft_size
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Frequency spacing between adjacent bins.
+    pub fn bin_width_hz(&self) -> f32 {
+        fft::bin_width_hz(self.sample_rate, self.dft_size)
+    }
+
+    pub fn nyquist_hz(&self) -> f32 {
+        fft::nyquist_hz(self.sample_rate)
+    }
+
+    /// Center frequency of a (fractional) bin at the current FFT size.
+    pub fn bin_to_hz(&self, bin: f32) -> f32 {
+        fft::bin_to_hz(bin, self.sample_rate, self.dft_size)
+    }
+
+    /// Fractional bin at `hz` for the current FFT size.
+    pub fn hz_to_bin(&self, hz: f32) -> f32 {
+        fft::hz_to_bin(hz, self.sample_rate, self.dft_size)
+    }
+
+    pub fn plan_name(&self) -> String {
+        self.plan.name()
+    }
+
+    /// Switches to an `n`-point FFT, rebuilding the plan and every buffer
+    /// sized from it. The most recent samples are kept, so the first frames
+    /// after growing are zero-padded at the front rather than discarded.
+    /// The hop scales with the size, keeping the same overlap.
+    pub fn set_dft_size(&mut self, n: usize) {
+        if n == self.dft_size {
+            return;
+        }
+        check_dft_size(n);
+        self.plan = find_rdft(n);
+        self.hop = (self.hop * n / self.dft_size).clamp(1, n);
+        self.dft_size = n;
+
+        for channel in &mut self.channels {
+            //
+            // Keep the newest samples, padding or trimming at the oldest end.
+            //
+            while channel.time_domain_buf.len() > n {
+                channel.time_domain_buf.pop_front();
+            }
+            while channel.time_domain_buf.len() < n {
+                channel.time_domain_buf.push_front(0.0);
+            }
+            channel.power = vec![0.0; n / 2];
+            channel.phase = vec![0.0; n / 2];
+            channel.averager.reset();
+        }
+
+        self.samples_since_frame = 0;
+        self.window = self.window_fn.coefficients(n);
+        self.window_gains = WindowGains::of(&self.window);
+        self.windowed = vec![0.0; n];
+        self.spectrum = vec![Complex32::default(); n / 2 + 1];
+        self.weighting_db = self.weighting.bin_gains_db(self.sample_rate, n);
+        self.set_low_cut_hz(self.low_cut_hz);
+    }
+
+    pub fn low_cut_hz(&self) -> Option<f32> {
+        self.low_cut_hz
+    }
+
+    /// Zeroes the DC bin and every bin centered below `hz` before
+    /// averaging, so offset and rumble neither take the top of the display
+    /// nor win `dominant_frequency`. `None` keeps every bin.
+    pub fn set_low_cut_hz(&mut self, hz: Option<f32>) {
+        self.low_cut_hz = hz;
+        self.low_cut_bins = match hz {
+            Some(hz) => (self.hz_to_bin(hz).ceil().max(1.0) as usize).min(self.dft_size / 2),
+            None => 0,
+        };
+    }
+
+    pub fn window_function(&self) -> WindowFunction {
+        self.window_fn
+    }
+
+    pub fn set_window_function(&mut self, window_fn: WindowFunction) {
+        self.window_fn = window_fn;
+        self.window = window_fn.coefficients(self.dft_size);
+        self.window_gains = WindowGains::of(&self.window);
+    }
+
+    /// Coherent gain and ENBW of the current window at the current FFT
+    /// size, updated with either.
+    pub fn window_gains(&self) -> WindowGains {
+        self.window_gains
+    }
+
+    /// Equivalent noise bandwidth of a bin in Hz. `power` is calibrated for
+    /// tones; dividing it by this gives the density of broadband noise in
+    /// the same units per Hz, independent of window and FFT size.
+    pub fn enbw_hz(&self) -> f32 {
+        self.window_gains.enbw_bins * self.bin_width_hz()
+    }
+
+    /// Magnitude scale 1 / (N × coherent gain), i.e. 1 / Σw, that makes a
+    /// full-scale sinusoid at a bin center read 0.5 before single-sided
+    /// doubling.
+    fn amplitude_scale(&self) -> f32 {
+        1.0 / (self.dft_size as f32 * self.window_gains.coherent_gain).max(f32::MIN_POSITIVE)
+    }
+
+    pub fn single_sided(&self) -> bool {
+        self.single_sided
+    }
+
+    /// Folds negative-frequency energy into the positive bins when set, so
+    /// a sinusoid reads its own amplitude. On by default.
+    pub fn set_single_sided(&mut self, single_sided: bool) {
+        self.single_sided = single_sided;
+    }
+
+    pub fn averaging(&self) -> AveragingMode {
+        self.channels[0].averager.mode()
+    }
+
+    pub fn set_averaging(&mut self, mode: AveragingMode) {
+        for channel in &mut self.channels {
+            channel.averager.set_mode(mode);
+        }
+    }
+
+    pub fn silence(&self) -> &SilenceDetector {
+        &self.silence
+    }
+
+    pub fn silence_mut(&mut self) -> &mut SilenceDetector {
+        &mut self.silence
+    }
+
+    pub fn meter(&self) -> &LevelMeter {
+        &self.meter
+    }
+
+    pub fn meter_mut(&mut self) -> &mut LevelMeter {
+        &mut self.meter
+    }
+
+    pub fn input_gain_db(&self) -> f32 {
+        self.input_gain_db
+    }
+
+    /// Sets the input trim applied to every sample as it is read, before
+    /// windowing. The spectrum, level meter, clip latch, silence detection
+    /// and input stats all see the trimmed signal, so a boosted quiet
+    /// source can clip here even though the source itself did not.
+    /// Samples already in the history keep their old gain until they
+    /// scroll out.
+    pub fn set_input_gain_db(&mut self, db: f32) {
+        self.input_gain_db = db;
+        self.input_gain = 10f32.powf(db / 20.0);
+    }
+
+    pub fn settings(&self) -> EngineSettings {
+        EngineSettings {
+            dft_size: self.dft_size,
+            hop: self.hop,
+            window_function: self.window_fn,
+            weighting: self.weighting,
+            single_sided: self.single_sided,
+            low_cut_hz: self.low_cut_hz,
+            averaging: self.averaging(),
+            input_gain_db: self.input_gain_db,
+            silence_enter_db: self.silence.enter_db,
+            silence_exit_db: self.silence.exit_db,
+            silence_timeout: self.silence.timeout,
+        }
+    }
+
+    /// Applies every setting that differs from the current one, through
+    /// the same setters as changing it directly. The FFT size goes first,
+    /// so `settings.hop` replaces the hop it scales.
+    pub fn apply_settings(&mut self, settings: &EngineSettings) {
+        let current = self.settings();
+        if settings.dft_size != current.dft_size {
+            self.set_dft_size(settings.dft_size);
+        }
+        if settings.hop != self.hop {
+            self.set_hop(settings.hop);
+        }
+        if settings.window_function != current.window_function {
+            self.set_window_function(settings.window_function);
+        }
+        if settings.weighting != current.weighting {
+            self.set_weighting(settings.weighting);
+        }
+        if settings.single_sided != current.single_sided {
+            self.set_single_sided(settings.single_sided);
+        }
+        if settings.low_cut_hz != current.low_cut_hz {
+            self.set_low_cut_hz(settings.low_cut_hz);
+        }
+        if settings.averaging != current.averaging {
+            self.set_averaging(settings.averaging);
+        }
+        if settings.input_gain_db != current.input_gain_db {
+            self.set_input_gain_db(settings.input_gain_db);
+        }
+        self.silence.enter_db = settings.silence_enter_db;
+        self.silence.exit_db = settings.silence_exit_db;
+        self.silence.timeout = settings.silence_timeout;
+    }
+
+    /// Returns and resets the input counters.
+    pub fn take_input_stats(&mut self) -> InputStats {
+        std::mem::take(&mut self.stats)
+    }
+
+    /// Takes the input counters as rates over `elapsed`, the time since
+    /// they were last taken, together with the current noise floor and
+    /// silence state.
+    pub fn take_dsp_stats(&mut self, elapsed: Duration) -> DspStats {
+        let input = self.take_input_stats();
+        let secs = elapsed.as_secs_f32().max(f32::MIN_POSITIVE);
+        let floor = self.noise_floor.median().unwrap_or(0.0);
+        DspStats {
+            samples_per_sec: input.samples as f32 / secs,
+            frames_per_sec: input.frames as f32 / secs,
+            max_peak: input.peak,
+            noise_floor_db: 10.0 * floor.max(1e-18).log10(),
+            dropped_samples: 0,
+            silent: self.silence.is_silence(),
+        }
+    }
+}
+
+/// A 1-point frame has no bins below Nyquist, so it would never produce
+/// any power.
+fn check_dft_size(n: usize) {
+    assert!(
+        n >= 2,
+        "SpectrumEngine needs an FFT size of at least 2, got {}",
+        n
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ringbuf::HeapRb;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn overlapped_frames_find_a_tone() {
+        let (n, rate) = (256, 8000);
+        let (mut producer, consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut engine = SpectrumEngine::new(consumer, rate, n);
+
+        //
+        // 1.5 frames of input is exactly three hops. The tone sits on bin 32
+        // (1 kHz at 31.25 Hz/bin).
+        //
+        for i in 0..n * 3 / 2 {
+            let _ = producer.push((2.0 * PI * 1000.0 * i as f32 / rate as f32).sin());
+        }
+
+        let mut peaks = Vec::new();
+        engine.process(|power| {
+            let (peak, _) =
+                power.iter().enumerate().fold(
+                    (0, 0.0),
+                    |best, (i, &p)| if p > best.1 { (i, p) } else { best },
+                );
+            peaks.push(peak);
+        });
+        assert_eq!(peaks, [32, 32, 32]);
+        assert_eq!(engine.take_input_stats().samples, n * 3 / 2);
+        assert!(!engine.silence().is_silence());
+    }
+
+    #[test]
+    fn replaced_inputs_start_from_a_clean_history() {
+        let n = 64;
+        let (mut old_producer, consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut engine = SpectrumEngine::new(consumer, 8000, n);
+        for _ in 0..n {
+            let _ = old_producer.push(1.0);
+        }
+        engine.process(|_| {});
+
+        //
+        // Queued samples on the new stream predate the switch and are
+        // dropped; only the ones pushed afterwards are analyzed.
+        //
+        let (mut producer, consumer) = HeapRb::<f32>::new(4 * n).split();
+        let _ = producer.push(1.0);
+        let mut previous = engine.replace_inputs(vec![consumer]);
+        assert_eq!(previous.len(), 1);
+        assert!(engine.power().iter().all(|&p| p == 0.0));
+        let _ = old_producer.push(0.5);
+        assert_eq!(previous[0].pop(), Some(0.5));
+
+        for _ in 0..n / 2 {
+            let _ = producer.push(0.0);
+        }
+        let _ = engine.take_input_stats();
+        let mut frames = 0;
+        engine.process(|power| {
+            frames += 1;
+            assert!(power.iter().all(|&p| p == 0.0));
+        });
+        assert_eq!(frames, 1);
+        assert_eq!(engine.take_input_stats().samples, n / 2);
+    }
+
+    #[test]
+    fn noise_density_does_not_depend_on_the_window() {
+        //
+        // Uniform noise in [-1, 1] has variance 1/3. Single-sided power is
+        // referenced to a sine's peak, so its density reads 4σ² / fs.
+        //
+        let (n, rate) = (1024, 48000);
+        let expected_db = 10.0 * (4.0 / 3.0 / rate as f32).log10();
+        let mut state = 1u32;
+        let noise: Vec<f32> = (0..64 * n)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as f32 / u32::MAX as f32 * 2.0 - 1.0
+            })
+            .collect();
+
+        for window_fn in [
+            WindowFunction::Rectangular,
+            WindowFunction::Hann,
+            WindowFunction::BlackmanHarris,
+        ] {
+            let (mut producer, consumer) = HeapRb::<f32>::new(noise.len()).split();
+            let mut engine = SpectrumEngine::new(consumer, rate, n);
+            engine.set_window_function(window_fn);
+            producer.push_slice(&noise);
+
+            let (mut sum, mut count) = (0.0f64, 0);
+            engine.process(|power| {
+                sum += power[1..].iter().map(|&p| p as f64).sum::<f64>();
+                count += power.len() - 1;
+            });
+            let density_db =
+                10.0 * (sum / count as f64).log10() as f32 - 10.0 * engine.enbw_hz().log10();
+            assert!(
+                (density_db - expected_db).abs() < 0.2,
+                "{}: {:.2} dB/Hz, want {:.2}",
+                window_fn.name(),
+                density_db,
+                expected_db
+            );
+        }
+    }
+
+    #[test]
+    fn full_scale_sine_reads_zero_dbfs() {
+        //
+        // Bin 64 of a 1024-point frame at 48 kHz is 3 kHz.
+        //
+        let (n, rate, bin) = (1024, 48000, 64);
+        for window_fn in WindowFunction::ALL {
+            let (mut producer, consumer) = HeapRb::<f32>::new(4 * n).split();
+            let mut engine = SpectrumEngine::new(consumer, rate, n);
+            engine.set_window_function(window_fn);
+            for i in 0..2 * n {
+                let _ = producer.push((2.0 * PI * (bin * i) as f32 / n as f32).sin());
+            }
+            engine.process(|_| {});
+
+            let db = 10.0 * engine.power()[bin].log10();
+            assert!(db.abs() < 0.1, "{}: {:.3} dB", window_fn.name(), db);
+
+            //
+            // The two-sided view splits the same power between ±f.
+            //
+            engine.set_single_sided(false);
+            for i in 0..n / 2 {
+                let _ = producer.push((2.0 * PI * (bin * (2 * n + i)) as f32 / n as f32).sin());
+            }
+            engine.process(|_| {});
+            let db = 10.0 * engine.power()[bin].log10();
+            assert!(
+                (db + 6.02).abs() < 0.1,
+                "{}: {:.3} dB",
+                window_fn.name(),
+                db
+            );
+        }
+    }
+
+    #[test]
+    fn frequencies_follow_the_negotiated_rate() {
+        //
+        // A device opened at 48 kHz: a quarter-band tone is 12 kHz, not the
+        // 11025 Hz a 44.1 kHz assumption would label it.
+        //
+        let (n, rate) = (256, 48000);
+        let (mut producer, consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut engine = SpectrumEngine::new(consumer, rate, n);
+        for i in 0..n {
+            let _ = producer.push((2.0 * PI * 12000.0 * i as f32 / rate as f32).sin());
+        }
+        while engine.next_frame() {}
+
+        assert_eq!(engine.nyquist_hz(), 24000.0);
+        assert_eq!(engine.bin_to_hz((n / 4) as f32), 12000.0);
+        let peak = engine.dominant_frequency().unwrap();
+        assert!((peak - 12000.0).abs() < 1.0, "peak at {} Hz", peak);
+    }
+
+    #[test]
+    fn skipped_input_is_drained_without_frames() {
+        let n = 256;
+        let (mut producer, consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut engine = SpectrumEngine::new(consumer, 8000, n);
+
+        for _ in 0..3 * n {
+            let _ = producer.push(0.5);
+        }
+        engine.skip_input();
+        assert!(producer.is_empty());
+        assert!(engine.power().iter().all(|&p| p == 0.0));
+
+        //
+        // Resuming transforms the history gathered while skipping.
+        //
+        for _ in 0..n / 2 {
+            let _ = producer.push(0.5);
+        }
+        assert!(engine.next_frame());
+        assert!(engine.power()[0] > 0.0);
+    }
+
+    #[test]
+    fn input_trim_scales_power_and_meter() {
+        let n = 256;
+        let (mut producer, consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut engine = SpectrumEngine::new(consumer, 8000, n);
+        engine.set_input_gain_db(20.0 * 2f32.log10());
+
+        for i in 0..n {
+            let _ = producer.push(0.25 * (2.0 * PI * 16.0 * i as f32 / n as f32).sin());
+        }
+        while engine.next_frame() {}
+        assert!((engine.meter().level() - 0.5).abs() < 0.01);
+        assert!(!engine.meter().clipped());
+
+        //
+        // A doubled 0.25 amplitude reads -6 dBFS at its bin.
+        //
+        let db = 10.0 * engine.power()[16].log10();
+        assert!((db + 6.02).abs() < 0.1, "{} dB", db);
+
+        let _ = producer.push(0.6);
+        engine.skip_input();
+        assert!(engine.meter().clipped());
+    }
+
+    #[test]
+    fn stereo_meter_releases_at_the_sample_rate() {
+        //
+        // One 0.5 peak on the right, then 0.1 s of silence on both.
+        //
+        let (n, rate) = (256, 8000);
+        let (mut left, left_consumer) = HeapRb::<f32>::new(4 * n).split();
+        let (mut right, right_consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut engine =
+            SpectrumEngine::with_channels(vec![left_consumer, right_consumer], rate, n);
+        let _ = left.push(0.0);
+        let _ = right.push(0.5);
+        engine.skip_input();
+        assert_eq!(engine.meter().level(), 0.5);
+
+        let ticks = rate as usize / 10;
+        for _ in 0..ticks {
+            let _ = left.push(0.0);
+            let _ = right.push(0.0);
+        }
+        engine.skip_input();
+        let expected = 0.5 * (-0.1 / METER_RELEASE_SECS).exp();
+        assert!((engine.meter().level() - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn stereo_channels_are_analyzed_separately() {
+        let (n, rate) = (256, 8000);
+        let (mut left, left_consumer) = HeapRb::<f32>::new(4 * n).split();
+        let (mut right, right_consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut engine =
+            SpectrumEngine::with_channels(vec![left_consumer, right_consumer], rate, n);
+
+        //
+        // Left carries bin 32, right bin 64; the right ring lags by half a
+        // hop, so only one frame is complete until it catches up.
+        //
+        let tone = |bin: f32, i: usize| (2.0 * PI * bin * i as f32 / n as f32).sin();
+        for i in 0..n {
+            let _ = left.push(tone(32.0, i));
+        }
+        for i in 0..n * 3 / 4 {
+            let _ = right.push(tone(64.0, i));
+        }
+
+        let loudest = |power: &[f32]| {
+            (0..power.len())
+                .max_by(|&a, &b| power[a].total_cmp(&power[b]))
+                .unwrap()
+        };
+        assert!(engine.next_frame());
+        assert!(!engine.next_frame());
+        assert_eq!(engine.channel_count(), 2);
+        assert_eq!(loudest(engine.power()), 32);
+        assert_eq!(loudest(engine.channel_power(1)), 64);
+    }
+
+    #[test]
+    fn phase_tracks_a_quarter_cycle_offset() {
+        //
+        // Cosine on the left, sine on the right, both on bin 32: the sine
+        // lags by a quarter cycle.
+        //
+        let (n, rate, bin) = (256, 8000, 32);
+        let (mut left, left_consumer) = HeapRb::<f32>::new(4 * n).split();
+        let (mut right, right_consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut engine =
+            SpectrumEngine::with_channels(vec![left_consumer, right_consumer], rate, n);
+        for i in 0..n {
+            let theta = 2.0 * PI * (bin * i) as f32 / n as f32;
+            let _ = left.push(theta.cos());
+            let _ = right.push(theta.sin());
+        }
+        engine.process(|_| {});
+
+        assert!(engine.phase()[bin].abs() < 0.05);
+        assert!((engine.channel_phase(1)[bin] + PI / 2.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn dsp_stats_report_rates_over_the_interval() {
+        //
+        // 128 hops of a 256-point frame in two seconds, long enough for the
+        // floor to forget the first frames' zero-padded edge. A quiet tone
+        // on bin 32 leaves the median bin near the empty floor.
+        //
+        let n = 256;
+        let (mut producer, consumer) = HeapRb::<f32>::new(64 * n).split();
+        let mut engine = SpectrumEngine::new(consumer, 8000, n);
+        for i in 0..64 * n {
+            let _ = producer.push(0.25 * (2.0 * PI * (32 * i) as f32 / n as f32).sin());
+        }
+        engine.process(|_| {});
+
+        let stats = engine.take_dsp_stats(Duration::from_secs(2));
+        assert_eq!(stats.samples_per_sec, 32.0 * n as f32);
+        assert_eq!(stats.frames_per_sec, 64.0);
+        assert!((stats.max_peak - 0.25).abs() < 1e-3);
+        assert!(stats.noise_floor_db < -60.0, "{}", stats.noise_floor_db);
+        assert!(!stats.silent);
+
+        let idle = engine.take_dsp_stats(Duration::from_secs(1));
+        assert_eq!((idle.samples_per_sec, idle.frames_per_sec), (0.0, 0.0));
+    }
+
+    #[test]
+    fn half_hop_doubles_the_frame_count() {
+        let n = 256;
+        let frames_at = |hop: usize| {
+            let (mut producer, consumer) = HeapRb::<f32>::new(8 * n).split();
+            let mut engine = SpectrumEngine::new(consumer, 8000, n);
+            engine.set_hop(hop);
+            producer.push_slice(&vec![0.5; 8 * n]);
+            engine.process(|_| {});
+            engine.take_input_stats().frames
+        };
+        assert_eq!(frames_at(n), 8);
+        assert_eq!(frames_at(n / 2), 16);
+        assert_eq!(frames_at(n / 4), 32);
+        assert_eq!(frames_at(0), 8 * n);
+
+        //
+        // The overlap survives a size change.
+        //
+        let (_, consumer) = HeapRb::<f32>::new(n).split();
+        let mut engine = SpectrumEngine::new(consumer, 8000, n);
+        engine.set_hop(n / 4);
+        engine.set_dft_size(4 * n);
+        assert_eq!(engine.hop(), n);
+    }
+
+    #[test]
+    fn low_cut_removes_offset_from_the_peak() {
+        //
+        // A large DC offset outweighs a quiet tone on bin 32 (1 kHz) until
+        // the bins under 100 Hz are cut.
+        //
+        let (n, rate) = (256, 8000);
+        let (mut producer, consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut engine = SpectrumEngine::new(consumer, rate, n);
+        let mut push_frame = |engine: &mut SpectrumEngine| {
+            for i in 0..n {
+                let tone = 0.1 * (2.0 * PI * 1000.0 * i as f32 / rate as f32).sin();
+                let _ = producer.push(0.8 + tone);
+            }
+            engine.process(|_| {});
+        };
+
+        push_frame(&mut engine);
+        assert!(engine.dominant_frequency().unwrap() < 100.0);
+
+        engine.set_low_cut_hz(Some(100.0));
+        push_frame(&mut engine);
+        assert!((engine.dominant_frequency().unwrap() - 1000.0).abs() < 1.0);
+        assert!(engine.power()[..4].iter().all(|&p| p == 0.0));
+        assert!(engine.power()[4] > 0.0);
+    }
+
+    #[test]
+    fn applied_settings_read_back() {
+        let n = 256;
+        let (_producer, consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut engine = SpectrumEngine::new(consumer, 8000, n);
+        let mut settings = engine.settings();
+        settings.dft_size = 2 * n;
+        settings.hop = n / 8;
+        settings.weighting = Weighting::A;
+        settings.low_cut_hz = Some(50.0);
+        settings.input_gain_db = 6.0;
+        settings.silence_timeout = Duration::from_secs(5);
+        engine.apply_settings(&settings);
+
+        assert_eq!(engine.settings(), settings);
+        assert_eq!(engine.dft_size(), 2 * n);
+        assert_eq!(engine.hop(), n / 8);
+        assert_eq!(engine.silence().timeout, Duration::from_secs(5));
+    }
+
+    #[test]
+    #[should_panic(expected = "FFT size of at least 2")]
+    fn single_point_frames_are_rejected() {
+        let (_producer, consumer) = HeapRb::<f32>::new(16).split();
+        SpectrumEngine::new(consumer, 8000, 1);
+    }
+}
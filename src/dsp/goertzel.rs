@@ -0,0 +1,116 @@
+use std::f64::consts::PI;
+
+/// Magnitudes at a fixed set of target frequencies, computed directly from a
+/// block of samples with the Goertzel recurrence.
+///
+/// Each target costs one multiply-add pass over the block, so a handful of
+/// tones (e.g. the DTMF pairs) is cheaper than a full FFT. Targets are not
+/// rounded to a bin: the result is the DFT magnitude at exactly that
+/// frequency for the block's length.
+pub struct Goertzel {
+    targets: Vec<f32>,
+
+    //
+    // Per target: 2·cos(ω) for the recurrence, and cos(ω), sin(ω) for the
+    // final output step. Kept in f64 so long blocks do not drift.
+    //
+    coeffs: Vec<(f64, f64, f64)>,
+    magnitudes: Vec<f32>,
+}
+
+impl Goertzel {
+    /// Creates a detector for `targets` in Hz at `sample_rate`.
+    pub fn new(targets: &[f32], sample_rate: u32) -> Self {
+        let coeffs = targets
+            .iter()
+            .map(|&hz| {
+                let omega = 2.0 * PI * hz as f64 / sample_rate as f64;
+                (2.0 * omega.cos(), omega.cos(), omega.sin())
+            })
+            .collect();
+        Self {
+            targets: targets.to_vec(),
+            coeffs,
+            magnitudes: vec![0.0; targets.len()],
+        }
+    }
+
+    pub fn targets(&self) -> &[f32] {
+        &self.targets
+    }
+
+    /// Runs every target over `block` and returns their magnitudes, in the
+    /// same unnormalized scale as `|X[k]|` from a forward DFT of `block`.
+    pub fn process(&mut self, block: &[f32]) -> &[f32] {
+        for (mag, &(coeff, cos, sin)) in self.magnitudes.iter_mut().zip(&self.coeffs) {
+            let (mut s1, mut s2) = (0.0, 0.0);
+            for &x in block {
+                let s0 = x as f64 + coeff * s1 - s2;
+                s2 = s1;
+                s1 = s0;
+            }
+
+            //
+            // X(ω) = e^{jω(N-1)}·(s1 - e^{-jω}·s2); the phase factor does not
+            // change the magnitude.
+            //
+            let re = s1 - cos * s2;
+            let im = sin * s2;
+            *mag = (re * re + im * im).sqrt() as f32;
+        }
+        &self.magnitudes
+    }
+
+    /// Magnitudes from the latest `process` call, one per target.
+    pub fn magnitudes(&self) -> &[f32] {
+        &self.magnitudes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft::find_rdft;
+    use num_complex::Complex32;
+
+    #[test]
+    fn dtmf_pair_matches_fft_magnitudes() {
+        //
+        // Digit 5 (770 Hz + 1336 Hz) over one second at 8 kHz, so every DTMF
+        // frequency falls on a 1 Hz bin the FFT can be compared at.
+        //
+        let (rate, n) = (8000, 8000);
+        let dtmf = [697.0, 770.0, 852.0, 941.0, 1209.0, 1336.0, 1477.0];
+        let input: Vec<f32> = (0..n)
+            .map(|i| {
+                let t = i as f32 / rate as f32;
+                (2.0 * std::f32::consts::PI * 770.0 * t).sin()
+                    + 0.5 * (2.0 * std::f32::consts::PI * 1336.0 * t).sin()
+            })
+            .collect();
+
+        let mut spectrum = vec![Complex32::default(); n / 2 + 1];
+        find_rdft(n).xform_real(&input, &mut spectrum);
+
+        let mut detector = Goertzel::new(&dtmf, rate);
+        let magnitudes = detector.process(&input);
+        for (&hz, &mag) in dtmf.iter().zip(magnitudes) {
+            let expected = spectrum[hz as usize].norm();
+            assert!(
+                (mag - expected).abs() <= 1e-3 * (n as f32 / 2.0),
+                "{} Hz: goertzel {} fft {}",
+                hz,
+                mag,
+                expected
+            );
+        }
+
+        //
+        // The two tones present stand far above the rest.
+        //
+        let mut order: Vec<usize> = (0..dtmf.len()).collect();
+        order.sort_by(|&a, &b| magnitudes[b].total_cmp(&magnitudes[a]));
+        assert_eq!((dtmf[order[0]], dtmf[order[1]]), (770.0, 1336.0));
+        assert!(magnitudes[order[2]] < 1e-3 * magnitudes[order[1]]);
+    }
+}
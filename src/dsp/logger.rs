@@ -0,0 +1,148 @@
+use super::bands::{BandAnalyzer, BandResolution};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Appends band levels to a CSV file at a fixed time interval for
+/// long-term monitoring.
+///
+/// Each row holds a UNIX timestamp followed by one level in dB per band,
+/// with the nominal band centers as column headers. Rows are flushed as they
+/// are written so an interrupted session keeps everything logged so far.
+pub struct BandLogger {
+    writer: BufWriter<File>,
+    analyzer: BandAnalyzer,
+    interval: Duration,
+    last_write: Option<Instant>,
+    rows: usize,
+}
+
+impl BandLogger {
+    pub fn create(
+        path: &Path,
+        resolution: BandResolution,
+        sample_rate: u32,
+        n: usize,
+        interval: Duration,
+    ) -> io::Result<Self> {
+        let analyzer = BandAnalyzer::new(resolution, sample_rate, n);
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        //
+        // Header: timestamp plus one column per band center.
+        //
+        write!(writer, "timestamp")?;
+        for band in analyzer.bands() {
+            write!(writer, ",{}", band.nominal_hz)?;
+        }
+        writeln!(writer)?;
+        writer.flush()?;
+
+        Ok(Self {
+            writer,
+            analyzer,
+            interval,
+            last_write: None,
+            rows: 0,
+        })
+    }
+
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Called once per processed spectrum with per-bin power, the weighting
+    /// gains and the window's ENBW in bins; writes a row of
+    /// `BandAnalyzer::levels_db` only when `interval` has elapsed since the
+    /// previous one.
+    pub fn record(
+        &mut self,
+        power: &[f32],
+        weighting_db: &[f32],
+        enbw_bins: f32,
+    ) -> io::Result<()> {
+        if let Some(last) = self.last_write {
+            if last.elapsed() < self.interval {
+                return Ok(());
+            }
+        }
+        self.last_write = Some(Instant::now());
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+
+        write!(self.writer, "{:.3}", timestamp)?;
+        for level in self.analyzer.levels_db(power, weighting_db, enbw_bins) {
+            write!(self.writer, ",{:.2}", level)?;
+        }
+        writeln!(self.writer)?;
+        self.writer.flush()?;
+
+        self.rows += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_header_and_rows_at_the_interval() {
+        let path = std::env::temp_dir().join(format!("fftanalyzer_{}.csv", std::process::id()));
+        let (rate, n) = (48000, 4096);
+        let mut logger = BandLogger::create(
+            &path,
+            BandResolution::Octave,
+            rate,
+            n,
+            Duration::from_secs(3600),
+        )
+        .unwrap();
+
+        //
+        // All power in the bin nearest 1 kHz. The second call falls
+        // within the interval and writes nothing; a zero interval writes
+        // on every call.
+        //
+        let mut power = vec![0.0; n / 2 + 1];
+        power[(1000.0 * n as f32 / rate as f32).round() as usize] = 1.0;
+        logger.record(&power, &[], 1.0).unwrap();
+        logger.record(&power, &[], 1.0).unwrap();
+        assert_eq!(logger.rows(), 1);
+        logger.set_interval(Duration::ZERO);
+
+        //
+        // Rows are calibrated like the RTA view: -3 dB of weighting and an
+        // ENBW of 2 bins take the band 6 dB down.
+        //
+        logger.record(&power, &vec![-3.0; n / 2 + 1], 2.0).unwrap();
+        assert_eq!(logger.rows(), 2);
+
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(path);
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("timestamp,31.5,63,125,250,500,1000,2000,4000,8000,16000")
+        );
+        let rows: Vec<Vec<&str>> = lines.map(|l| l.split(',').collect()).collect();
+        assert_eq!(rows.len(), 2);
+        for (row, tone) in rows.iter().zip(["0.00", "-6.01"]) {
+            assert!(row[0].parse::<f64>().unwrap() > 0.0);
+            let levels = &row[1..];
+            assert_eq!(levels.len(), 10);
+            for (i, level) in levels.iter().enumerate() {
+                let expected = if i == 5 { tone } else { "-180.00" };
+                assert_eq!(*level, expected, "band {}", i);
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+/// Peak level meter with a clip latch.
+///
+/// The envelope jumps straight up to each new peak and falls back
+/// exponentially with time constant `release` seconds, so short transients
+/// stay visible for a few GUI frames. Any sample reaching `CLIP_LEVEL` sets
+/// the clip latch, which stays set until `clear_clip` is called.
+pub struct LevelMeter {
+    release_coeff: f32,
+    level: f32,
+    clipped: bool,
+}
+
+/// Absolute sample value treated as full scale.
+pub const CLIP_LEVEL: f32 = 0.999;
+
+impl LevelMeter {
+    pub fn new(sample_rate: u32, release: f32) -> Self {
+        Self {
+            release_coeff: (-1.0 / (release * sample_rate as f32)).exp(),
+            level: 0.0,
+            clipped: false,
+        }
+    }
+
+    /// Feeds one sample.
+    pub fn update(&mut self, sample: f32) {
+        let abs = sample.abs();
+        self.level = abs.max(self.level * self.release_coeff);
+        if abs >= CLIP_LEVEL {
+            self.clipped = true;
+        }
+    }
+
+    /// Current envelope, linear full scale.
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// Current envelope in dBFS.
+    pub fn level_db(&self) -> f32 {
+        20.0 * self.level.max(1e-9).log10()
+    }
+
+    pub fn clipped(&self) -> bool {
+        self.clipped
+    }
+
+    pub fn clear_clip(&mut self) {
+        self.clipped = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attacks_instantly_releases_slowly_and_latches_clips() {
+        let rate = 1000;
+        let mut meter = LevelMeter::new(rate, 0.1);
+
+        meter.update(0.5);
+        assert_eq!(meter.level(), 0.5);
+
+        //
+        // One time constant of silence leaves about 1/e of the peak.
+        //
+        for _ in 0..100 {
+            meter.update(0.0);
+        }
+        assert!((meter.level() - 0.5 / std::f32::consts::E).abs() < 1e-3);
+        assert!(!meter.clipped());
+
+        meter.update(-1.0);
+        for _ in 0..10 * rate {
+            meter.update(0.0);
+        }
+        assert!(meter.clipped());
+        meter.clear_clip();
+        assert!(!meter.clipped());
+    }
+}
@@ -0,0 +1,17 @@
+pub mod average;
+pub mod bands;
+pub mod engine;
+pub mod goertzel;
+pub mod logger;
+pub mod meter;
+pub mod noise_floor;
+pub mod peak;
+pub mod phase;
+pub mod pitch;
+pub mod silence;
+pub mod streaming;
+pub mod weighting;
+pub mod welch;
+pub mod window;
+pub mod wola;
+pub mod worker;
@@ -0,0 +1,150 @@
+/// Per-bin noise floor that follows the quietest recent level of each bin.
+///
+/// Each bin's power is first smoothed over `SMOOTHING_SECS` to tame the
+/// frame-to-frame scatter of noise. The floor drops at once to any smoothed
+/// level below it and otherwise rises by at most `rise_db_per_sec`, so a
+/// tone that appears in a bin barely lifts that bin's floor while the floor
+/// still recovers when the noise itself gets louder. Power is linear, as
+/// `SpectrumEngine::power`.
+pub struct NoiseFloor {
+    rise_db_per_sec: f32,
+    smoothed: Vec<f32>,
+    floor: Vec<f32>,
+}
+
+//
+// Time constant of the per-bin smoothing. Longer is steadier but follows
+// falling noise more slowly.
+//
+const SMOOTHING_SECS: f32 = 0.2;
+
+impl NoiseFloor {
+    pub fn new(rise_db_per_sec: f32) -> Self {
+        Self {
+            rise_db_per_sec,
+            smoothed: Vec::new(),
+            floor: Vec::new(),
+        }
+    }
+
+    /// Folds in one frame of `power` that arrived `dt` seconds after the
+    /// previous one. A frame of a new length restarts the estimate from it.
+    pub fn update(&mut self, power: &[f32], dt: f32) {
+        if self.floor.len() != power.len() {
+            self.smoothed = power.to_vec();
+            self.floor = power.to_vec();
+            return;
+        }
+        let alpha = 1.0 - (-dt / SMOOTHING_SECS).exp();
+        let rise = 10f32.powf(self.rise_db_per_sec * dt / 10.0);
+        for ((smoothed, floor), &p) in self.smoothed.iter_mut().zip(&mut self.floor).zip(power) {
+            *smoothed += alpha * (p - *smoothed);
+            *floor = (*floor * rise).min(*smoothed);
+        }
+    }
+
+    /// Forgets the estimate; the next frame starts it afresh.
+    pub fn reset(&mut self) {
+        self.smoothed.clear();
+        self.floor.clear();
+    }
+
+    /// Floor power per bin; empty before the first frame.
+    pub fn floor(&self) -> &[f32] {
+        &self.floor
+    }
+
+    /// Median floor over the bins within `radius` of `bin`. Tones lift the
+    /// floor only in their own few bins, which the median passes over.
+    pub fn local_floor(&self, bin: usize, radius: usize) -> Option<f32> {
+        if bin >= self.floor.len() {
+            return None;
+        }
+        let lo = bin.saturating_sub(radius);
+        let hi = (bin + radius + 1).min(self.floor.len());
+        let mut around = self.floor[lo..hi].to_vec();
+        let mid = around.len() / 2;
+        let (_, &mut median, _) = around.select_nth_unstable_by(mid, f32::total_cmp);
+        Some(median)
+    }
+
+    /// Median floor over every bin, a single broadband figure; `None`
+    /// before the first frame.
+    pub fn median(&self) -> Option<f32> {
+        self.local_floor(self.floor.len() / 2, self.floor.len())
+    }
+
+    /// Ratio in dB of `power[bin]` to the local floor around `bin`.
+    pub fn snr_db(&self, power: &[f32], bin: usize, radius: usize) -> Option<f32> {
+        let floor = self.local_floor(bin, radius)?;
+        let signal = *power.get(bin)?;
+        Some(10.0 * (signal.max(1e-18) / floor.max(1e-18)).log10())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::engine::SpectrumEngine;
+    use ringbuf::HeapRb;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn floor_converges_under_a_tone() {
+        //
+        // A -20 dBFS tone on bin 64 over uniform noise, for 4 s of input,
+        // far longer than the floor needs to settle.
+        //
+        let (n, rate) = (1024, 8000);
+        let amplitude = 0.1;
+        let mut state = 1u32;
+        let input: Vec<f32> = (0..4 * rate as usize)
+            .map(|i| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let noise = (state as f32 / u32::MAX as f32 * 2.0 - 1.0) * 0.01;
+                noise + amplitude * (2.0 * PI * 64.0 * i as f32 / n as f32).sin()
+            })
+            .collect();
+        let (mut producer, consumer) = HeapRb::<f32>::new(input.len()).split();
+        producer.push_slice(&input);
+        let mut engine = SpectrumEngine::new(consumer, rate, n);
+
+        let dt = engine.hop() as f32 / rate as f32;
+        let mut floor = NoiseFloor::new(6.0);
+        let (mut noise_sum, mut noise_count) = (0.0f64, 0);
+        let mut last = Vec::new();
+        engine.process(|power| {
+            floor.update(power, dt);
+            noise_sum += power[200..400].iter().map(|&p| p as f64).sum::<f64>();
+            noise_count += 200;
+            last = power.to_vec();
+        });
+        let noise_db = 10.0 * (noise_sum / noise_count as f64).log10() as f32;
+
+        //
+        // Minimum tracking sits about 1 dB under the mean noise power, and
+        // the tone's bins do not drag the local floor up.
+        //
+        let away = 10.0 * floor.local_floor(300, 16).unwrap().log10();
+        let under = 10.0 * floor.local_floor(64, 16).unwrap().log10();
+        assert!((away - noise_db).abs() < 2.0, "{} vs {}", away, noise_db);
+        assert!((under - noise_db).abs() < 2.0, "{} vs {}", under, noise_db);
+
+        let snr = floor.snr_db(&last, 64, 16).unwrap();
+        let expected = -20.0 - noise_db;
+        assert!((snr - expected).abs() < 2.0, "{} vs {}", snr, expected);
+    }
+
+    #[test]
+    fn floor_rises_at_the_limited_rate() {
+        let mut floor = NoiseFloor::new(6.0);
+        floor.update(&[1e-6], 0.1);
+        for _ in 0..10 {
+            floor.update(&[1.0], 0.1);
+        }
+        let db = 10.0 * floor.floor()[0].log10();
+        assert!((db - (-60.0 + 6.0)).abs() < 0.01, "{}", db);
+    }
+}
@@ -0,0 +1,60 @@
+/// Locates the largest bin in `levels` and refines it to a fractional bin
+/// index by fitting a parabola through it and its two neighbours.
+///
+/// `levels` should be on a log scale (dB), where a windowed tone's main
+/// lobe is close to parabolic. Peaks on the first or last bin have only one
+/// neighbour and are returned unrefined. Returns `None` for empty input.
+pub fn interpolate_peak(levels: &[f32]) -> Option<f32> {
+    let (k, &peak) = levels
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))?;
+    if k == 0 || k + 1 == levels.len() {
+        return Some(k as f32);
+    }
+
+    let (left, right) = (levels[k - 1], levels[k + 1]);
+    let denom = left - 2.0 * peak + right;
+    if denom.abs() < f32::EPSILON {
+        return Some(k as f32);
+    }
+
+    //
+    // Vertex of the parabola, always within half a bin of `k`.
+    //
+    let offset = 0.5 * (left - right) / denom;
+    Some(k as f32 + offset.clamp(-0.5, 0.5))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::window::WindowFunction;
+    use crate::fft::find_rdft;
+    use num_complex::Complex32;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn refines_an_off_bin_tone() {
+        let n = 1024;
+        let window = WindowFunction::Hann.coefficients(n);
+        let bin = 100.3;
+        let input: Vec<f32> = (0..n)
+            .map(|i| (2.0 * PI * bin * i as f32 / n as f32).sin() * window[i])
+            .collect();
+
+        let mut spectrum = vec![Complex32::default(); n / 2 + 1];
+        find_rdft(n).xform_real(&input, &mut spectrum);
+        let levels: Vec<f32> = spectrum.iter().map(|c| 20.0 * c.norm().log10()).collect();
+
+        let found = interpolate_peak(&levels).unwrap();
+        assert!((found - bin).abs() < 0.05, "found {}", found);
+
+        //
+        // Edge peaks are not interpolated.
+        //
+        assert_eq!(interpolate_peak(&[3.0, 1.0, 0.0]), Some(0.0));
+        assert_eq!(interpolate_peak(&[0.0, 1.0, 3.0]), Some(2.0));
+        assert_eq!(interpolate_peak(&[]), None);
+    }
+}
@@ -0,0 +1,49 @@
+use std::f32::consts::{PI, TAU};
+
+/// Unwraps per-bin phase in place: wherever neighbouring bins differ by
+/// more than π, a multiple of 2π is added to the later bins so the curve
+/// runs on continuously instead of folding back into (-π, π].
+///
+/// The first bin is left as is, so the result starts in (-π, π] and may
+/// drift far outside it. Bins dominated by noise have arbitrary phase and
+/// make the unwrapped curve wander, so mask them before reading slopes.
+pub fn unwrap(phase: &mut [f32]) {
+    let mut offset = 0.0;
+    let mut previous = match phase.first() {
+        Some(&p) => p,
+        None => return,
+    };
+    for p in phase.iter_mut().skip(1) {
+        let raw = *p;
+        let jump = raw - previous;
+        if jump > PI {
+            offset -= TAU * ((jump - PI) / TAU).ceil();
+        } else if jump < -PI {
+            offset += TAU * ((-jump - PI) / TAU).ceil();
+        }
+        previous = raw;
+        *p = raw + offset;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapped_ramp_unwraps_to_a_line() {
+        //
+        // A delay of d samples gives phase -2π·k·d/n at bin k, which wraps
+        // many times across the band.
+        //
+        let (n, delay) = (256, 9.5);
+        let truth: Vec<f32> = (0..n / 2)
+            .map(|k| -TAU * k as f32 * delay / n as f32)
+            .collect();
+        let mut phase: Vec<f32> = truth.iter().map(|&p| p.sin().atan2(p.cos())).collect();
+        unwrap(&mut phase);
+        for (k, (&got, &want)) in phase.iter().zip(&truth).enumerate() {
+            assert!((got - want).abs() < 1e-3, "bin {}: {} vs {}", k, got, want);
+        }
+    }
+}
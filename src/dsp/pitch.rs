@@ -0,0 +1,173 @@
+use std::fmt;
+
+/// Standard concert pitch for A4, in Hz.
+pub const DEFAULT_A4_HZ: f32 = 440.0;
+
+const NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+//
+// MIDI note range the names cover, C-1 (8.18 Hz) to G9 (12.5 kHz).
+//
+const LOWEST_NOTE: i32 = 0;
+const HIGHEST_NOTE: i32 = 127;
+
+/// The equal-tempered note nearest a frequency. Displays as, e.g.,
+/// "A4 +3 cents".
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Note {
+    /// MIDI note number; 69 is A4.
+    pub number: i32,
+    /// Offset from the note, in [-50, 50] cents.
+    pub cents: f32,
+}
+
+impl Note {
+    /// Note name with sharps, e.g. "C#".
+    pub fn name(&self) -> &'static str {
+        NAMES[self.number.rem_euclid(12) as usize]
+    }
+
+    /// Scientific pitch octave; middle C is C4.
+    pub fn octave(&self) -> i32 {
+        self.number.div_euclid(12) - 1
+    }
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} {:+} cents",
+            self.name(),
+            self.octave(),
+            self.cents.round() as i32
+        )
+    }
+}
+
+/// Maps `hz` to its nearest note in twelve-tone equal temperament tuned
+/// to `a4_hz`. Returns `None` for frequencies that round to a note outside
+/// C-1..=G9, and for non-positive or non-finite input.
+pub fn nearest_note(hz: f32, a4_hz: f32) -> Option<Note> {
+    let semitones = 69.0 + 12.0 * (hz / a4_hz).log2();
+    if !semitones.is_finite() {
+        return None;
+    }
+    let number = semitones.round();
+    if number < LOWEST_NOTE as f32 || number > HIGHEST_NOTE as f32 {
+        return None;
+    }
+    Some(Note {
+        number: number as i32,
+        cents: (semitones - number) * 100.0,
+    })
+}
+
+/// Exponential smoothing of a pitch track on the semitone scale, for a
+/// tuner reading that does not jitter. A jump of more than a semitone is
+/// taken as a new note and followed at once instead of gliding to it.
+pub struct PitchSmoother {
+    time_constant: f32,
+
+    //
+    // Smoothed pitch in semitones above 1 Hz, once there is a reading.
+    //
+    semitones: Option<f32>,
+}
+
+impl PitchSmoother {
+    /// Smooths with a time constant of `time_constant` seconds.
+    pub fn new(time_constant: f32) -> Self {
+        Self {
+            time_constant,
+            semitones: None,
+        }
+    }
+
+    /// Folds in a reading of `hz` taken `dt` seconds after the previous
+    /// one and returns the smoothed frequency.
+    pub fn update(&mut self, hz: f32, dt: f32) -> f32 {
+        let target = 12.0 * hz.log2();
+        let semitones = match self.semitones {
+            Some(current) if (target - current).abs() <= 1.0 => {
+                let alpha = 1.0 - (-dt / self.time_constant.max(1e-6)).exp();
+                current + (target - current) * alpha
+            }
+            _ => target,
+        };
+        self.semitones = Some(semitones);
+        (semitones / 12.0).exp2()
+    }
+
+    /// Forgets the current reading, e.g. after the signal drops out.
+    pub fn reset(&mut self) {
+        self.semitones = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference_pitches_are_named() {
+        let a4 = nearest_note(440.0, DEFAULT_A4_HZ).unwrap();
+        assert_eq!((a4.name(), a4.octave()), ("A", 4));
+        assert_eq!(a4.to_string(), "A4 +0 cents");
+
+        let c4 = nearest_note(261.63, DEFAULT_A4_HZ).unwrap();
+        assert_eq!((c4.name(), c4.octave()), ("C", 4));
+        assert!(c4.cents.abs() < 0.1, "{} cents", c4.cents);
+
+        //
+        // 3 cents sharp of A4, and the same pitch against a 442 Hz A4.
+        //
+        let sharp = 440.0 * 2f32.powf(3.0 / 1200.0);
+        assert_eq!(
+            nearest_note(sharp, 440.0).unwrap().to_string(),
+            "A4 +3 cents"
+        );
+        assert_eq!(
+            nearest_note(sharp, 442.0).unwrap().to_string(),
+            "A4 -5 cents"
+        );
+        assert_eq!(
+            nearest_note(30.87, 440.0).unwrap().to_string(),
+            "B0 +0 cents"
+        );
+    }
+
+    #[test]
+    fn smoothing_settles_and_snaps_to_new_notes() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-3 * b;
+        let mut smoother = PitchSmoother::new(0.1);
+        assert!(close(smoother.update(440.0, 0.01), 440.0));
+
+        //
+        // A 10-cent step is approached gradually and reached after many
+        // time constants.
+        //
+        let sharp = 440.0 * 2f32.powf(10.0 / 1200.0);
+        let first = smoother.update(sharp, 0.01);
+        assert!(first > 440.0 && first < sharp);
+        for _ in 0..200 {
+            smoother.update(sharp, 0.01);
+        }
+        assert!(close(smoother.update(sharp, 0.01), sharp));
+
+        assert!(close(smoother.update(660.0, 0.01), 660.0));
+        smoother.reset();
+        assert!(close(smoother.update(100.0, 0.01), 100.0));
+    }
+
+    #[test]
+    fn out_of_range_frequencies_have_no_note() {
+        assert!(nearest_note(8.2, DEFAULT_A4_HZ).is_some());
+        assert_eq!(nearest_note(7.0, DEFAULT_A4_HZ), None);
+        assert_eq!(nearest_note(20000.0, DEFAULT_A4_HZ), None);
+        assert_eq!(nearest_note(0.0, DEFAULT_A4_HZ), None);
+        assert_eq!(nearest_note(f32::NAN, DEFAULT_A4_HZ), None);
+    }
+}
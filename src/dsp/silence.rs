@@ -0,0 +1,132 @@
+use std::time::{Duration, Instant};
+
+/// Silence detector with hysteresis.
+///
+/// A signal has to rise above `exit_db` to leave the silent state, while the
+/// silent state is only entered after the level stays below `enter_db` for
+/// `timeout`. Keeping `exit_db` above `enter_db` stops the state from
+/// flickering when the input hovers around a single threshold.
+pub struct SilenceDetector {
+    pub enter_db: f32,
+    pub exit_db: f32,
+    pub timeout: Duration,
+    last_signal: Instant,
+    is_silence: bool,
+}
+
+impl SilenceDetector {
+    pub fn new() -> Self {
+        Self {
+            enter_db: -80.0,
+            exit_db: -74.0,
+            timeout: Duration::from_secs(2),
+            last_signal: Instant::now(),
+            is_silence: true,
+        }
+    }
+
+    /// Feeds the peak absolute sample of the latest batch and returns the
+    /// updated state.
+    pub fn update(&mut self, peak: f32) -> bool {
+        let level_db = 20.0 * peak.max(1e-9).log10();
+        let exit_db = self.exit_db.max(self.enter_db);
+
+        if self.is_silence {
+            //
+            // Leave silence only once the signal clears the upper threshold.
+            //
+            if level_db > exit_db {
+                self.is_silence = false;
+                self.last_signal = Instant::now();
+            }
+        } else if level_db > self.enter_db {
+            self.last_signal = Instant::now();
+        } else if self.last_signal.elapsed() > self.timeout {
+            self.is_silence = true;
+        }
+
+        self.is_silence
+    }
+
+    pub fn is_silence(&self) -> bool {
+        self.is_silence
+    }
+}
+
+impl Default for SilenceDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //
+    // Linear peak of a level in dBFS.
+    //
+    fn peak(db: f32) -> f32 {
+        10f32.powf(db / 20.0)
+    }
+
+    //
+    // Moves the last signal back by `secs`, as if that long had passed.
+    //
+    fn age(detector: &mut SilenceDetector, secs: u64) {
+        detector.last_signal = Instant::now() - Duration::from_secs(secs);
+    }
+
+    #[test]
+    fn leaves_silence_only_above_the_exit_threshold() {
+        let mut detector = SilenceDetector::new();
+        assert!(detector.is_silence());
+
+        //
+        // Between the thresholds stays silent; above exit_db leaves.
+        //
+        assert!(detector.update(peak(-77.0)));
+        assert!(!detector.update(peak(-70.0)));
+    }
+
+    #[test]
+    fn enters_silence_after_the_hold_time() {
+        let mut detector = SilenceDetector::new();
+        detector.update(peak(-20.0));
+
+        //
+        // Quiet input within the timeout keeps the signal state.
+        //
+        assert!(!detector.update(peak(-100.0)));
+        age(&mut detector, 1);
+        assert!(!detector.update(peak(-100.0)));
+
+        age(&mut detector, 3);
+        assert!(detector.update(peak(-100.0)));
+    }
+
+    #[test]
+    fn level_above_enter_restarts_the_hold_time() {
+        let mut detector = SilenceDetector::new();
+        detector.update(peak(-20.0));
+
+        //
+        // A level between the thresholds doesn't leave silence, but while
+        // signal is present it counts as signal.
+        //
+        age(&mut detector, 3);
+        assert!(!detector.update(peak(-77.0)));
+        assert!(!detector.update(peak(-100.0)));
+    }
+
+    #[test]
+    fn recovers_from_silence() {
+        let mut detector = SilenceDetector::new();
+        detector.update(peak(-20.0));
+        age(&mut detector, 3);
+        assert!(detector.update(peak(-100.0)));
+
+        assert!(!detector.update(peak(-20.0)));
+        assert!(!detector.update(peak(-100.0)));
+    }
+}
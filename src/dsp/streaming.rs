@@ -0,0 +1,115 @@
+use super::window::WindowFunction;
+use crate::fft::{find_dft, DFTBase, Direction};
+use num_complex::Complex32;
+use std::sync::Arc;
+
+/// Short-time Fourier transform over a sample iterator.
+///
+/// Each item is the full `n`-bin spectrum (unnormalized, as from
+/// `find_dft`) of one windowed frame. Frames start every `hop` samples,
+/// so `hop < n` overlaps them. The first frame needs `n` samples, which is
+/// one window of latency; after that, every `hop` new samples produce a
+/// frame. A trailing partial hop is not transformed, so `len` samples give
+/// `1 + (len - n) / hop` frames, or none when `len < n`.
+///
+/// ```
+/// use fftanalyzer::dsp::streaming::StreamingFft;
+/// use fftanalyzer::dsp::window::WindowFunction;
+///
+/// let samples = (0..4096).map(|i| (i as f32 * 0.1).sin());
+/// let frames = StreamingFft::new(samples, 1024, 512, WindowFunction::Hann);
+/// assert_eq!(frames.count(), 7);
+/// ```
+pub struct StreamingFft<I> {
+    samples: I,
+    n: usize,
+    hop: usize,
+    window: Vec<f32>,
+    plan: Arc<dyn DFTBase>,
+
+    //
+    // The last `n` samples, oldest first, or fewer before the first frame.
+    //
+    frame: Vec<f32>,
+}
+
+impl<I: Iterator<Item = f32>> StreamingFft<I> {
+    /// Frames `samples` into windows of `n` samples advanced by `hop`.
+    pub fn new(
+        samples: impl IntoIterator<IntoIter = I>,
+        n: usize,
+        hop: usize,
+        window_fn: WindowFunction,
+    ) -> Self {
+        assert!(hop > 0 && hop <= n, "STFT hop must be in 1..={}", n);
+        Self {
+            samples: samples.into_iter(),
+            n,
+            hop,
+            window: window_fn.coefficients(n),
+            plan: find_dft(n, Direction::Forward),
+            frame: Vec::with_capacity(n),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub fn hop(&self) -> usize {
+        self.hop
+    }
+}
+
+impl<I: Iterator<Item = f32>> Iterator for StreamingFft<I> {
+    type Item = Vec<Complex32>;
+
+    fn next(&mut self) -> Option<Vec<Complex32>> {
+        //
+        // Drop the oldest hop once a frame has gone out, then top up to a
+        // full window.
+        //
+        if self.frame.len() == self.n {
+            self.frame.drain(..self.hop);
+        }
+        while self.frame.len() < self.n {
+            self.frame.push(self.samples.next()?);
+        }
+
+        let mut spectrum: Vec<Complex32> = self
+            .frame
+            .iter()
+            .zip(&self.window)
+            .map(|(&x, &w)| Complex32::new(x * w, 0.0))
+            .collect();
+        self.plan.xform_inplace(&mut spectrum);
+        Some(spectrum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn frame_count_follows_the_input_length() {
+        let (n, hop) = (64, 16);
+        for (len, frames) in [(0, 0), (63, 0), (64, 1), (79, 1), (80, 2), (200, 9)] {
+            let stft = StreamingFft::new(vec![0.0; len], n, hop, WindowFunction::Hann);
+            assert_eq!(stft.count(), frames, "{} samples", len);
+        }
+    }
+
+    #[test]
+    fn every_frame_finds_the_tone() {
+        let n = 64;
+        let tone = (0..256).map(|i| (2.0 * PI * 4.0 * i as f32 / n as f32).sin());
+        for spectrum in StreamingFft::new(tone, n, 24, WindowFunction::Hann) {
+            let peak = (0..n / 2)
+                .max_by(|&a, &b| spectrum[a].norm().total_cmp(&spectrum[b].norm()))
+                .unwrap();
+            assert_eq!(peak, 4);
+        }
+    }
+}
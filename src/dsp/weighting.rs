@@ -0,0 +1,74 @@
+use crate::fft;
+
+// Pole frequencies of the IEC 61672-1 weighting curves, in Hz.
+const F1: f64 = 20.598_997;
+const F2: f64 = 107.652_65;
+const F3: f64 = 737.862_23;
+const F4: f64 = 12_194.217;
+
+// Gain reported where a curve has a zero (DC), so sums stay finite.
+const MIN_GAIN_DB: f32 = -200.0;
+
+/// Frequency weighting applied to displayed levels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Weighting {
+    #[default]
+    None,
+    A,
+    C,
+}
+
+impl Weighting {
+    pub const ALL: [Weighting; 3] = [Weighting::None, Weighting::A, Weighting::C];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Weighting::None => "None (Z)",
+            Weighting::A => "A",
+            Weighting::C => "C",
+        }
+    }
+
+    /// IEC 61672-1 gain in dB at `hz`, normalized to 0 dB at 1 kHz.
+    pub fn gain_db(self, hz: f32) -> f32 {
+        let f2 = (hz as f64).powi(2);
+        let (response, offset) = match self {
+            Weighting::None => return 0.0,
+            Weighting::A => (
+                F4 * F4 * f2 * f2
+                    / ((f2 + F1 * F1) * ((f2 + F2 * F2) * (f2 + F3 * F3)).sqrt() * (f2 + F4 * F4)),
+                2.0,
+            ),
+            Weighting::C => (F4 * F4 * f2 / ((f2 + F1 * F1) * (f2 + F4 * F4)), 0.062),
+        };
+        ((20.0 * response.log10() + offset) as f32).max(MIN_GAIN_DB)
+    }
+
+    /// Gain for the center frequency of each of the `n / 2` bins of an
+    /// `n`-point FFT at `sample_rate`, to add to per-bin dB levels.
+    pub fn bin_gains_db(self, sample_rate: u32, n: usize) -> Vec<f32> {
+        (0..n / 2)
+            .map(|bin| self.gain_db(fft::bin_to_hz(bin as f32, sample_rate, n)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_match_iec_61672_table_values() {
+        assert!(Weighting::A.gain_db(1000.0).abs() < 0.01);
+        assert!((Weighting::A.gain_db(100.0) + 19.1).abs() < 0.05);
+        assert!((Weighting::A.gain_db(10_000.0) + 2.5).abs() < 0.05);
+        assert!(Weighting::C.gain_db(1000.0).abs() < 0.01);
+        assert!((Weighting::C.gain_db(31.5) + 3.0).abs() < 0.05);
+        assert_eq!(Weighting::None.gain_db(100.0), 0.0);
+
+        let gains = Weighting::A.bin_gains_db(48000, 48);
+        assert_eq!(gains.len(), 24);
+        assert_eq!(gains[0], MIN_GAIN_DB);
+        assert!(gains[1].abs() < 0.01);
+    }
+}
@@ -0,0 +1,144 @@
+use super::window::{WindowFunction, WindowGains};
+use crate::fft::{find_dft, Direction};
+use num_complex::Complex32;
+
+/// One-sided power spectral density of `samples` by Welch's method, in
+/// squared input units per Hz, for bins `0..=segment_len / 2`.
+///
+/// The input is cut into `segment_len`-sample segments starting
+/// `segment_len - overlap` apart. Each is windowed and transformed, and the
+/// periodograms are averaged, which lowers the variance of the estimate by
+/// about the number of segments. Dividing by the window's ENBW makes the
+/// level independent of the window: white noise of variance σ² reads 2σ²/fs
+/// in every bin but DC and Nyquist, which are not folded and read σ²/fs.
+///
+/// Returns an empty vector when `samples` is shorter than one segment.
+/// Panics if `overlap` is not less than `segment_len`.
+pub fn welch_psd(
+    samples: &[f32],
+    sample_rate: u32,
+    segment_len: usize,
+    overlap: usize,
+    window: WindowFunction,
+) -> Vec<f32> {
+    assert!(
+        overlap < segment_len,
+        "Welch overlap of {} needs segments longer than that, got {}",
+        overlap,
+        segment_len
+    );
+    if samples.len() < segment_len {
+        return Vec::new();
+    }
+    let n = segment_len;
+    let step = n - overlap;
+    let coefficients = window.coefficients(n);
+    let plan = find_dft(n, Direction::Forward);
+
+    let mut input = vec![Complex32::new(0.0, 0.0); n];
+    let mut output = vec![Complex32::new(0.0, 0.0); n];
+    let mut sums = vec![0.0f64; n / 2 + 1];
+    let mut segments = 0;
+    for segment in samples.windows(n).step_by(step) {
+        for ((x, &s), &w) in input.iter_mut().zip(segment).zip(&coefficients) {
+            *x = Complex32::new(s * w, 0.0);
+        }
+        plan.xform(&input, &mut output);
+        for (sum, bin) in sums.iter_mut().zip(&output) {
+            *sum += bin.norm_sqr() as f64;
+        }
+        segments += 1;
+    }
+
+    //
+    // |X|² / (Σw)² is the window's power gain for a tone; per Hz it is
+    // spread over the ENBW. Folding the negative frequencies onto the
+    // positive ones doubles every bin that has a mirror image.
+    //
+    let gains = WindowGains::of(&coefficients);
+    let sum_w = gains.coherent_gain as f64 * n as f64;
+    let enbw_hz = gains.enbw_bins as f64 * sample_rate as f64 / n as f64;
+    let scale = 1.0 / (segments as f64 * sum_w * sum_w * enbw_hz);
+    sums.iter()
+        .enumerate()
+        .map(|(k, &sum)| {
+            let fold = if k == 0 || 2 * k == n { 1.0 } else { 2.0 };
+            (sum * scale * fold) as f32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uniform_noise(len: usize) -> Vec<f32> {
+        let mut state = 1u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as f32 / u32::MAX as f32 * 2.0 - 1.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn white_noise_reads_its_variance() {
+        //
+        // Uniform noise in [-1, 1] has variance 1/3, so its one-sided
+        // density is 2σ² / fs, and the bins integrate back to σ².
+        //
+        let (n, rate) = (512, 48000);
+        let noise = uniform_noise(256 * n);
+        let variance = 1.0 / 3.0;
+        for window in [
+            WindowFunction::Rectangular,
+            WindowFunction::Hann,
+            WindowFunction::BlackmanHarris,
+        ] {
+            let psd = welch_psd(&noise, rate, n, n / 2, window);
+            assert_eq!(psd.len(), n / 2 + 1);
+            let interior = &psd[1..n / 2];
+            let mean = interior.iter().map(|&p| p as f64).sum::<f64>() / interior.len() as f64;
+            let expected = 2.0 * variance / rate as f64;
+            assert!(
+                (mean / expected - 1.0).abs() < 0.02,
+                "{:?}: {} vs {}",
+                window,
+                mean,
+                expected
+            );
+
+            let total: f64 = psd.iter().map(|&p| p as f64).sum::<f64>() * rate as f64 / n as f64;
+            assert!(
+                (total / variance - 1.0).abs() < 0.02,
+                "{:?}: {}",
+                window,
+                total
+            );
+        }
+    }
+
+    #[test]
+    fn more_segments_lower_the_variance() {
+        let n = 256;
+        let noise = uniform_noise(64 * n);
+        let spread = |psd: &[f32]| {
+            let interior = &psd[1..n / 2];
+            let mean = interior.iter().sum::<f32>() / interior.len() as f32;
+            let var =
+                interior.iter().map(|&p| (p - mean).powi(2)).sum::<f32>() / interior.len() as f32;
+            var.sqrt() / mean
+        };
+        let single = welch_psd(&noise[..n], 8000, n, 0, WindowFunction::Hann);
+        let many = welch_psd(&noise, 8000, n, n / 2, WindowFunction::Hann);
+        assert!(spread(&many) < spread(&single) / 4.0);
+    }
+
+    #[test]
+    fn input_shorter_than_a_segment_is_empty() {
+        assert!(welch_psd(&[0.0; 100], 8000, 128, 64, WindowFunction::Hann).is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+use std::f32::consts::PI;
+
+/// Kaiser β listed in `WindowFunction::ALL`, with side lobes close to
+/// Blackman's.
+pub const DEFAULT_KAISER_BETA: f32 = 8.6;
+
+/// Analysis window applied to each frame before the FFT.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WindowFunction {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+    BlackmanHarris,
+    FlatTop,
+    /// Kaiser-Bessel window. `beta` trades main-lobe width for side-lobe
+    /// level: 0 is rectangular, and each step up widens the main lobe and
+    /// lowers the side lobes.
+    Kaiser {
+        beta: f32,
+    },
+}
+
+impl WindowFunction {
+    pub const ALL: [WindowFunction; 7] = [
+        WindowFunction::Rectangular,
+        WindowFunction::Hann,
+        WindowFunction::Hamming,
+        WindowFunction::Blackman,
+        WindowFunction::BlackmanHarris,
+        WindowFunction::FlatTop,
+        WindowFunction::Kaiser {
+            beta: DEFAULT_KAISER_BETA,
+        },
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WindowFunction::Rectangular => "Rectangular",
+            WindowFunction::Hann => "Hann",
+            WindowFunction::Hamming => "Hamming",
+            WindowFunction::Blackman => "Blackman",
+            WindowFunction::BlackmanHarris => "Blackman-Harris",
+            WindowFunction::FlatTop => "Flat top",
+            WindowFunction::Kaiser { .. } => "Kaiser",
+        }
+    }
+
+    /// Cosine-sum coefficients `a_k` of `w[i] = Σ (-1)^k a_k cos(2πki / (n-1))`,
+    /// empty for Kaiser, which is not a cosine sum.
+    fn terms(self) -> &'static [f32] {
+        match self {
+            WindowFunction::Rectangular => &[1.0],
+            WindowFunction::Hann => &[0.5, 0.5],
+            WindowFunction::Hamming => &[0.54, 0.46],
+            WindowFunction::Blackman => &[0.42, 0.5, 0.08],
+            WindowFunction::BlackmanHarris => &[0.35875, 0.48829, 0.14128, 0.01168],
+            WindowFunction::FlatTop => &[
+                0.215_578_95,
+                0.416_631_58,
+                0.277_263_16,
+                0.083_578_95,
+                0.006_947_37,
+            ],
+            WindowFunction::Kaiser { .. } => &[],
+        }
+    }
+
+    /// Mean window value, i.e. the amplitude a full-scale sinusoid keeps
+    /// after windowing, in closed form for long windows. For Kaiser this is
+    /// the limit sinh(β) / (β·I0(β)). `WindowGains::of` measures the exact
+    /// value for a given length, which is what the engine calibrates with.
+    pub fn coherent_gain(self) -> f32 {
+        match self {
+            WindowFunction::Kaiser { beta } => {
+                let beta = beta.abs() as f64;
+                if beta < 1e-6 {
+                    1.0
+                } else {
+                    (beta.sinh() / (beta * bessel_i0(beta))) as f32
+                }
+            }
+            _ => self.terms()[0],
+        }
+    }
+
+    /// Symmetric window of length `n`.
+    pub fn coefficients(self, n: usize) -> Vec<f32> {
+        if let WindowFunction::Kaiser { beta } = self {
+            return kaiser(n, beta);
+        }
+        let terms = self.terms();
+        let denom = n.saturating_sub(1).max(1) as f32;
+        (0..n)
+            .map(|i| {
+                let phase = 2.0 * PI * i as f32 / denom;
+                terms
+                    .iter()
+                    .enumerate()
+                    .map(|(k, &a)| {
+                        let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+                        sign * a * (k as f32 * phase).cos()
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+/// Calibration figures of one coefficient table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowGains {
+    /// Mean coefficient, Σw / N: the amplitude a bin-centered sinusoid
+    /// keeps. Divide tonal magnitudes by this.
+    pub coherent_gain: f32,
+    /// Equivalent noise bandwidth in bins, N·Σw² / (Σw)²: the width of the
+    /// rectangular filter that passes the same noise power. Divide tonally
+    /// calibrated noise power by this times the bin width for a density.
+    pub enbw_bins: f32,
+}
+
+impl WindowGains {
+    pub fn of(window: &[f32]) -> Self {
+        let n = window.len().max(1) as f64;
+        let sum: f64 = window.iter().map(|&w| w as f64).sum();
+        let sum_sq: f64 = window.iter().map(|&w| w as f64 * w as f64).sum();
+        Self {
+            coherent_gain: (sum / n) as f32,
+            enbw_bins: (n * sum_sq / (sum * sum).max(f64::MIN_POSITIVE)) as f32,
+        }
+    }
+}
+
+/// `w[i] = I0(β·√(1 - x²)) / I0(β)` with `x` running from -1 to 1 across
+/// the window.
+fn kaiser(n: usize, beta: f32) -> Vec<f32> {
+    let beta = beta.abs() as f64;
+    let denom = n.saturating_sub(1).max(1) as f64;
+    let norm = bessel_i0(beta);
+    (0..n)
+        .map(|i| {
+            let x = 2.0 * i as f64 / denom - 1.0;
+            (bessel_i0(beta * (1.0 - x * x).max(0.0).sqrt()) / norm) as f32
+        })
+        .collect()
+}
+
+/// Zeroth-order modified Bessel function of the first kind, from its power
+/// series Σ ((x/2)^k / k!)², which converges quickly for window βs.
+fn bessel_i0(x: f64) -> f64 {
+    let half = x / 2.0;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for k in 1..500 {
+        term *= half / k as f64;
+        sum += term * term;
+        if term * term < sum * 1e-17 {
+            break;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coherent_gain_matches_published_constants() {
+        let published = [
+            (WindowFunction::Rectangular, 1.0),
+            (WindowFunction::Hann, 0.5),
+            (WindowFunction::Hamming, 0.54),
+            (WindowFunction::Blackman, 0.42),
+            (WindowFunction::BlackmanHarris, 0.35875),
+            (WindowFunction::FlatTop, 0.2156),
+        ];
+
+        for (window, gain) in published {
+            let n = 4096;
+            let mean = window.coefficients(n).iter().sum::<f32>() / n as f32;
+            assert!(
+                (mean - gain).abs() < 1e-3,
+                "{}: measured {} want {}",
+                window.name(),
+                mean,
+                gain
+            );
+            assert!((window.coherent_gain() - gain).abs() < 1e-4);
+        }
+    }
+
+    /// Highest side lobe in dB relative to the main lobe, from the window's
+    /// spectrum sampled 16 times per bin out to the middle of the band.
+    fn peak_side_lobe_db(window: &[f32]) -> f64 {
+        let n = window.len();
+        let levels: Vec<f64> = (0..n * 8)
+            .map(|k| {
+                let omega = 2.0 * std::f64::consts::PI * k as f64 / (16 * n) as f64;
+                let (re, im) = window
+                    .iter()
+                    .enumerate()
+                    .fold((0.0, 0.0), |(re, im), (i, &w)| {
+                        let phase = omega * i as f64;
+                        (re + w as f64 * phase.cos(), im - w as f64 * phase.sin())
+                    });
+                re.hypot(im)
+            })
+            .collect();
+
+        //
+        // The main lobe ends at the first minimum; the side lobes follow.
+        //
+        let edge = (1..levels.len())
+            .find(|&k| levels[k] > levels[k - 1])
+            .unwrap();
+        let side = levels[edge..].iter().cloned().fold(0.0, f64::max);
+        20.0 * (side / levels[0]).log10()
+    }
+
+    #[test]
+    fn kaiser_with_small_beta_is_rectangular() {
+        let window = WindowFunction::Kaiser { beta: 1e-3 }.coefficients(64);
+        assert!(window.iter().all(|&w| (w - 1.0).abs() < 1e-6));
+        assert!((WindowFunction::Kaiser { beta: 0.0 }.coherent_gain() - 1.0).abs() < 1e-6);
+
+        //
+        // A rectangular window's first side lobe sits 13.3 dB down.
+        //
+        let db = peak_side_lobe_db(&window);
+        assert!((db + 13.26).abs() < 0.2, "{:.2} dB", db);
+    }
+
+    #[test]
+    fn larger_kaiser_beta_lowers_side_lobes() {
+        let mut previous = 0.0;
+        for beta in [1.0, 3.0, 6.0, 9.0, 12.0] {
+            let window_fn = WindowFunction::Kaiser { beta };
+            let db = peak_side_lobe_db(&window_fn.coefficients(64));
+            assert!(db < previous - 5.0, "beta {}: {:.1} dB", beta, db);
+            previous = db;
+
+            let n = 4096;
+            let mean = window_fn.coefficients(n).iter().sum::<f32>() / n as f32;
+            assert!(
+                (mean - window_fn.coherent_gain()).abs() < 1e-3,
+                "beta {}",
+                beta
+            );
+        }
+    }
+
+    #[test]
+    fn hann_gains_match_published_constants() {
+        let gains = WindowGains::of(&WindowFunction::Hann.coefficients(4096));
+        assert!((gains.coherent_gain - 0.5).abs() < 1e-3);
+        assert!((gains.enbw_bins - 1.5).abs() < 1e-3);
+
+        let rect = WindowGains::of(&WindowFunction::Rectangular.coefficients(64));
+        assert_eq!(rect.coherent_gain, 1.0);
+        assert_eq!(rect.enbw_bins, 1.0);
+    }
+}
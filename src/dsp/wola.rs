@@ -0,0 +1,230 @@
+use crate::fft::{find_dft, DFTBase, Direction};
+use num_complex::Complex32;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+/// Weighted overlap-add (WOLA) analysis/resynthesis for modified spectra.
+///
+/// Frames are taken every `hop` samples, windowed with a sqrt-Hann analysis
+/// window and transformed. After the caller edits the spectrum, each frame is
+/// inverse-transformed, weighted by the matching synthesis window and summed
+/// into an accumulator. The overlapping analysis*synthesis window sum is
+/// divided out per sample (COLA normalization), so an unmodified spectrum
+/// reconstructs the input and edited spectra cross-fade without clicks at
+/// frame boundaries.
+///
+/// ```
+/// use fftanalyzer::dsp::wola::Wola;
+/// use num_complex::Complex32;
+///
+/// // Band-pass: keep bins 10..40 and their mirror images in every frame.
+/// let (n, hop) = (256, 64);
+/// let input: Vec<f32> = (0..4096).map(|i| (i as f32 * 0.3).sin()).collect();
+/// let mut wola = Wola::new(n, hop);
+/// let mut spectrum = vec![Complex32::default(); n];
+/// let mut output = vec![0.0; input.len()];
+/// for start in (0..=input.len() - n).step_by(hop) {
+///     wola.analyze(&input[start..start + n], &mut spectrum);
+///     for (k, bin) in spectrum.iter_mut().enumerate() {
+///         if !(10..40).contains(&k.min(n - k)) {
+///             *bin = Complex32::default();
+///         }
+///     }
+///     wola.synthesize(&spectrum, &mut output[start..start + hop]);
+/// }
+/// ```
+pub struct Wola {
+    n: usize,
+    hop: usize,
+    fft_plan: Arc<dyn DFTBase>,
+    ifft_plan: Arc<dyn DFTBase>,
+    analysis: Vec<f32>,
+    synthesis: Vec<f32>,
+
+    //
+    // Overlapped window energy for each of the `hop` output positions.
+    //
+    norm: Vec<f32>,
+
+    //
+    // Pending overlap-add output, one frame long.
+    //
+    accum: Vec<f32>,
+    scratch: Vec<Complex32>,
+}
+
+impl Wola {
+    /// Creates a resynthesizer for frames of `n` samples advanced by `hop`.
+    pub fn new(n: usize, hop: usize) -> Self {
+        assert!(hop > 0 && hop <= n, "WOLA hop must be in 1..={}", n);
+
+        //
+        // Periodic sqrt-Hann for both analysis and synthesis, so their
+        // product is a Hann window that overlaps cleanly at common hops.
+        //
+        let window: Vec<f32> = (0..n)
+            .map(|i| (0.5 * (1.0 - (2.0 * PI * i as f32 / n as f32).cos())).sqrt())
+            .collect();
+
+        //
+        // Sum the window product over every frame covering each output position.
+        //
+        let mut norm = vec![0.0; hop];
+        for (j, v) in norm.iter_mut().enumerate() {
+            for k in (j..n).step_by(hop) {
+                *v += window[k] * window[k];
+            }
+        }
+
+        Self {
+            n,
+            hop,
+            fft_plan: find_dft(n, Direction::Forward),
+            ifft_plan: find_dft(n, Direction::Inverse),
+            analysis: window.clone(),
+            synthesis: window,
+            norm,
+            accum: vec![0.0; n],
+            scratch: vec![Complex32::default(); n],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub fn hop(&self) -> usize {
+        self.hop
+    }
+
+    /// Windows one frame of `n` samples and writes its `n`-bin spectrum.
+    pub fn analyze(&self, frame: &[f32], spectrum: &mut [Complex32]) {
+        for ((s, &x), &w) in spectrum.iter_mut().zip(frame).zip(&self.analysis) {
+            *s = Complex32::new(x * w, 0.0);
+        }
+        self.fft_plan.xform_inplace(&mut spectrum[..self.n]);
+    }
+
+    /// Overlap-adds one (possibly modified) spectrum and writes the next
+    /// `hop` fully reconstructed samples to `out`.
+    ///
+    /// Output lines up with the start of the frame passed to `analyze`, so a
+    /// caller that advances its input by `hop` per frame gets a gapless,
+    /// phase-aligned signal back. The first `n - hop` samples fade in while
+    /// the overlap fills.
+    pub fn synthesize(&mut self, spectrum: &[Complex32], out: &mut [f32]) {
+        let n = self.n;
+        let hop = self.hop;
+
+        //
+        // Unnormalized inverse DFT; the 1/n scale is applied while accumulating.
+        //
+        self.ifft_plan.xform(&spectrum[..n], &mut self.scratch);
+
+        //
+        // Apply synthesis window and accumulate the real part.
+        //
+        let scale = 1.0 / n as f32;
+        for ((a, s), &w) in self
+            .accum
+            .iter_mut()
+            .zip(&self.scratch)
+            .zip(&self.synthesis)
+        {
+            *a += s.re * scale * w;
+        }
+
+        //
+        // Emit the leading hop, which no later frame overlaps, then shift.
+        //
+        for ((o, &a), &w) in out.iter_mut().zip(&self.accum[..hop]).zip(&self.norm) {
+            *o = a / w.max(1e-9);
+        }
+        self.accum.copy_within(hop.., 0);
+        self.accum[n - hop..].fill(0.0);
+    }
+
+    /// Clears the overlap state, e.g. after a discontinuity in the input.
+    pub fn reset(&mut self) {
+        self.accum.fill(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //
+    // Runs `input` through analysis and resynthesis, scaling every bin by
+    // `gain`, and returns the output aligned with the input.
+    //
+    fn resynthesize(input: &[f32], n: usize, hop: usize, gain: f32) -> Vec<f32> {
+        let mut wola = Wola::new(n, hop);
+        let mut spectrum = vec![Complex32::default(); n];
+        let mut output = vec![0.0; input.len()];
+        for start in (0..=input.len() - n).step_by(hop) {
+            wola.analyze(&input[start..start + n], &mut spectrum);
+            spectrum.iter_mut().for_each(|s| *s *= gain);
+            wola.synthesize(&spectrum, &mut output[start..start + hop]);
+        }
+        output
+    }
+
+    fn signal(len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| {
+                let t = i as f32;
+                (0.05 * t).sin() + 0.5 * (0.71 * t).cos()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unmodified_spectra_reconstruct_the_input() {
+        //
+        // Once the overlap has filled, every sample up to the last
+        // emitted hop comes back.
+        //
+        let n = 256;
+        let input = signal(16 * n);
+        for hop in [n / 2, n / 4, 48] {
+            let output = resynthesize(&input, n, hop, 1.0);
+            let end = (input.len() - n) / hop * hop + hop;
+            for i in n - hop..end {
+                assert!(
+                    (output[i] - input[i]).abs() < 1e-4,
+                    "hop {} sample {}: {} vs {}",
+                    hop,
+                    i,
+                    output[i],
+                    input[i]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn scaled_spectra_scale_the_output() {
+        let n = 128;
+        let input = signal(8 * n);
+        let output = resynthesize(&input, n, n / 2, 0.5);
+        for i in n / 2..input.len() - n {
+            assert!((output[i] - 0.5 * input[i]).abs() < 1e-4, "sample {}", i);
+        }
+    }
+
+    #[test]
+    fn reset_clears_the_overlap() {
+        let n = 64;
+        let mut wola = Wola::new(n, n / 2);
+        let mut spectrum = vec![Complex32::default(); n];
+        let mut out = vec![0.0; n / 2];
+        wola.analyze(&signal(n), &mut spectrum);
+        wola.synthesize(&spectrum, &mut out);
+        wola.reset();
+
+        spectrum.fill(Complex32::default());
+        wola.synthesize(&spectrum, &mut out);
+        assert!(out.iter().all(|&s| s == 0.0));
+    }
+}
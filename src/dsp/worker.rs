@@ -0,0 +1,389 @@
+use super::engine::{EngineSettings, SpectrumEngine};
+use crate::fft;
+use ringbuf::{Consumer, HeapRb, Producer};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+//
+// How long the worker sleeps when no input is waiting, and how many
+// finished frames may queue for the reader before new ones are dropped.
+// As many spent frames may queue on the way back for reuse.
+//
+const IDLE_SLEEP: Duration = Duration::from_millis(1);
+const FRAME_QUEUE: usize = 64;
+
+type FrameQueue = HeapRb<SpectrumFrame>;
+type Command = Box<dyn FnOnce(&mut SpectrumEngine) + Send>;
+
+/// Everything a display needs from one engine frame, copied out so it can
+/// be read without holding the engine.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpectrumFrame {
+    /// FFT size and sample rate the frame was computed at.
+    pub dft_size: usize,
+    pub sample_rate: u32,
+    /// Input samples since the previous frame.
+    pub hop: usize,
+    /// `channel_power` of every channel, first channel first.
+    pub power: Vec<Vec<f32>>,
+    /// `channel_phase` of every channel.
+    pub phase: Vec<Vec<f32>>,
+    /// Weighting curve in effect, in dB per bin.
+    pub weighting_db: Vec<f32>,
+    pub dominant_hz: Option<f32>,
+    pub silent: bool,
+    /// `SpectrumEngine::enbw_hz` for the window in use.
+    pub enbw_hz: f32,
+    /// Whether `power` was single-sided, as `SpectrumEngine::single_sided`.
+    pub single_sided: bool,
+}
+
+impl SpectrumFrame {
+    /// Copies the engine's latest frame.
+    pub fn from_engine(engine: &SpectrumEngine) -> Self {
+        let mut frame = Self::default();
+        frame.copy_from(engine);
+        frame
+    }
+
+    /// Overwrites this frame with the engine's latest one, reusing its
+    /// buffers so a frame of the same size allocates nothing.
+    pub fn copy_from(&mut self, engine: &SpectrumEngine) {
+        let channels = 0..engine.channel_count();
+        self.dft_size = engine.dft_size();
+        self.sample_rate = engine.sample_rate();
+        self.hop = engine.hop();
+        copy_rows(
+            &mut self.power,
+            channels.clone().map(|i| engine.channel_power(i)),
+        );
+        copy_rows(&mut self.phase, channels.map(|i| engine.channel_phase(i)));
+        self.weighting_db.clear();
+        self.weighting_db.extend_from_slice(engine.weighting_db());
+        self.dominant_hz = engine.dominant_frequency();
+        self.silent = engine.silence().is_silence();
+        self.enbw_hz = engine.enbw_hz();
+        self.single_sided = engine.single_sided();
+    }
+
+    pub fn channel_count(&self) -> usize {
+        self.power.len()
+    }
+
+    /// Frequency spacing between adjacent bins.
+    pub fn bin_width_hz(&self) -> f32 {
+        fft::bin_width_hz(self.sample_rate, self.dft_size)
+    }
+
+    pub fn nyquist_hz(&self) -> f32 {
+        fft::nyquist_hz(self.sample_rate)
+    }
+
+    /// Center frequency of a (fractional) bin at the frame's FFT size.
+    pub fn bin_to_hz(&self, bin: f32) -> f32 {
+        fft::bin_to_hz(bin, self.sample_rate, self.dft_size)
+    }
+
+    /// Fractional bin at `hz` for the frame's FFT size.
+    pub fn hz_to_bin(&self, hz: f32) -> f32 {
+        fft::hz_to_bin(hz, self.sample_rate, self.dft_size)
+    }
+}
+
+fn copy_rows<'a>(rows: &mut Vec<Vec<f32>>, source: impl ExactSizeIterator<Item = &'a [f32]>) {
+    rows.resize_with(source.len(), Vec::new);
+    for (row, values) in rows.iter_mut().zip(source) {
+        row.clear();
+        row.extend_from_slice(values);
+    }
+}
+
+/// Level meter state the thread publishes after every pass, so the reader
+/// can draw it without waiting on the engine.
+#[derive(Default)]
+struct MeterReading {
+    level_db: AtomicU32,
+    clipped: AtomicBool,
+}
+
+/// Runs a `SpectrumEngine` on its own thread, so transform cost never
+/// holds up the reader.
+///
+/// The thread owns the engine. It drains the engine's ring buffers and
+/// pushes every finished frame into a lock-free queue read with
+/// `try_recv`; `recycle` hands spent frames back so their buffers are
+/// reused. Changes reach the engine through a command queue that the
+/// thread applies between frames, so no call here waits on a transform
+/// except `call`. The worker keeps its own copy of the engine's settings
+/// for reading. Dropping the worker stops the thread.
+pub struct SpectrumWorker {
+    commands: Sender<Command>,
+    settings: EngineSettings,
+    frames: Consumer<SpectrumFrame, Arc<FrameQueue>>,
+    spares: Producer<SpectrumFrame, Arc<FrameQueue>>,
+    meter: Arc<MeterReading>,
+    paused: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    dropped: Arc<AtomicUsize>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SpectrumWorker {
+    pub fn spawn(engine: SpectrumEngine) -> Self {
+        let settings = engine.settings();
+        let (commands, queue) = mpsc::channel();
+        let (producer, frames) = HeapRb::new(FRAME_QUEUE).split();
+        let (spares, spent) = HeapRb::new(FRAME_QUEUE).split();
+        let meter = Arc::new(MeterReading::default());
+        let paused = Arc::new(AtomicBool::new(false));
+        let stop = Arc::new(AtomicBool::new(false));
+        let dropped = Arc::new(AtomicUsize::new(0));
+
+        let thread = {
+            let meter = meter.clone();
+            let paused = paused.clone();
+            let stop = stop.clone();
+            let dropped = dropped.clone();
+            thread::spawn(move || {
+                run(
+                    engine, queue, producer, spent, &meter, &paused, &stop, &dropped,
+                )
+            })
+        };
+
+        Self {
+            commands,
+            settings,
+            frames,
+            spares,
+            meter,
+            paused,
+            stop,
+            dropped,
+            thread: Some(thread),
+        }
+    }
+
+    /// The engine's settings as last set here or read back by `call`.
+    pub fn settings(&self) -> &EngineSettings {
+        &self.settings
+    }
+
+    /// Queues `settings` for the engine, as `SpectrumEngine::apply_settings`,
+    /// if they differ from `settings()`. Frames already queued keep the
+    /// settings they were computed with.
+    pub fn set_settings(&mut self, settings: EngineSettings) {
+        if settings != self.settings {
+            self.settings = settings;
+            self.send(move |engine| engine.apply_settings(&settings));
+        }
+    }
+
+    /// Queues `command` to run on the engine before its next frame.
+    pub fn send(&self, command: impl FnOnce(&mut SpectrumEngine) + Send + 'static) {
+        //
+        // The thread only stops when the worker is dropped.
+        //
+        let _ = self.commands.send(Box::new(command));
+    }
+
+    /// Runs `f` on the engine after every queued command and waits for its
+    /// result, at most one transform. `settings()` is read back with it.
+    pub fn call<R: Send + 'static>(
+        &mut self,
+        f: impl FnOnce(&mut SpectrumEngine) -> R + Send + 'static,
+    ) -> R {
+        let (reply, result) = mpsc::sync_channel(1);
+        self.send(move |engine| {
+            let _ = reply.send((f(engine), engine.settings()));
+        });
+        let (value, settings) = result.recv().expect("spectrum worker thread stopped");
+        self.settings = settings;
+        value
+    }
+
+    /// Oldest finished frame not yet taken, if any.
+    pub fn try_recv(&mut self) -> Option<SpectrumFrame> {
+        self.frames.pop()
+    }
+
+    /// Returns a frame taken with `try_recv` for the thread to fill again.
+    pub fn recycle(&mut self, frame: SpectrumFrame) {
+        let _ = self.spares.push(frame);
+    }
+
+    /// `LevelMeter::level_db` and `clipped` as of the thread's last pass.
+    pub fn meter_level_db(&self) -> f32 {
+        f32::from_bits(self.meter.level_db.load(Ordering::Relaxed))
+    }
+
+    pub fn meter_clipped(&self) -> bool {
+        self.meter.clipped.load(Ordering::Relaxed)
+    }
+
+    /// Queues `LevelMeter::clear_clip`.
+    pub fn clear_clip(&self) {
+        self.send(|engine| engine.meter_mut().clear_clip());
+    }
+
+    /// While paused, input is drained without computing frames, as with
+    /// `SpectrumEngine::skip_input`.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Release);
+    }
+
+    /// Frames discarded so far because the queue was full.
+    pub fn dropped_frames(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for SpectrumWorker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The worker thread: applies queued commands, then computes one frame or
+/// drains the input while paused, until stopped.
+#[allow(clippy::too_many_arguments)]
+fn run(
+    mut engine: SpectrumEngine,
+    commands: Receiver<Command>,
+    mut frames: Producer<SpectrumFrame, Arc<FrameQueue>>,
+    mut spent: Consumer<SpectrumFrame, Arc<FrameQueue>>,
+    meter: &MeterReading,
+    paused: &AtomicBool,
+    stop: &AtomicBool,
+    dropped: &AtomicUsize,
+) {
+    while !stop.load(Ordering::Acquire) {
+        for command in commands.try_iter() {
+            command(&mut engine);
+        }
+        let ready = if paused.load(Ordering::Acquire) {
+            engine.skip_input();
+            false
+        } else {
+            engine.next_frame()
+        };
+        meter
+            .level_db
+            .store(engine.meter().level_db().to_bits(), Ordering::Relaxed);
+        meter
+            .clipped
+            .store(engine.meter().clipped(), Ordering::Relaxed);
+
+        if !ready {
+            thread::sleep(IDLE_SLEEP);
+            continue;
+        }
+        let mut frame = spent.pop().unwrap_or_default();
+        frame.copy_from(&engine);
+        if frames.push(frame).is_err() {
+            dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+    use std::time::Instant;
+
+    fn wait_for(mut ready: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !ready() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn frames_arrive_from_the_worker_thread() {
+        //
+        // Bin 32 of a 256-point frame; 1024 samples make 8 frames.
+        //
+        let n = 256;
+        let (mut producer, consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut worker = SpectrumWorker::spawn(SpectrumEngine::new(consumer, 8000, n));
+        for i in 0..4 * n {
+            let _ = producer.push((2.0 * PI * (32 * i) as f32 / n as f32).sin());
+        }
+
+        let mut frames = Vec::new();
+        wait_for(|| {
+            frames.extend(std::iter::from_fn(|| worker.try_recv()));
+            frames.len() >= 8
+        });
+        let last = frames.last().unwrap();
+        assert_eq!(last.dft_size, n);
+        assert_eq!(last.power.len(), 1);
+        assert!((last.dominant_hz.unwrap() - 1000.0).abs() < 1.0);
+        assert_eq!(worker.dropped_frames(), 0);
+    }
+
+    #[test]
+    fn pausing_drains_input_without_frames() {
+        let n = 256;
+        let (mut producer, consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut worker = SpectrumWorker::spawn(SpectrumEngine::new(consumer, 8000, n));
+        worker.set_paused(true);
+        thread::sleep(Duration::from_millis(20));
+        producer.push_slice(&vec![0.5; 4 * n]);
+
+        wait_for(|| producer.is_empty());
+        worker.set_paused(false);
+        thread::sleep(Duration::from_millis(20));
+        assert!(worker.try_recv().is_none());
+        assert_eq!(worker.call(|e| e.take_input_stats()).samples, 4 * n);
+    }
+
+    #[test]
+    fn settings_reach_the_thread_in_order() {
+        let n = 256;
+        let (_producer, consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut worker = SpectrumWorker::spawn(SpectrumEngine::new(consumer, 8000, n));
+        let mut settings = *worker.settings();
+        settings.hop = n / 4;
+        worker.set_settings(settings);
+        settings.input_gain_db = 12.0;
+        worker.set_settings(settings);
+
+        let applied = worker.call(|e| (e.hop(), e.input_gain_db()));
+        assert_eq!(applied, (n / 4, 12.0));
+        assert_eq!(*worker.settings(), settings);
+    }
+
+    #[test]
+    fn recycled_frames_are_refilled() {
+        let n = 256;
+        let (mut producer, consumer) = HeapRb::<f32>::new(4 * n).split();
+        let mut worker = SpectrumWorker::spawn(SpectrumEngine::new(consumer, 8000, n));
+        let mut spare = SpectrumFrame::from_engine(&SpectrumEngine::new(
+            HeapRb::<f32>::new(n).split().1,
+            8000,
+            n,
+        ));
+        spare.power[0].fill(7.0);
+        let buffer = spare.power[0].as_ptr();
+        worker.recycle(spare);
+        producer.push_slice(&vec![0.0; n]);
+
+        let mut frame = None;
+        wait_for(|| {
+            frame = worker.try_recv();
+            frame.is_some()
+        });
+        let frame = frame.unwrap();
+        assert_eq!(frame.power[0].as_ptr(), buffer);
+        assert!(frame.power[0].iter().all(|&p| p != 7.0));
+    }
+}
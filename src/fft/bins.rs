@@ -0,0 +1,42 @@
+//
+// Conversions between DFT bin indices and frequencies. Bins are fractional
+// where a caller interpolates between them; bin `k` of an `n`-point DFT at
+// `sample_rate` sits at `k * sample_rate / n` Hz.
+//
+
+/// Frequency spacing between adjacent bins of an `n`-point DFT.
+pub fn bin_width_hz(sample_rate: u32, n: usize) -> f32 {
+    sample_rate as f32 / n as f32
+}
+
+/// Highest representable frequency, half the sample rate.
+pub fn nyquist_hz(sample_rate: u32) -> f32 {
+    sample_rate as f32 / 2.0
+}
+
+/// Center frequency of (fractional) bin `bin`.
+pub fn bin_to_hz(bin: f32, sample_rate: u32, n: usize) -> f32 {
+    bin * bin_width_hz(sample_rate, n)
+}
+
+/// Fractional bin at frequency `hz`; round or truncate for an index.
+pub fn hz_to_bin(hz: f32, sample_rate: u32, n: usize) -> f32 {
+    hz / bin_width_hz(sample_rate, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_round_trip() {
+        assert_eq!(bin_width_hz(48000, 1024), 46.875);
+        assert_eq!(nyquist_hz(44100), 22050.0);
+        assert_eq!(bin_to_hz(512.0, 44100, 1024), nyquist_hz(44100));
+        assert_eq!(hz_to_bin(1000.0, 8000, 256), 32.0);
+        for bin in [0.0, 1.5, 100.25, 1024.0] {
+            let hz = bin_to_hz(bin, 44100, 2048);
+            assert!((hz_to_bin(hz, 44100, 2048) - bin).abs() < 1e-3);
+        }
+    }
+}
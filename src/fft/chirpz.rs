@@ -0,0 +1,273 @@
+use super::scratch::Scratch;
+use super::{find_dft_generic, DFTBase, Direction, FftFloat};
+use num_complex::{Complex, Complex64};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Chirp-z transform: the z-transform of `n` samples evaluated at `m`
+/// points `z_k = a·w^(-k)` along a spiral arc of the z-plane.
+///
+/// On the unit circle this is a zoom FFT: `m` bins of any spacing starting
+/// at any frequency, e.g. 0.05 Hz steps across 440–460 Hz, for the cost of
+/// one convolution of length `n + m - 1` rather than an FFT long enough
+/// to reach that resolution. `DFTBluestein` runs on the same
+/// `ChirpConvolution` as the special case `m = n`, `a = 1`,
+/// `w = exp(-2πi/n)`.
+///
+/// Like `find_dft` the output is unnormalized: a bin that lands on an FFT
+/// bin reads the same value as that FFT bin.
+pub struct ChirpZ<T: FftFloat = f32> {
+    conv: ChirpConvolution<T>,
+}
+
+impl<T: FftFloat> ChirpZ<T> {
+    /// Zoom transform of `n` samples at `sample_rate`: `points` bins from
+    /// `start_hz` in steps of `step_hz`. Bin `k` is at
+    /// `start_hz + k * step_hz`.
+    pub fn new(n: usize, sample_rate: u32, start_hz: f32, step_hz: f32, points: usize) -> Self {
+        let rate = sample_rate as f64;
+        let a = Complex64::from_polar(1.0, 2.0 * PI * start_hz as f64 / rate);
+        let w = Complex64::from_polar(1.0, -2.0 * PI * step_hz as f64 / rate);
+        Self::on_arc(n, points, a, w)
+    }
+
+    /// Transform of `n` samples at the `m` points `a·w^(-k)`. Radii away
+    /// from 1 spiral in or out; `|w|^(k²/2)` grows quickly, so keep `|w|`
+    /// within a small distance of 1 for long transforms.
+    pub fn on_arc(n: usize, m: usize, a: Complex64, w: Complex64) -> Self {
+        assert!(n > 0 && m > 0, "chirp-z sizes must be non-zero");
+
+        //
+        // w^(t/2) in polar form, evaluated in f64; t = j² gets large.
+        //
+        let (w_r, w_theta) = w.to_polar();
+        let (a_r, a_theta) = a.to_polar();
+        let chirp = |j: usize| {
+            let t = (j * j) as f64 / 2.0;
+            Complex64::from_polar(w_r.powf(t), w_theta * t)
+        };
+        let a_pow = |j: usize| Complex64::from_polar(a_r.powf(-(j as f64)), -a_theta * j as f64);
+        Self {
+            conv: ChirpConvolution::new(n, m, (n + m - 1).next_power_of_two(), a_pow, chirp),
+        }
+    }
+
+    /// Number of input samples.
+    pub fn size(&self) -> usize {
+        self.conv.n
+    }
+
+    /// Number of output points.
+    pub fn points(&self) -> usize {
+        self.conv.m
+    }
+
+    /// Evaluates the `n` samples of `input` at the `m` arc points.
+    pub fn xform(&self, input: &[Complex<T>], output: &mut [Complex<T>]) {
+        assert_eq!(input.len(), self.conv.n, "chirp-z input length");
+        assert_eq!(output.len(), self.conv.m, "chirp-z output length");
+        self.conv
+            .xform_many(input, output, 1, 0, 1, 0, 1, |x| x, |x| x);
+    }
+}
+
+/// The chirp convolution behind `ChirpZ` and `DFTBluestein`.
+///
+/// With `chirp(j) = w^(j²/2)`, the arc points satisfy
+/// `z_k^-j = a^-j · chirp(j) · chirp(k) / chirp(k - j)`, so the transform
+/// is the input modulated by `a^-j·chirp(j)`, convolved with
+/// `1 / chirp(lag)` and demodulated by `chirp(k)`. The convolution is one
+/// `l`-point circular convolution, `l >= n + m - 1`, using only forward
+/// `l`-point transforms: the inverse is taken as conj(DFT(conj(x))), and
+/// its 1/l is folded into the stored kernel spectrum, so no call rescales.
+pub(crate) struct ChirpConvolution<T: FftFloat> {
+    n: usize,
+    m: usize,
+    l: usize,
+
+    //
+    // Input modulation a^-j·chirp(j), output modulation chirp(k), and the
+    // conjugated spectrum of the 1 / chirp(lag) kernel pre-scaled by 1/l.
+    //
+    pre: Vec<Complex<T>>,
+    post: Vec<Complex<T>>,
+    kernel: Vec<Complex<T>>,
+    dft_l: Arc<dyn DFTBase<T>>,
+
+    //
+    // Two padded blocks per transform, grown to the largest batch seen.
+    //
+    scratch: Scratch<Complex<T>>,
+}
+
+impl<T: FftFloat> ChirpConvolution<T> {
+    /// Convolution of `n` inputs to `m` outputs in `l` points, with
+    /// `a_pow(j) = a^-j` and `chirp(j) = w^(j²/2)` evaluated in f64.
+    pub(crate) fn new(
+        n: usize,
+        m: usize,
+        l: usize,
+        a_pow: impl Fn(usize) -> Complex64,
+        chirp: impl Fn(usize) -> Complex64,
+    ) -> Self {
+        assert!(
+            n > 0 && m > 0 && l >= n + m - 1,
+            "a chirp convolution of {} to {} points needs at least {}, got {}",
+            n,
+            m,
+            n + m - 1,
+            l
+        );
+        let narrow = |c: Complex64| Complex::new(T::cast(c.re), T::cast(c.im));
+        let pre = (0..n).map(|j| narrow(a_pow(j) * chirp(j))).collect();
+        let post = (0..m).map(|k| narrow(chirp(k))).collect();
+
+        //
+        // Kernel for lags -(n-1)..m, wrapped into one circular block. It
+        // depends on lag² only, so negative lags reuse the positive ones.
+        //
+        let scale = 1.0 / l as f64;
+        let mut kernel = vec![Complex::default(); l];
+        for (k, h) in kernel.iter_mut().enumerate().take(m) {
+            *h = narrow(chirp(k).inv() * scale);
+        }
+        for j in 1..n {
+            kernel[l - j] = narrow(chirp(j).inv() * scale);
+        }
+        let dft_l = find_dft_generic::<T>(l, Direction::Forward);
+        dft_l.xform_inplace(&mut kernel);
+        kernel.iter_mut().for_each(|k| *k = k.conj());
+
+        Self {
+            n,
+            m,
+            l,
+            pre,
+            post,
+            kernel,
+            dft_l,
+            scratch: Scratch::with_capacity(2 * l),
+        }
+    }
+
+    /// Transforms `count` strided blocks of `n` inputs into `count` blocks
+    /// of `m` outputs, passing each input through `load` and each output
+    /// through `store`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn xform_many(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+        load: impl Fn(Complex<T>) -> Complex<T>,
+        store: impl Fn(Complex<T>) -> Complex<T>,
+    ) {
+        let l = self.l;
+        self.scratch.with(2 * l * count, |buf| {
+            let (blocks, spectra) = buf.split_at_mut(l * count);
+
+            //
+            // Apply the input modulation, zero padding up to l; the buffer
+            // is reused between calls.
+            //
+            for (i, block) in blocks.chunks_exact_mut(l).enumerate() {
+                for (j, (b, &p)) in block.iter_mut().zip(&self.pre).enumerate() {
+                    *b = load(input[j * istep + i * istep2]) * p;
+                }
+                block[self.n..].fill(Complex::default());
+            }
+
+            //
+            // Forward DFT of the modulated blocks, then multiply by the
+            // kernel spectrum. The conj() here and the one at write-back
+            // turn the second forward pass into an inverse; the kernel
+            // carries the 1/l.
+            //
+            self.dft_l.xform_many(blocks, spectra, 1, l, 1, l, count);
+            for spectrum in spectra.chunks_exact_mut(l) {
+                for (s, &k) in spectrum.iter_mut().zip(&self.kernel) {
+                    *s = s.conj() * k;
+                }
+            }
+            self.dft_l.xform_many(spectra, blocks, 1, l, 1, l, count);
+
+            //
+            // Demodulate the first m lags and write them back.
+            //
+            for (i, block) in blocks.chunks_exact(l).enumerate() {
+                for (k, (b, &p)) in block.iter().zip(&self.post).enumerate() {
+                    output[k * ostep + i * ostep2] = store(b.conj() * p);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft::find_dft;
+    use num_complex::Complex32;
+
+    fn signal(n: usize) -> Vec<Complex32> {
+        (0..n)
+            .map(|i| {
+                let t = i as f32;
+                Complex32::new((0.7 * t).sin() + 0.3 * (2.9 * t).cos(), (1.3 * t).cos())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn zoom_matches_a_zero_padded_fft_over_the_band() {
+        //
+        // At 64 Hz, a 256-point FFT of the zero-padded 64 samples has
+        // 0.25 Hz bins; 10 Hz..18 Hz is bins 40..72.
+        //
+        let (n, padded, rate) = (64, 256, 64);
+        let input = signal(n);
+        let czt = ChirpZ::new(n, rate, 10.0, 0.25, 32);
+        let mut zoom = vec![Complex32::default(); 32];
+        czt.xform(&input, &mut zoom);
+
+        let mut full = input.clone();
+        full.resize(padded, Complex32::default());
+        find_dft(padded, Direction::Forward).xform_inplace(&mut full);
+
+        let scale = full.iter().map(|c| c.norm()).fold(0.0, f32::max);
+        for (k, (z, f)) in zoom.iter().zip(&full[40..72]).enumerate() {
+            assert!((z - f).norm() < 1e-4 * scale, "bin {}: {} vs {}", k, z, f);
+        }
+    }
+
+    #[test]
+    fn spiral_arc_matches_the_direct_sum() {
+        let (n, m) = (20, 12);
+        let a = Complex64::from_polar(1.05, 0.3);
+        let w = Complex64::from_polar(0.98, -0.1);
+        let input: Vec<Complex64> = signal(n)
+            .iter()
+            .map(|c| Complex64::new(c.re as f64, c.im as f64))
+            .collect();
+        let mut output = vec![Complex64::default(); m];
+        ChirpZ::<f64>::on_arc(n, m, a, w).xform(&input, &mut output);
+
+        for (k, &got) in output.iter().enumerate() {
+            let z = a * w.powi(-(k as i32));
+            let want: Complex64 = input
+                .iter()
+                .enumerate()
+                .map(|(j, &x)| x * z.powi(-(j as i32)))
+                .sum();
+            assert!(
+                (got - want).norm() < 1e-9 * want.norm().max(1.0),
+                "point {}",
+                k
+            );
+        }
+    }
+}
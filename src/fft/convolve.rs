@@ -0,0 +1,196 @@
+use super::{find_dft, DFTBase, Direction};
+use num_complex::Complex32;
+use std::sync::Arc;
+
+/// Overlap-add convolution of a streaming signal with a fixed impulse
+/// response, e.g. FIR filtering of captured audio.
+///
+/// Input is cut into segments of `segment_len` samples. Each one is
+/// zero-padded to the FFT size, multiplied by the response's spectrum and
+/// transformed back, and its `ir_len - 1` sample tail is added to the start
+/// of the next. Every `process` call returns exactly as many samples as it
+/// was given, aligned with the input, so there is no added latency.
+pub struct Convolver {
+    ir_len: usize,
+    segment_len: usize,
+    fft: Arc<dyn DFTBase>,
+    ifft: Arc<dyn DFTBase>,
+
+    //
+    // Spectrum of the zero-padded response, pre-scaled by 1/n so the
+    // unnormalized inverse transform comes out at unit gain.
+    //
+    ir_spectrum: Vec<Complex32>,
+
+    //
+    // Output accumulator: the head is finished output, the rest the
+    // overlapping tails of earlier segments.
+    //
+    overlap: Vec<f32>,
+    buf: Vec<Complex32>,
+}
+
+impl Convolver {
+    /// Creates a convolver for `ir`, picking the power-of-two FFT size with
+    /// the least work per output sample.
+    pub fn new(ir: &[f32]) -> Self {
+        assert!(!ir.is_empty(), "impulse response must not be empty");
+        let m = ir.len();
+
+        //
+        // A size-n FFT pair produces n - m + 1 new samples; n·log2(n) per
+        // useful sample is smallest a few times above the response length.
+        //
+        let smallest = (2 * m).next_power_of_two().max(64);
+        let n = (0..6)
+            .map(|shift| smallest << shift)
+            .min_by(|&a, &b| {
+                let cost = |n: usize| n as f64 * (n as f64).log2() / (n - m + 1) as f64;
+                cost(a).total_cmp(&cost(b))
+            })
+            .unwrap_or(smallest);
+        Self::with_fft_size(ir, n)
+    }
+
+    /// Creates a convolver for `ir` using `n`-point transforms; `n` must be
+    /// at least `ir.len()` and need not be a power of two.
+    pub fn with_fft_size(ir: &[f32], n: usize) -> Self {
+        let m = ir.len();
+        assert!(
+            m > 0 && n >= m,
+            "FFT size {} is shorter than the response",
+            n
+        );
+
+        let fft = find_dft(n, Direction::Forward);
+        let mut ir_spectrum = vec![Complex32::default(); n];
+        let padded: Vec<Complex32> = (0..n)
+            .map(|i| Complex32::new(ir.get(i).copied().unwrap_or(0.0), 0.0))
+            .collect();
+        fft.xform(&padded, &mut ir_spectrum);
+        let scale = 1.0 / n as f32;
+        for c in &mut ir_spectrum {
+            *c *= scale;
+        }
+
+        Self {
+            ir_len: m,
+            segment_len: n - m + 1,
+            fft,
+            ifft: find_dft(n, Direction::Inverse),
+            ir_spectrum,
+            overlap: vec![0.0; n],
+            buf: vec![Complex32::default(); n],
+        }
+    }
+
+    pub fn ir_len(&self) -> usize {
+        self.ir_len
+    }
+
+    pub fn fft_size(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Filters the next `block` of the stream, returning the same number of
+    /// output samples.
+    pub fn process(&mut self, block: &[f32]) -> Vec<f32> {
+        let mut out = Vec::with_capacity(block.len());
+        for segment in block.chunks(self.segment_len) {
+            self.convolve_segment(segment);
+            out.extend_from_slice(&self.overlap[..segment.len()]);
+            self.advance(segment.len());
+        }
+        out
+    }
+
+    /// Returns the response's remaining `ir_len - 1` samples of ringing
+    /// after the last input and clears the state for a new stream.
+    pub fn flush(&mut self) -> Vec<f32> {
+        let tail = self.overlap[..self.ir_len - 1].to_vec();
+        self.reset();
+        tail
+    }
+
+    /// Drops any pending tail, e.g. after a discontinuity in the input.
+    pub fn reset(&mut self) {
+        self.overlap.fill(0.0);
+    }
+
+    /// Adds the linear convolution of `segment` with the response to the
+    /// accumulator.
+    fn convolve_segment(&mut self, segment: &[f32]) {
+        for (i, c) in self.buf.iter_mut().enumerate() {
+            *c = Complex32::new(segment.get(i).copied().unwrap_or(0.0), 0.0);
+        }
+        self.fft.xform_inplace(&mut self.buf);
+        for (c, &h) in self.buf.iter_mut().zip(&self.ir_spectrum) {
+            *c *= h;
+        }
+        self.ifft.xform_inplace(&mut self.buf);
+
+        //
+        // The result spans segment.len() + ir_len - 1 samples, which fits in
+        // n because segments are at most n - ir_len + 1 long.
+        //
+        let len = segment.len() + self.ir_len - 1;
+        for (acc, c) in self.overlap[..len].iter_mut().zip(&self.buf) {
+            *acc += c.re;
+        }
+    }
+
+    /// Shifts `count` finished samples out of the accumulator.
+    fn advance(&mut self, count: usize) {
+        self.overlap.copy_within(count.., 0);
+        let n = self.overlap.len();
+        self.overlap[n - count..].fill(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streaming_matches_direct_convolution() {
+        let ir = [0.5, -0.25, 0.125, 1.0, -0.75, 0.3, 0.05];
+        let input: Vec<f32> = (0..500).map(|i| (0.37 * i as f32).sin()).collect();
+
+        let mut expected = vec![0.0f32; input.len() + ir.len() - 1];
+        for (i, &x) in input.iter().enumerate() {
+            for (k, &h) in ir.iter().enumerate() {
+                expected[i + k] += x * h;
+            }
+        }
+
+        //
+        // Uneven blocks, some longer than a segment, and a non-power-of-two
+        // transform size.
+        //
+        for mut convolver in [Convolver::new(&ir), Convolver::with_fft_size(&ir, 24)] {
+            let mut output = Vec::new();
+            let mut rest = &input[..];
+            for len in [1, 13, 64, 100, 7].iter().cycle() {
+                let (block, tail) = rest.split_at((*len).min(rest.len()));
+                output.extend(convolver.process(block));
+                rest = tail;
+                if rest.is_empty() {
+                    break;
+                }
+            }
+            output.extend(convolver.flush());
+
+            assert_eq!(output.len(), expected.len());
+            for (i, (o, e)) in output.iter().zip(&expected).enumerate() {
+                assert!(
+                    (o - e).abs() < 1e-4,
+                    "n={} sample {}: {} vs {}",
+                    convolver.fft_size(),
+                    i,
+                    o,
+                    e
+                );
+            }
+        }
+    }
+}
@@ -1,73 +1,131 @@
-use super::DFTBase;
+use super::{debug_check_strides, DFTBase, Direction, FftError, RDFTBase};
+use fftw::array::AlignedVec;
 use fftw::plan::*;
 use fftw::types::*;
 use num_complex::Complex32;
-use std::sync::Arc;
-use std::sync::Mutex;
+use std::ffi::CString;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
 
-/// FFTW3 wrapper providing a dedicated internal buffer and plan.
-/// The internal state is guarded by a mutex because FFTW plan/buffer
-/// combinations are not thread-safe under concurrent writes.
+/// FFTW3 wrapper providing dedicated internal buffers and plan.
+/// Each plan/buffer pair is guarded by a mutex because FFTW plan/buffer
+/// combinations are not thread-safe under concurrent writes. A plan built
+/// with `with_pool` keeps several pairs, so up to that many threads can
+/// transform at once; further callers wait for a pair to free up.
+#[allow(non_camel_case_types)]
 pub struct DFT_FFTW {
     n: usize,
+    direction: Direction,
     //
-    // Internal plan and dedicated buffer.
+    // Internal plans with their dedicated buffers, and the slot the next
+    // caller tries first.
     //
-    state: Mutex<InternalState>,
+    states: Vec<Mutex<InternalState>>,
+    next: AtomicUsize,
 }
 
 struct InternalState {
     //
-    // Plan stored in Arc to allow moving the wrapper safely.
+    // Plan executed against the buffers below.
     //
-    plan: Arc<C2CPlan32>,
+    plan: C2CPlan32,
 
     //
-    // Internal buffer used for both input and output.
+    // Out-of-place input and output buffers. The plan is created on exactly
+    // these aligned arrays, since FFTW bakes their alignment into the plan.
     //
-    data: Vec<Complex32>,
+    input: AlignedVec<Complex32>,
+    output: AlignedVec<Complex32>,
 }
 
-impl DFT_FFTW {
-    pub fn new(n: usize) -> Self {
-        let n_i32 = n as i32;
+impl InternalState {
+    fn new(n: usize, direction: Direction) -> Result<Self, FftError> {
+        let mut input = AlignedVec::new(n);
+        let mut output = AlignedVec::new(n);
 
         //
-        // Build a plan using temporary buffers.
+        // Create a plan for the requested direction with MEASURE. Measuring
+        // overwrites the buffers, which is harmless before first use.
         //
-        let mut data = vec![Complex32::default(); n];
-        let mut out = vec![Complex32::default(); n];
+        let sign = match direction {
+            Direction::Forward => Sign::Forward,
+            Direction::Inverse => Sign::Backward,
+        };
+        let plan = C2CPlan::new(&[n], &mut input, &mut output, sign, Flag::MEASURE)
+            .map_err(|err| plan_error(n, err))?;
 
-        //
-        // Create a forward FFT plan with MEASURE.
-        //
-        let plan = C2CPlan::new(&[n], &mut data, &mut out, Sign::Forward, Flag::MEASURE)
-            .expect("Failed to create FFTW plan");
+        Ok(Self {
+            plan,
+            input,
+            output,
+        })
+    }
+}
 
-        //
-        // Allocate a buffer twice the size of the transform length.
-        //
-        let mut double_buffer = vec![Complex32::default(); n * 2];
+fn plan_error(n: usize, err: fftw::error::Error) -> FftError {
+    FftError::Plan {
+        n,
+        reason: err.to_string(),
+    }
+}
+
+impl DFT_FFTW {
+    /// Builds a single plan, or reports why FFTW could not.
+    pub fn new(n: usize, direction: Direction) -> Result<Self, FftError> {
+        Self::with_pool(n, direction, 1)
+    }
 
-        Self {
+    /// Builds `pool_size` independent plan/buffer pairs (at least one).
+    /// Plans after the first are answered from FFTW's wisdom, so the extra
+    /// cost is mostly the buffers: `2 * n` complex samples per pair.
+    pub fn with_pool(n: usize, direction: Direction, pool_size: usize) -> Result<Self, FftError> {
+        Ok(Self {
             n,
-            state: Mutex::new(InternalState {
-                plan: Arc::new(plan),
-                data: double_buffer,
-            }),
+            direction,
+            states: (0..pool_size.max(1))
+                .map(|_| InternalState::new(n, direction).map(Mutex::new))
+                .collect::<Result<_, _>>()?,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    pub fn pool_size(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Takes the first free plan/buffer pair, starting from a rotating slot
+    /// so concurrent callers spread over the pool, or waits on that slot if
+    /// every pair is busy.
+    fn state(&self) -> MutexGuard<'_, InternalState> {
+        let len = self.states.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % len;
+        for i in 0..len {
+            if let Ok(state) = self.states[(start + i) % len].try_lock() {
+                return state;
+            }
         }
+        self.states[start].lock().unwrap()
     }
 }
 
 impl DFTBase for DFT_FFTW {
     fn name(&self) -> String {
-        format!("FFTW({})", self.n)
+        match self.direction {
+            Direction::Forward => format!("FFTW({})", self.n),
+            Direction::Inverse => format!("FFTW-Inv({})", self.n),
+        }
     }
 
     fn size(&self) -> usize {
         self.n
     }
 
+    fn direction(&self) -> Direction {
+        self.direction
+    }
+
     fn is_inplace(&self) -> bool {
         //
         // Externally behaves as an in-place transform due to internal buffering.
@@ -83,7 +141,16 @@ impl DFTBase for DFT_FFTW {
         //
         // Perform transform using the internal buffer.
         //
-        self.xform(buffer, buffer);
+        let mut state = self.state();
+        let n = self.n;
+        let InternalState {
+            plan,
+            input,
+            output,
+        } = &mut *state;
+        input.copy_from_slice(&buffer[..n]);
+        plan.c2c(input, output).expect("Exec failed");
+        buffer[..n].copy_from_slice(output);
     }
 
     //
@@ -99,32 +166,293 @@ impl DFTBase for DFT_FFTW {
         ostep2: usize,
         count: usize,
     ) {
-        let mut state = self.state.lock().unwrap();
-        let n = self.n;
+        debug_check_strides(
+            self.size(),
+            input.len(),
+            output.len(),
+            istep,
+            istep2,
+            ostep,
+            ostep2,
+            count,
+        );
+        let mut state = self.state();
+        let InternalState {
+            plan,
+            input: in_buf,
+            output: out_buf,
+        } = &mut *state;
 
         for k in 0..count {
             //
-            // Copy input slice into the internal buffer.
+            // Gather the strided input into the planned input buffer.
             //
-            for i in 0..n {
-                state.data[i] = input[k * istep2 + i * istep];
+            for (i, v) in in_buf.iter_mut().enumerate() {
+                *v = input[k * istep2 + i * istep];
             }
 
-            //
-            // Execute FFT into the second half of the buffer.
-            //
-            let (in_buf, out_buf) = state.data.split_at_mut(n);
-            state
-                .plan
-                .reprocess(&mut in_buf[0..n], &mut out_buf[0..n])
-                .expect("Exec failed");
+            plan.c2c(in_buf, out_buf).expect("Exec failed");
 
             //
-            // Copy FFT output to the destination slice.
+            // Scatter the planned output buffer to the destination.
             //
-            for i in 0..n {
-                output[k * ostep2 + i * ostep] = out_buf[i];
+            for (i, &v) in out_buf.iter().enumerate() {
+                output[k * ostep2 + i * ostep] = v;
+            }
+        }
+    }
+}
+
+/// FFTW3 real-to-complex wrapper for real input such as audio.
+/// Produces the `n/2 + 1` non-redundant bins directly, roughly halving the
+/// work and buffer size of the complex plan.
+#[allow(non_camel_case_types)]
+pub struct RDFT_FFTW {
+    n: usize,
+    state: Mutex<RealState>,
+}
+
+struct RealState {
+    //
+    // R2C plan built on the aligned buffers below.
+    //
+    plan: R2CPlan32,
+
+    //
+    // Real input (`n`) and half-spectrum output (`n/2 + 1`) buffers.
+    //
+    input: AlignedVec<f32>,
+    output: AlignedVec<Complex32>,
+}
+
+impl RDFT_FFTW {
+    /// Builds a real-input forward plan, or reports why FFTW could not.
+    pub fn new(n: usize) -> Result<Self, FftError> {
+        let mut input = AlignedVec::new(n);
+        let mut output = AlignedVec::new(n / 2 + 1);
+
+        //
+        // Plan directly on the buffers that will be executed against.
+        //
+        let plan = R2CPlan::new(&[n], &mut input, &mut output, Flag::MEASURE)
+            .map_err(|err| plan_error(n, err))?;
+
+        Ok(Self {
+            n,
+            state: Mutex::new(RealState {
+                plan,
+                input,
+                output,
+            }),
+        })
+    }
+}
+
+impl RDFTBase for RDFT_FFTW {
+    fn name(&self) -> String {
+        format!("FFTW-R2C({})", self.n)
+    }
+
+    fn size(&self) -> usize {
+        self.n
+    }
+
+    fn xform_real(&self, input: &[f32], output: &mut [Complex32]) {
+        let mut state = self.state.lock().unwrap();
+        let n = self.n;
+
+        let RealState {
+            plan,
+            input: in_buf,
+            output: out_buf,
+        } = &mut *state;
+        in_buf.copy_from_slice(&input[..n]);
+        plan.r2c(in_buf, out_buf).expect("Exec failed");
+        output[..n / 2 + 1].copy_from_slice(out_buf);
+    }
+}
+
+/// Converts `path` for FFTW's C file API.
+fn c_path(path: &Path) -> io::Result<CString> {
+    path.to_str()
+        .and_then(|s| CString::new(s).ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "unusable wisdom path"))
+}
+
+/// Loads single-precision FFTW wisdom from `path`. Call before planning;
+/// plans created afterwards reuse the stored measurements.
+///
+/// Wisdom is planner state, so this holds `fftw::FFTW_MUTEX` like the
+/// planner calls in the `fftw` crate do.
+pub fn import_wisdom(path: &Path) -> io::Result<()> {
+    let c_path = c_path(path)?;
+    let _lock = fftw::FFTW_MUTEX.lock().expect("Cannot get lock");
+    if unsafe { fftw_sys::fftwf_import_wisdom_from_filename(c_path.as_ptr()) } != 0 {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "failed to import FFTW wisdom from {}",
+            path.display()
+        )))
+    }
+}
+
+/// Saves the wisdom accumulated by every plan created so far to `path`.
+pub fn export_wisdom(path: &Path) -> io::Result<()> {
+    let c_path = c_path(path)?;
+    let _lock = fftw::FFTW_MUTEX.lock().expect("Cannot get lock");
+    if unsafe { fftw_sys::fftwf_export_wisdom_to_filename(c_path.as_ptr()) } != 0 {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "failed to export FFTW wisdom to {}",
+            path.display()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn r2c_matches_c2c() {
+        for n in [8, 15, 2048] {
+            let real: Vec<f32> = (0..n)
+                .map(|i| (i as f32 * 0.71).sin() + 0.25 * (i as f32 * 2.3).cos())
+                .collect();
+            let complex: Vec<Complex32> = real.iter().map(|&x| Complex32::new(x, 0.0)).collect();
+
+            let mut expected = vec![Complex32::default(); n];
+            DFT_FFTW::new(n, Direction::Forward)
+                .unwrap()
+                .xform(&complex, &mut expected);
+
+            let mut actual = vec![Complex32::default(); n / 2 + 1];
+            RDFT_FFTW::new(n).unwrap().xform_real(&real, &mut actual);
+
+            for k in 0..=n / 2 {
+                let err = (actual[k] - expected[k]).norm();
+                assert!(
+                    err < 1e-3 * (n as f32).sqrt(),
+                    "n={} k={} err={}",
+                    n,
+                    k,
+                    err
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn batched_strided_matches_naive_dft() {
+        let (n, count) = (12, 5);
+        let (istep, istep2) = (count, 1);
+        let (ostep, ostep2) = (3, 3 * n + 2);
+        let input: Vec<Complex32> = (0..n * count)
+            .map(|i| Complex32::new((i as f32 * 0.37).sin(), (i as f32 * 1.3).cos()))
+            .collect();
+
+        for direction in [Direction::Forward, Direction::Inverse] {
+            let mut output = vec![Complex32::default(); count * ostep2];
+            DFT_FFTW::new(n, direction).unwrap().xform_many(
+                &input,
+                &mut output,
+                istep,
+                istep2,
+                ostep,
+                ostep2,
+                count,
+            );
+
+            for b in 0..count {
+                for k in 0..n {
+                    let expected: Complex32 = (0..n)
+                        .map(|j| {
+                            let phase = direction.sign()
+                                * 2.0
+                                * std::f32::consts::PI
+                                * ((j * k) % n) as f32
+                                / n as f32;
+                            input[b * istep2 + j * istep] * Complex32::from_polar(1.0, phase)
+                        })
+                        .sum();
+                    let got = output[b * ostep2 + k * ostep];
+                    assert!(
+                        (got - expected).norm() < 1e-4,
+                        "{:?} batch {} bin {}",
+                        direction,
+                        b,
+                        k
+                    );
+                }
             }
         }
     }
+
+    #[test]
+    fn pooled_plan_transforms_concurrently() {
+        let n = 256;
+        let plan = DFT_FFTW::with_pool(n, Direction::Forward, 4).unwrap();
+        assert_eq!(plan.pool_size(), 4);
+
+        let inputs: Vec<Vec<Complex32>> = (0..8)
+            .map(|t| {
+                (0..n)
+                    .map(|i| Complex32::new((i as f32 * 0.1 * (t + 1) as f32).sin(), 0.0))
+                    .collect()
+            })
+            .collect();
+        let expected: Vec<Vec<Complex32>> = inputs
+            .iter()
+            .map(|input| {
+                let mut out = vec![Complex32::default(); n];
+                DFT_FFTW::new(n, Direction::Forward)
+                    .unwrap()
+                    .xform(input, &mut out);
+                out
+            })
+            .collect();
+
+        //
+        // More threads than pairs, each repeating its transform, so the
+        // busy path is exercised as well.
+        //
+        std::thread::scope(|s| {
+            for (input, expected) in inputs.iter().zip(&expected) {
+                let plan = &plan;
+                s.spawn(move || {
+                    let mut out = vec![Complex32::default(); n];
+                    for _ in 0..50 {
+                        plan.xform(input, &mut out);
+                        let err = out
+                            .iter()
+                            .zip(expected)
+                            .map(|(a, b)| (a - b).norm())
+                            .fold(0.0, f32::max);
+                        assert!(err < 1e-4, "err={}", err);
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn wisdom_round_trips_through_a_file() {
+        let path = std::env::temp_dir().join(format!("fftanalyzer_{}.wisdom", std::process::id()));
+        let _plan = DFT_FFTW::new(64, Direction::Forward).unwrap();
+        export_wisdom(&path).unwrap();
+        import_wisdom(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(import_wisdom(&path).is_err());
+    }
+
+    #[test]
+    fn unplannable_sizes_report_an_error() {
+        let err = DFT_FFTW::new(0, Direction::Forward)
+            .err()
+            .expect("n = 0 should not plan");
+        assert!(matches!(err, FftError::Plan { n: 0, .. }));
+        assert!(RDFT_FFTW::new(0).is_err());
+    }
 }
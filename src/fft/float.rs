@@ -0,0 +1,122 @@
+use super::{DFTBase, Direction};
+use lazy_static::lazy_static;
+use num_traits::{Float, FloatConst, NumAssign};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::iter::Sum;
+use std::simd::{LaneCount, Simd, SimdElement, SupportedLaneCount};
+use std::sync::{Arc, OnceLock};
+
+/// Plan cache keyed by size and direction, one per element type. Each
+/// entry is a slot that the first caller for that key fills; concurrent
+/// callers for the same key wait for it instead of building a duplicate.
+pub type PlanCache<T> = Mutex<HashMap<(usize, Direction), Arc<OnceLock<Arc<dyn DFTBase<T>>>>>>;
+
+/// Floating-point element type the transforms can be instantiated with.
+/// Besides scalar math it supplies lane-wise SIMD arithmetic, which
+/// `portable_simd` only implements per concrete element type, and the
+/// plan cache used by `find_dft_generic`.
+pub trait FftFloat:
+    Float
+    + FloatConst
+    + NumAssign
+    + SimdElement
+    + Default
+    + Sum
+    + Debug
+    + Display
+    + Send
+    + Sync
+    + 'static
+{
+    /// Converts an `f64` constant, rounding to the nearest value.
+    fn cast(v: f64) -> Self;
+
+    fn simd_add<const L: usize>(a: Simd<Self, L>, b: Simd<Self, L>) -> Simd<Self, L>
+    where
+        LaneCount<L>: SupportedLaneCount;
+    fn simd_sub<const L: usize>(a: Simd<Self, L>, b: Simd<Self, L>) -> Simd<Self, L>
+    where
+        LaneCount<L>: SupportedLaneCount;
+    fn simd_mul<const L: usize>(a: Simd<Self, L>, b: Simd<Self, L>) -> Simd<Self, L>
+    where
+        LaneCount<L>: SupportedLaneCount;
+
+    /// Cache of native and external plans for this element type.
+    fn plan_cache() -> &'static PlanCache<Self>;
+
+    /// Plan from an external backend, if one is enabled for this type.
+    fn external_plan(_n: usize, _direction: Direction) -> Option<Arc<dyn DFTBase<Self>>> {
+        None
+    }
+}
+
+macro_rules! impl_simd_ops {
+    () => {
+        #[inline(always)]
+        fn cast(v: f64) -> Self {
+            v as Self
+        }
+
+        #[inline(always)]
+        fn simd_add<const L: usize>(a: Simd<Self, L>, b: Simd<Self, L>) -> Simd<Self, L>
+        where
+            LaneCount<L>: SupportedLaneCount,
+        {
+            a + b
+        }
+
+        #[inline(always)]
+        fn simd_sub<const L: usize>(a: Simd<Self, L>, b: Simd<Self, L>) -> Simd<Self, L>
+        where
+            LaneCount<L>: SupportedLaneCount,
+        {
+            a - b
+        }
+
+        #[inline(always)]
+        fn simd_mul<const L: usize>(a: Simd<Self, L>, b: Simd<Self, L>) -> Simd<Self, L>
+        where
+            LaneCount<L>: SupportedLaneCount,
+        {
+            a * b
+        }
+    };
+}
+
+impl FftFloat for f32 {
+    impl_simd_ops!();
+
+    fn plan_cache() -> &'static PlanCache<f32> {
+        lazy_static! {
+            static ref CACHE: PlanCache<f32> = Mutex::new(HashMap::new());
+        }
+        &CACHE
+    }
+
+    #[cfg(feature = "use_fftw")]
+    fn external_plan(n: usize, direction: Direction) -> Option<Arc<dyn DFTBase<f32>>> {
+        //
+        // A plan FFTW cannot build falls through to the native strategies.
+        //
+        match super::fftw::DFT_FFTW::new(n, direction) {
+            Ok(plan) => Some(Arc::new(plan)),
+            Err(err) => {
+                log::warn!("{}; using the native planner", err);
+                None
+            }
+        }
+    }
+}
+
+impl FftFloat for f64 {
+    impl_simd_ops!();
+
+    fn plan_cache() -> &'static PlanCache<f64> {
+        lazy_static! {
+            static ref CACHE: PlanCache<f64> = Mutex::new(HashMap::new());
+        }
+        &CACHE
+    }
+}
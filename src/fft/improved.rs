@@ -1,46 +1,43 @@
-use super::DFTBase;
-use num_complex::Complex32;
-use std::f32::consts::PI;
+//
+// Kernels follow the usual DFT notation: `x` is the input and `X` the output.
+//
+#![allow(non_snake_case)]
+
+use super::{debug_check_strides, DFTBase, Direction, FftFloat};
+use num_complex::Complex;
+use std::f64::consts::PI;
 use std::ops::{Add, Mul, Sub};
 use std::simd::prelude::*;
 use std::simd::LaneCount;
 use std::simd::SupportedLaneCount;
+use std::sync::OnceLock;
 
 /// SIMD batch of L complex numbers stored in SoA layout.
 /// Exposes real and imaginary SIMD vectors for kernel operations.
 #[derive(Clone, Copy)]
-pub struct BatchComplex<const L: usize>
+pub struct BatchComplex<T: FftFloat, const L: usize>
 where
     LaneCount<L>: SupportedLaneCount,
 {
-    pub re: Simd<f32, L>,
-    pub im: Simd<f32, L>,
+    pub re: Simd<T, L>,
+    pub im: Simd<T, L>,
 }
 
-impl<const L: usize> BatchComplex<L>
+impl<T: FftFloat, const L: usize> BatchComplex<T, L>
 where
     LaneCount<L>: SupportedLaneCount,
 {
-    /// Returns a batch with all lanes set to the same complex value.
-    #[inline(always)]
-    fn splat(c: Complex32) -> Self {
-        Self {
-            re: Simd::splat(c.re),
-            im: Simd::splat(c.im),
-        }
-    }
-
     /// Returns a batch initialized to zero.
     #[inline(always)]
     fn zero() -> Self {
         Self {
-            re: Simd::splat(0.0),
-            im: Simd::splat(0.0),
+            re: Simd::splat(T::zero()),
+            im: Simd::splat(T::zero()),
         }
     }
 }
 
-impl<const L: usize> Add for BatchComplex<L>
+impl<T: FftFloat, const L: usize> Add for BatchComplex<T, L>
 where
     LaneCount<L>: SupportedLaneCount,
 {
@@ -50,13 +47,13 @@ where
     #[inline(always)]
     fn add(self, rhs: Self) -> Self {
         Self {
-            re: self.re + rhs.re,
-            im: self.im + rhs.im,
+            re: T::simd_add(self.re, rhs.re),
+            im: T::simd_add(self.im, rhs.im),
         }
     }
 }
 
-impl<const L: usize> Sub for BatchComplex<L>
+impl<T: FftFloat, const L: usize> Sub for BatchComplex<T, L>
 where
     LaneCount<L>: SupportedLaneCount,
 {
@@ -66,13 +63,13 @@ where
     #[inline(always)]
     fn sub(self, rhs: Self) -> Self {
         Self {
-            re: self.re - rhs.re,
-            im: self.im - rhs.im,
+            re: T::simd_sub(self.re, rhs.re),
+            im: T::simd_sub(self.im, rhs.im),
         }
     }
 }
 
-impl<const L: usize> Mul<f32> for BatchComplex<L>
+impl<T: FftFloat, const L: usize> Mul<T> for BatchComplex<T, L>
 where
     LaneCount<L>: SupportedLaneCount,
 {
@@ -80,16 +77,16 @@ where
 
     /// Multiplies each lane by a scalar.
     #[inline(always)]
-    fn mul(self, rhs: f32) -> Self {
+    fn mul(self, rhs: T) -> Self {
         let s = Simd::splat(rhs);
         Self {
-            re: self.re * s,
-            im: self.im * s,
+            re: T::simd_mul(self.re, s),
+            im: T::simd_mul(self.im, s),
         }
     }
 }
 
-impl<const L: usize> Mul<Complex32> for BatchComplex<L>
+impl<T: FftFloat, const L: usize> Mul<Complex<T>> for BatchComplex<T, L>
 where
     LaneCount<L>: SupportedLaneCount,
 {
@@ -97,17 +94,17 @@ where
 
     /// SIMD complex multiplication with a scalar complex value.
     #[inline(always)]
-    fn mul(self, rhs: Complex32) -> Self {
+    fn mul(self, rhs: Complex<T>) -> Self {
         let cre = Simd::splat(rhs.re);
         let cim = Simd::splat(rhs.im);
         Self {
-            re: self.re * cre - self.im * cim,
-            im: self.re * cim + self.im * cre,
+            re: T::simd_sub(T::simd_mul(self.re, cre), T::simd_mul(self.im, cim)),
+            im: T::simd_add(T::simd_mul(self.re, cim), T::simd_mul(self.im, cre)),
         }
     }
 }
 
-impl<const L: usize> Mul<BatchComplex<L>> for BatchComplex<L>
+impl<T: FftFloat, const L: usize> Mul<BatchComplex<T, L>> for BatchComplex<T, L>
 where
     LaneCount<L>: SupportedLaneCount,
 {
@@ -117,34 +114,111 @@ where
     #[inline(always)]
     fn mul(self, rhs: Self) -> Self {
         Self {
-            re: self.re * rhs.re - self.im * rhs.im,
-            im: self.re * rhs.im + self.im * rhs.re,
+            re: T::simd_sub(T::simd_mul(self.re, rhs.re), T::simd_mul(self.im, rhs.im)),
+            im: T::simd_add(T::simd_mul(self.re, rhs.im), T::simd_mul(self.im, rhs.re)),
         }
     }
 }
 
 /// Computes sin(pi * a / b).
 #[inline(always)]
-fn sab(a: f32, b: f32) -> f32 {
-    (PI * a / b).sin()
+fn sab<T: FftFloat>(a: f64, b: f64) -> T {
+    T::cast((PI * a / b).sin())
 }
 
 /// Computes cos(pi * a / b).
 #[inline(always)]
-fn cab(a: f32, b: f32) -> f32 {
-    (PI * a / b).cos()
+fn cab<T: FftFloat>(a: f64, b: f64) -> T {
+    T::cast((PI * a / b).cos())
 }
 
 /// Returns the imaginary-unit complex constant.
 #[inline(always)]
-fn i_c() -> Complex32 {
-    Complex32::new(0.0, 1.0)
+fn i_c<T: FftFloat>() -> Complex<T> {
+    Complex::new(T::zero(), T::one())
+}
+
+/// Returns the forward twiddle exp(-2πi·m/n).
+#[inline(always)]
+fn w_c<T: FftFloat>(m: usize, n: usize) -> Complex<T> {
+    Complex::new(
+        cab(2.0 * m as f64, n as f64),
+        -sab::<T>(2.0 * m as f64, n as f64),
+    )
+}
+
+/// Size-3 butterfly shared by composite kernels.
+#[inline(always)]
+fn bfly3<T: FftFloat, const L: usize>(
+    x0: BatchComplex<T, L>,
+    x1: BatchComplex<T, L>,
+    x2: BatchComplex<T, L>,
+) -> [BatchComplex<T, L>; 3]
+where
+    LaneCount<L>: SupportedLaneCount,
+{
+    let s: T = sab(1.0, 3.0);
+    let t0 = (x1 - x2) * s * i_c();
+    let u0 = x1 + x2;
+    let u1 = x0 - u0 * T::cast(0.5);
+    [x0 + u0, u1 - t0, u1 + t0]
+}
+
+/// Size-4 butterfly shared by composite kernels.
+#[inline(always)]
+fn bfly4<T: FftFloat, const L: usize>(
+    x0: BatchComplex<T, L>,
+    x1: BatchComplex<T, L>,
+    x2: BatchComplex<T, L>,
+    x3: BatchComplex<T, L>,
+) -> [BatchComplex<T, L>; 4]
+where
+    LaneCount<L>: SupportedLaneCount,
+{
+    let t0 = x0 + x2;
+    let t1 = x3 + x1;
+    let u0 = x0 - x2;
+    let u1 = (x3 - x1) * i_c();
+    [t0 + t1, u0 + u1, t0 - t1, u0 - u1]
+}
+
+/// Size-5 butterfly shared by composite kernels.
+#[inline(always)]
+fn bfly5<T: FftFloat, const L: usize>(
+    x0: BatchComplex<T, L>,
+    x1: BatchComplex<T, L>,
+    x2: BatchComplex<T, L>,
+    x3: BatchComplex<T, L>,
+    x4: BatchComplex<T, L>,
+) -> [BatchComplex<T, L>; 5]
+where
+    LaneCount<L>: SupportedLaneCount,
+{
+    let a: T = T::cast(0.25);
+    let b: T = sab(2.0, 5.0);
+    let c: T = sab(1.0, 5.0);
+    let d: T = cab::<T>(1.0, 5.0) - a;
+
+    let t0 = x1 + x4;
+    let t1 = x2 + x3;
+    let t2 = (t0 - t1) * d;
+    let u0 = x1 - x4;
+    let u1 = x2 - x3;
+    let u2 = t0 + t1;
+    let u3 = x0 - u2 * a;
+    let t4 = u3 + t2;
+    let t5 = (u0 * b + u1 * c) * i_c();
+    let u4 = u3 - t2;
+    let u5 = (u1 * b - u0 * c) * i_c();
+    [x0 + u2, t4 - t5, u4 + u5, u4 - u5, t4 + t5]
 }
 
 /// DFT kernel interface for SIMD-capable transform implementations.
 pub trait DftKernel {
-    fn transform<const L: usize>(x: &mut [BatchComplex<L>], X: &mut [BatchComplex<L>])
-    where
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
         LaneCount<L>: SupportedLaneCount;
 }
 
@@ -152,8 +226,10 @@ pub trait DftKernel {
 pub struct Kernel1;
 impl DftKernel for Kernel1 {
     #[inline(always)]
-    fn transform<const L: usize>(x: &mut [BatchComplex<L>], X: &mut [BatchComplex<L>])
-    where
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
         LaneCount<L>: SupportedLaneCount,
     {
         X[0] = x[0];
@@ -164,8 +240,10 @@ impl DftKernel for Kernel1 {
 pub struct Kernel2;
 impl DftKernel for Kernel2 {
     #[inline(always)]
-    fn transform<const L: usize>(x: &mut [BatchComplex<L>], X: &mut [BatchComplex<L>])
-    where
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
         LaneCount<L>: SupportedLaneCount,
     {
         X[0] = x[0] + x[1];
@@ -177,13 +255,16 @@ impl DftKernel for Kernel2 {
 pub struct Kernel3;
 impl DftKernel for Kernel3 {
     #[inline(always)]
-    fn transform<const L: usize>(x: &mut [BatchComplex<L>], X: &mut [BatchComplex<L>])
-    where
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
         LaneCount<L>: SupportedLaneCount,
     {
-        let t0 = (x[1] - x[2]) * sab(1.0, 3.0) * i_c();
+        let s: T = sab(1.0, 3.0);
+        let t0 = (x[1] - x[2]) * s * i_c();
         let u0 = x[1] + x[2];
-        let u1 = x[0] - u0 * 0.5;
+        let u1 = x[0] - u0 * T::cast(0.5);
         X[0] = x[0] + u0;
         X[1] = u1 - t0;
         X[2] = u1 + t0;
@@ -194,8 +275,10 @@ impl DftKernel for Kernel3 {
 pub struct Kernel4;
 impl DftKernel for Kernel4 {
     #[inline(always)]
-    fn transform<const L: usize>(x: &mut [BatchComplex<L>], X: &mut [BatchComplex<L>])
-    where
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
         LaneCount<L>: SupportedLaneCount,
     {
         let t0 = x[0] + x[2];
@@ -213,66 +296,38 @@ impl DftKernel for Kernel4 {
 pub struct Kernel5;
 impl DftKernel for Kernel5 {
     #[inline(always)]
-    fn transform<const L: usize>(x: &mut [BatchComplex<L>], X: &mut [BatchComplex<L>])
-    where
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
         LaneCount<L>: SupportedLaneCount,
     {
-        let a = 0.25;
-        let b = sab(2.0, 5.0);
-        let c = sab(1.0, 5.0);
-        let d = cab(1.0, 5.0) - a;
-
-        let t0 = x[1] + x[4];
-        let t1 = x[2] + x[3];
-        let t2 = (t0 - t1) * d;
-        let u0 = x[1] - x[4];
-        let u1 = x[2] - x[3];
-        let u2 = t0 + t1;
-        let u3 = x[0] - u2 * a;
-        let t4 = u3 + t2;
-        let t5 = (u0 * b + u1 * c) * i_c();
-
-        X[0] = x[0] + u2;
-
-        let u4 = u3 - t2;
-        let u5 = (u1 * b - u0 * c) * i_c();
-
-        X[1] = t4 - t5;
-        X[2] = u4 + u5;
-        X[4] = t4 + t5;
-        X[3] = u4 - u5;
+        X[..5].copy_from_slice(&bfly5(x[0], x[1], x[2], x[3], x[4]));
     }
 }
 
 /// Kernel for size-6 DFT.
+/// Splits into two size-3 butterflies over x[j] ± x[j+3]: the sums give the
+/// even outputs and the differences, with x[4] - x[1] absorbing the
+/// (-1)^j twiddle, give X[3], X[5], X[1].
 pub struct Kernel6;
 impl DftKernel for Kernel6 {
     #[inline(always)]
-    fn transform<const L: usize>(x: &mut [BatchComplex<L>], X: &mut [BatchComplex<L>])
-    where
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
         LaneCount<L>: SupportedLaneCount,
     {
-        let a = 0.5;
-        let b = sab(1.0, 3.0);
-
-        let t0 = x[0] + x[3];
-        let t1 = x[4] + x[1];
-        let t2 = x[2] + x[5];
-        let t3 = t0 - (t1 + t2) * a;
-        let t4 = (t1 - t2) * i_c();
-
-        let u0 = x[0] - x[3];
-        let u1 = x[4] - x[1];
-        let u2 = x[2] - x[5];
-        let u3 = u0 - (u1 + u2) * a * i_c();
-        let u4 = (u1 - u2) * i_c();
-
-        X[0] = t0 + t1 + t2;
-        X[1] = u3 + u4 * b;
-        X[4] = t3 + t4 * b;
-        X[3] = u0 + u1 + u2;
-        X[5] = u3 - u4 * b;
-        X[2] = t3 - t4 * b;
+        let [e0, e1, e2] = bfly3(x[0] + x[3], x[1] + x[4], x[2] + x[5]);
+        let [o0, o1, o2] = bfly3(x[0] - x[3], x[4] - x[1], x[2] - x[5]);
+
+        X[0] = e0;
+        X[2] = e1;
+        X[4] = e2;
+        X[3] = o0;
+        X[5] = o1;
+        X[1] = o2;
     }
 }
 
@@ -280,11 +335,13 @@ impl DftKernel for Kernel6 {
 pub struct Kernel8;
 impl DftKernel for Kernel8 {
     #[inline(always)]
-    fn transform<const L: usize>(x: &mut [BatchComplex<L>], X: &mut [BatchComplex<L>])
-    where
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
         LaneCount<L>: SupportedLaneCount,
     {
-        let a = sab(1.0, 4.0);
+        let a: T = sab(1.0, 4.0);
         let t0 = x[7] - x[3];
         let t1 = x[1] - x[5];
         let t2 = x[0] + x[4];
@@ -315,38 +372,307 @@ impl DftKernel for Kernel8 {
     }
 }
 
+/// Kernel for size-7 DFT.
+/// Pairs x[j] with x[7-j] so each output pair shares one cosine sum and
+/// one sine sum.
+pub struct Kernel7;
+impl DftKernel for Kernel7 {
+    #[inline(always)]
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        let c1: T = cab(2.0, 7.0);
+        let c2: T = cab(4.0, 7.0);
+        let c3: T = cab(6.0, 7.0);
+        let s1: T = sab(2.0, 7.0);
+        let s2: T = sab(4.0, 7.0);
+        let s3: T = sab(6.0, 7.0);
+
+        let t1 = x[1] + x[6];
+        let t2 = x[2] + x[5];
+        let t3 = x[3] + x[4];
+        let u1 = x[1] - x[6];
+        let u2 = x[2] - x[5];
+        let u3 = x[3] - x[4];
+
+        let a1 = x[0] + t1 * c1 + t2 * c2 + t3 * c3;
+        let a2 = x[0] + t1 * c2 + t2 * c3 + t3 * c1;
+        let a3 = x[0] + t1 * c3 + t2 * c1 + t3 * c2;
+        let b1 = (u1 * s1 + u2 * s2 + u3 * s3) * i_c();
+        let b2 = (u1 * s2 - u2 * s3 - u3 * s1) * i_c();
+        let b3 = (u1 * s3 - u2 * s1 + u3 * s2) * i_c();
+
+        X[0] = x[0] + t1 + t2 + t3;
+        X[1] = a1 - b1;
+        X[6] = a1 + b1;
+        X[2] = a2 - b2;
+        X[5] = a2 + b2;
+        X[3] = a3 - b3;
+        X[4] = a3 + b3;
+    }
+}
+
+/// Kernel for size-9 DFT.
+/// 3x3 Cooley-Tukey: size-3 columns, twiddles W9^(n1·k2), size-3 rows.
+pub struct Kernel9;
+impl DftKernel for Kernel9 {
+    #[inline(always)]
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        let a0 = bfly3(x[0], x[3], x[6]);
+        let a1 = bfly3(x[1], x[4], x[7]);
+        let a2 = bfly3(x[2], x[5], x[8]);
+
+        let b = [
+            bfly3(a0[0], a1[0], a2[0]),
+            bfly3(a0[1], a1[1] * w_c(1, 9), a2[1] * w_c(2, 9)),
+            bfly3(a0[2], a1[2] * w_c(2, 9), a2[2] * w_c(4, 9)),
+        ];
+
+        for (k, out) in X.iter_mut().enumerate().take(9) {
+            *out = b[k % 3][k / 3];
+        }
+    }
+}
+
+//
+// The 10-, 12- and 15-point kernels use the Good-Thomas prime factor
+// mapping for N = N1·N2 with coprime factors: input n = (N2·n1 + N1·n2)
+// mod N and output k = (A·k1 + B·k2) mod N, where A ≡ 1 (mod N1),
+// A ≡ 0 (mod N2) and B the other way round. Under that reindexing the
+// DFT is an exact N1 × N2 two-dimensional DFT, so unlike a Cooley-Tukey
+// step there are no twiddle multiplies between the two butterfly passes.
+// Multiply counts below exclude the trivial constants ±1 and ±i.
+//
+
+/// Kernel for size-10 DFT.
+/// Prime factor 2x5: five size-2 butterflies, then two size-5 ones.
+/// 24 real multiplies per transform, against 40 for a radix-2 step over
+/// size-5 leaves.
+pub struct Kernel10;
+impl DftKernel for Kernel10 {
+    #[inline(always)]
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        let a: [[BatchComplex<T, L>; 2]; 5] = std::array::from_fn(|n2| {
+            let (x0, x1) = (x[(2 * n2) % 10], x[(5 + 2 * n2) % 10]);
+            [x0 + x1, x0 - x1]
+        });
+        for (k1, &base) in [0, 5].iter().enumerate() {
+            let b = bfly5(a[0][k1], a[1][k1], a[2][k1], a[3][k1], a[4][k1]);
+            for (k2, out) in b.into_iter().enumerate() {
+                X[(base + 6 * k2) % 10] = out;
+            }
+        }
+    }
+}
+
+/// Kernel for size-12 DFT.
+/// Prime factor 3x4: four size-3 butterflies, then three size-4 ones,
+/// which need no multiplies. 16 real multiplies per transform, against 32
+/// for a radix-4 step over size-3 leaves.
+pub struct Kernel12;
+impl DftKernel for Kernel12 {
+    #[inline(always)]
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        let a: [[BatchComplex<T, L>; 3]; 4] = std::array::from_fn(|n2| {
+            bfly3(x[(3 * n2) % 12], x[(4 + 3 * n2) % 12], x[(8 + 3 * n2) % 12])
+        });
+        for (k1, &base) in [0, 4, 8].iter().enumerate() {
+            let b = bfly4(a[0][k1], a[1][k1], a[2][k1], a[3][k1]);
+            for (k2, out) in b.into_iter().enumerate() {
+                X[(base + 9 * k2) % 12] = out;
+            }
+        }
+    }
+}
+
+/// Kernel for size-15 DFT.
+/// Prime factor 3x5: five size-3 butterflies, then three size-5 ones.
+/// 56 real multiplies per transform, against 88 for a radix-3 step over
+/// size-5 leaves.
+pub struct Kernel15;
+impl DftKernel for Kernel15 {
+    #[inline(always)]
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        let a: [[BatchComplex<T, L>; 3]; 5] = std::array::from_fn(|n2| {
+            bfly3(
+                x[(3 * n2) % 15],
+                x[(5 + 3 * n2) % 15],
+                x[(10 + 3 * n2) % 15],
+            )
+        });
+        for (k1, &base) in [0, 10, 5].iter().enumerate() {
+            let b = bfly5(a[0][k1], a[1][k1], a[2][k1], a[3][k1], a[4][k1]);
+            for (k2, out) in b.into_iter().enumerate() {
+                X[(base + 6 * k2) % 15] = out;
+            }
+        }
+    }
+}
+
+/// Kernel for size-16 DFT.
+/// 4x4 Cooley-Tukey: size-4 columns, twiddles W16^(n1·k2), size-4 rows.
+pub struct Kernel16;
+impl DftKernel for Kernel16 {
+    #[inline(always)]
+    fn transform<T: FftFloat, const L: usize>(
+        x: &mut [BatchComplex<T, L>],
+        X: &mut [BatchComplex<T, L>],
+    ) where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        let a0 = bfly4(x[0], x[4], x[8], x[12]);
+        let a1 = bfly4(x[1], x[5], x[9], x[13]);
+        let a2 = bfly4(x[2], x[6], x[10], x[14]);
+        let a3 = bfly4(x[3], x[7], x[11], x[15]);
+
+        let b = [
+            bfly4(a0[0], a1[0], a2[0], a3[0]),
+            bfly4(
+                a0[1],
+                a1[1] * w_c(1, 16),
+                a2[1] * w_c(2, 16),
+                a3[1] * w_c(3, 16),
+            ),
+            bfly4(
+                a0[2],
+                a1[2] * w_c(2, 16),
+                a2[2] * w_c(4, 16),
+                a3[2] * w_c(6, 16),
+            ),
+            bfly4(
+                a0[3],
+                a1[3] * w_c(3, 16),
+                a2[3] * w_c(6, 16),
+                a3[3] * w_c(9, 16),
+            ),
+        ];
+
+        for (k, out) in X.iter_mut().enumerate().take(16) {
+            *out = b[k % 4][k / 4];
+        }
+    }
+}
+
+/// Number of `T` lanes in one vector register, between 4 and 16. With f32,
+/// AVX-512 gives 16 lanes, AVX 8, and SSE or NEON 4.
+///
+/// Registers wider than SSE only count when both the CPU and the build
+/// support them (e.g. `-C target-cpu=native`). Without the target feature a
+/// wide batch is split into several narrower instructions anyway.
+pub fn max_lanes<T: FftFloat>() -> usize {
+    static REGISTER_BYTES: OnceLock<usize> = OnceLock::new();
+    let bytes = *REGISTER_BYTES.get_or_init(register_bytes);
+    (bytes / std::mem::size_of::<T>()).clamp(4, 16)
+}
+
+/// Vector register size in bytes usable by this build on this CPU.
+fn register_bytes() -> usize {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if cfg!(target_feature = "avx512f") && is_x86_feature_detected!("avx512f") {
+            return 64;
+        }
+        if cfg!(target_feature = "avx") && is_x86_feature_detected!("avx") {
+            return 32;
+        }
+    }
+    16
+}
+
 /// DFT implementation using SIMD gather/transform/scatter.
 /// Parameterized by kernel type and transform size.
-pub struct DFTImproved<K: DftKernel, const N: usize> {
-    _marker: std::marker::PhantomData<K>,
+/// Inverse transforms reuse the forward kernels by swapping real and
+/// imaginary parts on gather and scatter: IDFT(x) = swap(DFT(swap(x))).
+pub struct DFTImproved<T: FftFloat, K: DftKernel, const N: usize> {
+    direction: Direction,
+
+    //
+    // Widest batch `dosimd2` tries; 16, 8, 4 or 1.
+    //
+    lanes: usize,
+    _marker: std::marker::PhantomData<(T, K)>,
 }
 
-impl<K: DftKernel + Send + Sync + 'static, const N: usize> DFTImproved<K, N> {
-    pub fn new() -> Self {
+impl<T: FftFloat, K: DftKernel + Send + Sync + 'static, const N: usize> DFTImproved<T, K, N> {
+    /// Creates a plan with the batch width suited to `N` on this machine.
+    pub fn new(direction: Direction) -> Self {
+        //
+        // Eight lanes whatever the register width: they beat four even on
+        // SSE or NEON, where a batch takes two registers, so the detected
+        // width never lowers it. Sixteen only pay off for the 16-point
+        // kernel with AVX-512 enabled; smaller kernels get slower at that
+        // width, and without AVX-512 so does the 16-point one.
+        //
+        let lanes = if N >= 16 && max_lanes::<T>() >= 16 {
+            16
+        } else {
+            8
+        };
+        Self::with_lanes(direction, lanes)
+    }
+
+    /// Creates a plan batching at most `lanes` transforms at once, e.g. to
+    /// compare widths. `lanes` must be 1, 4, 8 or 16.
+    pub fn with_lanes(direction: Direction, lanes: usize) -> Self {
+        assert!(
+            matches!(lanes, 1 | 4 | 8 | 16),
+            "unsupported SIMD lane count {}",
+            lanes
+        );
         Self {
+            direction,
+            lanes,
             _marker: std::marker::PhantomData,
         }
     }
 
+    pub fn lanes(&self) -> usize {
+        self.lanes
+    }
+
     /// Gathers strided input into SIMD batches, applies the kernel, and scatters results back.
     #[inline(always)]
     fn dosimd3<const L: usize>(
         &self,
-        input: &[Complex32],
+        input: &[Complex<T>],
         istep: usize,
         istep2: usize,
-        output: &mut [Complex32],
+        output: &mut [Complex<T>],
         ostep: usize,
         ostep2: usize,
     ) where
         LaneCount<L>: SupportedLaneCount,
     {
-        let mut x = [BatchComplex::<L>::zero(); N];
-        let mut X = [BatchComplex::<L>::zero(); N];
+        let mut x = [BatchComplex::<T, L>::zero(); N];
+        let mut X = [BatchComplex::<T, L>::zero(); N];
+        let swap = self.direction == Direction::Inverse;
 
-        for a in 0..N {
-            let mut re_arr = [0.0f32; L];
-            let mut im_arr = [0.0f32; L];
+        for (a, xa) in x.iter_mut().enumerate() {
+            let mut re_arr = [T::zero(); L];
+            let mut im_arr = [T::zero(); L];
 
             for b in 0..L {
                 let idx = a * istep + b * istep2;
@@ -355,30 +681,38 @@ impl<K: DftKernel + Send + Sync + 'static, const N: usize> DFTImproved<K, N> {
                 im_arr[b] = c.im;
             }
 
-            x[a].re = Simd::from_array(re_arr);
-            x[a].im = Simd::from_array(im_arr);
+            if swap {
+                std::mem::swap(&mut re_arr, &mut im_arr);
+            }
+            xa.re = Simd::from_array(re_arr);
+            xa.im = Simd::from_array(im_arr);
         }
 
         K::transform(&mut x, &mut X);
 
-        for a in 0..N {
-            let re_arr = X[a].re.to_array();
-            let im_arr = X[a].im.to_array();
+        for (a, xa) in X.iter().enumerate() {
+            let mut re_arr = xa.re.to_array();
+            let mut im_arr = xa.im.to_array();
+            if swap {
+                std::mem::swap(&mut re_arr, &mut im_arr);
+            }
 
             for b in 0..L {
                 let idx = a * ostep + b * ostep2;
-                output[idx] = Complex32::new(re_arr[b], im_arr[b]);
+                output[idx] = Complex::new(re_arr[b], im_arr[b]);
             }
         }
     }
 
-    /// Attempts SIMD processing in widths 8, then 4, then scalar width 1.
+    /// Processes batches of 16, 8 and 4 transforms, skipping widths above
+    /// `lanes`, then the remainder at scalar width 1.
+    #[allow(clippy::too_many_arguments)]
     fn dosimd2(
         &self,
-        mut input: &[Complex32],
+        input: &[Complex<T>],
         istep: usize,
         mut istep2: usize,
-        mut output: &mut [Complex32],
+        output: &mut [Complex<T>],
         ostep: usize,
         mut ostep2: usize,
         num: usize,
@@ -388,54 +722,223 @@ impl<K: DftKernel + Send + Sync + 'static, const N: usize> DFTImproved<K, N> {
             ostep2 = 0;
         }
 
+        //
+        // Slice from each batch's own offset: advancing past the last batch
+        // can run off the end of a tightly sized strided buffer.
+        //
         let mut n = 0;
 
-        while n + 8 <= num {
-            self.dosimd3::<8>(input, istep, istep2, output, ostep, ostep2);
-            input = &input[8 * istep2..];
-            output = &mut output[8 * ostep2..];
+        while self.lanes >= 16 && n + 16 <= num {
+            self.dosimd3::<16>(
+                &input[n * istep2..],
+                istep,
+                istep2,
+                &mut output[n * ostep2..],
+                ostep,
+                ostep2,
+            );
+            n += 16;
+        }
+
+        while self.lanes >= 8 && n + 8 <= num {
+            self.dosimd3::<8>(
+                &input[n * istep2..],
+                istep,
+                istep2,
+                &mut output[n * ostep2..],
+                ostep,
+                ostep2,
+            );
             n += 8;
         }
 
-        while n + 4 <= num {
-            self.dosimd3::<4>(input, istep, istep2, output, ostep, ostep2);
-            input = &input[4 * istep2..];
-            output = &mut output[4 * ostep2..];
+        while self.lanes >= 4 && n + 4 <= num {
+            self.dosimd3::<4>(
+                &input[n * istep2..],
+                istep,
+                istep2,
+                &mut output[n * ostep2..],
+                ostep,
+                ostep2,
+            );
             n += 4;
         }
 
         while n < num {
-            self.dosimd3::<1>(input, istep, istep2, output, ostep, ostep2);
-            input = &input[1 * istep2..];
-            output = &mut output[1 * ostep2..];
+            self.dosimd3::<1>(
+                &input[n * istep2..],
+                istep,
+                istep2,
+                &mut output[n * ostep2..],
+                ostep,
+                ostep2,
+            );
             n += 1;
         }
     }
 }
 
-impl<K: DftKernel + Send + Sync + 'static, const N: usize> DFTBase for DFTImproved<K, N> {
+impl<T: FftFloat, K: DftKernel + Send + Sync + 'static, const N: usize> DFTBase<T>
+    for DFTImproved<T, K, N>
+{
     fn name(&self) -> String {
-        format!("Improved_{}", N)
+        format!("{}Improved_{}", self.direction.tag(), N)
     }
 
     fn size(&self) -> usize {
         N
     }
 
+    fn direction(&self) -> Direction {
+        self.direction
+    }
+
     fn is_inplace(&self) -> bool {
         true
     }
 
     fn xform_many(
         &self,
-        input: &[Complex32],
-        output: &mut [Complex32],
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
         istep: usize,
         istep2: usize,
         ostep: usize,
         ostep2: usize,
         count: usize,
     ) {
+        debug_check_strides(
+            self.size(),
+            input.len(),
+            output.len(),
+            istep,
+            istep2,
+            ostep,
+            ostep2,
+            count,
+        );
         self.dosimd2(input, istep, istep2, output, ostep, ostep2, count);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_complex::Complex32;
+
+    /// Runs kernel `K` on `L` lanes of pseudo-random input and compares every
+    /// lane against a naive O(N²) DFT.
+    fn check_kernel<K: DftKernel, const N: usize, const L: usize>()
+    where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        let mut seed = 0x2545_f491_u32;
+        let mut next = || {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0
+        };
+
+        let lanes: Vec<Vec<Complex32>> = (0..L)
+            .map(|_| (0..N).map(|_| Complex32::new(next(), next())).collect())
+            .collect();
+
+        let mut x = [BatchComplex::<f32, L>::zero(); N];
+        let mut X = [BatchComplex::<f32, L>::zero(); N];
+        for (a, batch) in x.iter_mut().enumerate() {
+            batch.re = Simd::from_array(std::array::from_fn(|b| lanes[b][a].re));
+            batch.im = Simd::from_array(std::array::from_fn(|b| lanes[b][a].im));
+        }
+
+        K::transform(&mut x, &mut X);
+
+        for (b, lane) in lanes.iter().enumerate() {
+            for (k, out) in X.iter().enumerate() {
+                let expected: Complex32 = lane
+                    .iter()
+                    .enumerate()
+                    .map(|(j, &v)| v * w_c((j * k) % N, N))
+                    .sum();
+                let actual = Complex32::new(out.re[b], out.im[b]);
+                assert!(
+                    (actual - expected).norm() < 1e-4 * N as f32,
+                    "N={} L={} lane={} k={}: got {} want {}",
+                    N,
+                    L,
+                    b,
+                    k,
+                    actual,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn kernel6_matches_naive_dft() {
+        check_kernel::<Kernel6, 6, 1>();
+        check_kernel::<Kernel6, 6, 4>();
+        check_kernel::<Kernel6, 6, 8>();
+        check_kernel::<Kernel6, 6, 16>();
+    }
+
+    #[test]
+    fn kernel7_matches_naive_dft() {
+        check_kernel::<Kernel7, 7, 1>();
+        check_kernel::<Kernel7, 7, 4>();
+        check_kernel::<Kernel7, 7, 8>();
+        check_kernel::<Kernel7, 7, 16>();
+    }
+
+    #[test]
+    fn kernel9_matches_naive_dft() {
+        check_kernel::<Kernel9, 9, 1>();
+        check_kernel::<Kernel9, 9, 4>();
+        check_kernel::<Kernel9, 9, 8>();
+        check_kernel::<Kernel9, 9, 16>();
+    }
+
+    #[test]
+    fn prime_factor_kernels_match_naive_dft() {
+        check_kernel::<Kernel10, 10, 1>();
+        check_kernel::<Kernel10, 10, 4>();
+        check_kernel::<Kernel10, 10, 8>();
+        check_kernel::<Kernel10, 10, 16>();
+        check_kernel::<Kernel12, 12, 1>();
+        check_kernel::<Kernel12, 12, 4>();
+        check_kernel::<Kernel12, 12, 8>();
+        check_kernel::<Kernel12, 12, 16>();
+        check_kernel::<Kernel15, 15, 1>();
+        check_kernel::<Kernel15, 15, 4>();
+        check_kernel::<Kernel15, 15, 8>();
+        check_kernel::<Kernel15, 15, 16>();
+    }
+
+    #[test]
+    fn kernel16_matches_naive_dft() {
+        check_kernel::<Kernel16, 16, 1>();
+        check_kernel::<Kernel16, 16, 4>();
+        check_kernel::<Kernel16, 16, 8>();
+        check_kernel::<Kernel16, 16, 16>();
+    }
+
+    #[test]
+    fn every_lane_width_gives_the_same_batch() {
+        //
+        // 29 transforms exercise every width plus a scalar remainder.
+        //
+        let count = 29;
+        let input: Vec<Complex32> = (0..16 * count)
+            .map(|i| Complex32::new((0.37 * i as f32).sin(), (0.11 * i as f32).cos()))
+            .collect();
+        let run = |lanes| {
+            let plan = DFTImproved::<f32, Kernel16, 16>::with_lanes(Direction::Forward, lanes);
+            let mut output = vec![Complex32::default(); input.len()];
+            plan.xform_many(&input, &mut output, 1, 16, 1, 16, count);
+            output
+        };
+        let scalar = run(1);
+        for lanes in [4, 8, 16] {
+            assert_eq!(run(lanes), scalar, "lanes={}", lanes);
+        }
+    }
+}
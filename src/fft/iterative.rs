@@ -0,0 +1,182 @@
+use super::orig::w;
+#[cfg(feature = "parallel")]
+use super::parallel;
+use super::{debug_check_strides, DFTBase, Direction, FftFloat};
+use num_complex::Complex;
+
+/// Iterative in-place radix-2 DFT for `n = 2^k`.
+///
+/// The input is gathered into the output in bit-reversed order through a
+/// precomputed permutation table, so there is no separate reordering pass,
+/// and `log2(n)` butterfly stages then run in place using one table of
+/// `n/2` twiddles. It never recurses or calls the planner, which keeps the
+/// data in one buffer and the plan self-contained.
+pub struct DFTIterativeP2<T: FftFloat> {
+    n: usize,
+    direction: Direction,
+
+    //
+    // W^k for k in 0..n/2; stage `len` steps through it by n/len.
+    //
+    twiddles: Vec<Complex<T>>,
+
+    //
+    // Output slot k reads input sample bitrev[k].
+    //
+    bitrev: Vec<usize>,
+}
+
+impl<T: FftFloat> DFTIterativeP2<T> {
+    pub fn new(n: usize, direction: Direction) -> Self {
+        assert!(
+            n.is_power_of_two(),
+            "iterative radix-2 size must be a power of two, got {}",
+            n
+        );
+
+        let bits = n.trailing_zeros();
+        let bitrev = (0..n)
+            .map(|k| match bits {
+                0 => 0,
+                _ => k.reverse_bits() >> (usize::BITS - bits),
+            })
+            .collect();
+        Self {
+            n,
+            direction,
+            twiddles: (0..n / 2).map(|k| w(k, n, direction)).collect(),
+            bitrev,
+        }
+    }
+}
+
+impl<T: FftFloat> DFTBase<T> for DFTIterativeP2<T> {
+    fn name(&self) -> String {
+        format!("{}IterativeP2({})", self.direction.tag(), self.n)
+    }
+    fn size(&self) -> usize {
+        self.n
+    }
+    fn direction(&self) -> Direction {
+        self.direction
+    }
+    fn is_inplace(&self) -> bool {
+        false
+    }
+
+    fn xform_many(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+    ) {
+        debug_check_strides(
+            self.size(),
+            input.len(),
+            output.len(),
+            istep,
+            istep2,
+            ostep,
+            ostep2,
+            count,
+        );
+        #[cfg(feature = "parallel")]
+        if count >= parallel::MIN_PARALLEL_COUNT {
+            return parallel::xform_batches(
+                self.n,
+                input,
+                output,
+                istep,
+                istep2,
+                ostep,
+                ostep2,
+                count,
+                |i, o, is, is2, os, os2, c| self.xform_many_serial(i, o, is, is2, os, os2, c),
+            );
+        }
+        self.xform_many_serial(input, output, istep, istep2, ostep, ostep2, count);
+    }
+}
+
+impl<T: FftFloat> DFTIterativeP2<T> {
+    #[allow(clippy::too_many_arguments)]
+    fn xform_many_serial(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+    ) {
+        let n = self.n;
+        for i in 0..count {
+            let in_base = i * istep2;
+            let out_base = i * ostep2;
+            for (k, &src) in self.bitrev.iter().enumerate() {
+                output[out_base + k * ostep] = input[in_base + src * istep];
+            }
+
+            //
+            // Butterflies of span `half` within blocks of `len`; the first
+            // stage's only twiddle is 1.
+            //
+            for pair in 0..n / 2 {
+                let i0 = out_base + 2 * pair * ostep;
+                let i1 = i0 + ostep;
+                let (a, b) = (output[i0], output[i1]);
+                output[i0] = a + b;
+                output[i1] = a - b;
+            }
+            let mut len = 4;
+            while len <= n {
+                let half = len / 2;
+                let stride = n / len;
+                for start in (0..n).step_by(len) {
+                    for j in 0..half {
+                        let i0 = out_base + (start + j) * ostep;
+                        let i1 = i0 + half * ostep;
+                        let t = output[i1] * self.twiddles[j * stride];
+                        let a = output[i0];
+                        output[i0] = a + t;
+                        output[i1] = a - t;
+                    }
+                }
+                len *= 2;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_complex::Complex32;
+
+    #[test]
+    fn round_trip_recovers_the_input() {
+        for n in [1, 2, 4, 32, 256, 2048, 8192] {
+            let input: Vec<Complex32> = (0..n)
+                .map(|i| Complex32::new((i as f32 * 0.7).sin(), (i as f32 * 0.3).cos()))
+                .collect();
+
+            let plan = DFTIterativeP2::new(n, Direction::Forward);
+            let mut spectrum = vec![Complex32::default(); n];
+            let mut restored = vec![Complex32::default(); n];
+            plan.xform(&input, &mut spectrum);
+            plan.xform_inverse(&spectrum, &mut restored);
+
+            let err = input
+                .iter()
+                .zip(&restored)
+                .map(|(a, b)| (a - b).norm())
+                .fold(0.0, f32::max);
+            assert!(err < 1e-5, "n={}: round-trip error {}", n, err);
+        }
+    }
+}
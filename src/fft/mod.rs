@@ -1,19 +1,130 @@
+pub mod bins;
+pub mod chirpz;
+pub mod convolve;
+pub mod dft2d;
 #[cfg(feature = "use_fftw")]
 pub mod fftw;
+pub mod float;
 pub mod improved;
+pub mod iterative;
 pub mod orig;
+#[cfg(feature = "parallel")]
+mod parallel;
 pub mod prime_cache;
+pub mod prime_power;
+pub mod real;
+mod scratch;
+pub mod split_radix;
+
+pub use bins::{bin_to_hz, bin_width_hz, hz_to_bin, nyquist_hz};
+pub use chirpz::ChirpZ;
+pub use convolve::Convolver;
+pub use dft2d::DFT2D;
+pub use float::FftFloat;
 
 use lazy_static::lazy_static;
-use num_complex::Complex32;
+use num_complex::{Complex, Complex32};
 use parking_lot::Mutex;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+/// Failure to build a transform plan.
+#[derive(Debug)]
+pub enum FftError {
+    /// An external library could not plan an `n`-point transform.
+    Plan { n: usize, reason: String },
+}
+
+impl fmt::Display for FftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FftError::Plan { n, reason } => {
+                write!(f, "failed to plan a {}-point transform: {}", n, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FftError {}
+
+/// Transform direction.
+/// `Forward` uses the exp(-2πi·jk/N) kernel and `Inverse` its conjugate.
+/// Neither direction applies a 1/N normalization; use
+/// `DFTBase::xform_inverse` for a normalized round trip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Forward,
+    Inverse,
+}
+
+impl Direction {
+    /// Sign of the twiddle exponent: -1 for forward, +1 for inverse.
+    pub fn sign(self) -> f32 {
+        match self {
+            Direction::Forward => -1.0,
+            Direction::Inverse => 1.0,
+        }
+    }
+
+    /// The direction that undoes this one.
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Forward => Direction::Inverse,
+            Direction::Inverse => Direction::Forward,
+        }
+    }
+
+    /// Short prefix used in plan names.
+    fn tag(self) -> &'static str {
+        match self {
+            Direction::Forward => "",
+            Direction::Inverse => "Inv",
+        }
+    }
+}
+
+/// Sign convention of the forward transform kernel.
+/// `Negative` is exp(-2πi·jk/N), used throughout this crate; `Positive`
+/// matches libraries that define the forward DFT with exp(+2πi·jk/N).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SignConvention {
+    Negative,
+    Positive,
+}
+
+impl SignConvention {
+    /// Maps a direction under this convention onto the crate's own direction.
+    pub fn resolve(self, direction: Direction) -> Direction {
+        match (self, direction) {
+            (SignConvention::Negative, d) => d,
+            (SignConvention::Positive, Direction::Forward) => Direction::Inverse,
+            (SignConvention::Positive, Direction::Inverse) => Direction::Forward,
+        }
+    }
+}
 
 /// Base interface for all DFT implementations.
-pub trait DFTBase: Send + Sync {
+///
+/// Plans are immutable once built and may be shared across threads. A plan
+/// of size `n` reads `n` elements per transform and writes `n` bins; no
+/// 1/N normalization is applied except by the `*_inverse` helpers.
+///
+/// The native strategies hold no locks while transforming, so one plan can
+/// serve any number of threads at once without them waiting on each other.
+/// FFTW plans serialize callers per plan/buffer pair; see `find_dft_pooled`.
+///
+/// The built-in strategies allocate their twiddles and working buffers when
+/// the plan is built or on first use at a given `count`, so repeated
+/// `xform`/`xform_many` calls do not allocate. The exceptions are the
+/// default `xform_inplace` (one copy of the buffer for plans that are not
+/// `is_inplace`), calls that race another thread for the same plan's
+/// scratch, and batches split across threads by the `parallel` feature.
+pub trait DFTBase<T: FftFloat = f32>: Send + Sync {
     /// Single transform using contiguous input/output.
-    fn xform(&self, input: &[Complex32], output: &mut [Complex32]) {
+    fn xform(&self, input: &[Complex<T>], output: &mut [Complex<T>]) {
         self.xform_many(input, output, 1, 0, 1, 0, 1);
     }
 
@@ -22,10 +133,20 @@ pub trait DFTBase: Send + Sync {
     /// `istep2` = stride between distinct transforms.
     /// `ostep`  = output element stride.
     /// `ostep2` = output transform stride.
+    ///
+    /// Element `j` of transform `i` is read from `input[j * istep + i * istep2]`
+    /// and bin `k` is written to `output[k * ostep + i * ostep2]`, so both
+    /// slices must cover the largest such index. `count` contiguous
+    /// transforms of length `n` use `(1, n, 1, n, count)`; `count`
+    /// interleaved columns of a row-major matrix use `(count, 1, count, 1, count)`.
+    /// Input and output must not overlap; use `xform_inplace` for that.
+    /// Debug builds panic up front, naming the stride, if either slice is
+    /// too short.
+    #[allow(clippy::too_many_arguments)]
     fn xform_many(
         &self,
-        input: &[Complex32],
-        output: &mut [Complex32],
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
         istep: usize,
         istep2: usize,
         ostep: usize,
@@ -34,68 +155,724 @@ pub trait DFTBase: Send + Sync {
     );
 
     /// Default in-place transform: temporary buffer copy.
-    fn xform_inplace(&self, buffer: &mut [Complex32]) {
+    fn xform_inplace(&self, buffer: &mut [Complex<T>]) {
         let temp = buffer.to_vec();
         self.xform_many(&temp, buffer, 1, 0, 1, 0, 1);
     }
 
+    /// Normalized inverse of this plan, using the same strides as
+    /// `xform_many`. Runs the opposite-direction plan of the same size
+    /// and scales by 1/N, so `xform_inverse(xform(x)) ≈ x`. Rader and
+    /// Bluestein plans instead run themselves on conjugated data.
+    #[allow(clippy::too_many_arguments)]
+    fn xform_many_inverse(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+    ) {
+        let n = self.size();
+        find_dft_generic::<T>(n, self.direction().opposite())
+            .xform_many(input, output, istep, istep2, ostep, ostep2, count);
+
+        let scale = T::one() / T::cast(n as f64);
+        for i in 0..count {
+            for k in 0..n {
+                output[k * ostep + i * ostep2] *= scale;
+            }
+        }
+    }
+
+    /// Single normalized inverse using contiguous input/output.
+    fn xform_inverse(&self, input: &[Complex<T>], output: &mut [Complex<T>]) {
+        self.xform_many_inverse(input, output, 1, 0, 1, 0, 1);
+    }
+
     fn name(&self) -> String;
     fn size(&self) -> usize;
+    fn direction(&self) -> Direction;
     fn is_inplace(&self) -> bool;
 }
 
+/// Debug-build check of `xform_many`'s contract: both slices must cover
+/// every index `count` size-`n` transforms reach through their strides.
+/// Called first by each implementation, so a bad stride fails with the
+/// parameter named instead of deep inside a kernel's indexing.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn debug_check_strides(
+    n: usize,
+    input_len: usize,
+    output_len: usize,
+    istep: usize,
+    istep2: usize,
+    ostep: usize,
+    ostep2: usize,
+    count: usize,
+) {
+    if cfg!(debug_assertions) && n > 0 && count > 0 {
+        check_span(
+            "input",
+            input_len,
+            n,
+            ("istep", istep),
+            ("istep2", istep2),
+            count,
+        );
+        check_span(
+            "output",
+            output_len,
+            n,
+            ("ostep", ostep),
+            ("ostep2", ostep2),
+            count,
+        );
+    }
+}
+
+fn check_span(
+    slice: &str,
+    len: usize,
+    n: usize,
+    (step_name, step): (&str, usize),
+    (step2_name, step2): (&str, usize),
+    count: usize,
+) {
+    //
+    // Reported as the first index past the end, saturating so absurd
+    // strides still reach the message rather than an overflow panic.
+    //
+    let element_end = (n - 1).saturating_mul(step).saturating_add(1);
+    assert!(
+        element_end <= len,
+        "xform_many: {} = {} needs {} {} elements for one size-{} transform, but it has {}",
+        step_name,
+        step,
+        element_end,
+        slice,
+        n,
+        len
+    );
+    let batch_end = (count - 1)
+        .saturating_mul(step2)
+        .saturating_add(element_end);
+    assert!(
+        batch_end <= len,
+        "xform_many: {} = {} with count = {} needs {} {} elements, but it has {}",
+        step2_name,
+        step2,
+        count,
+        batch_end,
+        slice,
+        len
+    );
+}
+
+/// Interface for real-input forward transforms.
+pub trait RDFTBase: Send + Sync {
+    /// Transforms `n` real samples into the `n/2 + 1` non-redundant bins.
+    fn xform_real(&self, input: &[f32], output: &mut [Complex32]);
+
+    fn name(&self) -> String;
+    fn size(&self) -> usize;
+}
+
+//
+// Once-filled entry of the real plan cache, as in `PlanCache`.
+//
+type RealPlanSlot = Arc<OnceLock<Arc<dyn RDFTBase>>>;
+
 lazy_static! {
-    static ref PLAN_CACHE: Mutex<HashMap<usize, Arc<dyn DFTBase>>> = Mutex::new(HashMap::new());
+    static ref REAL_PLAN_CACHE: Mutex<HashMap<usize, RealPlanSlot>> = Mutex::new(HashMap::new());
 }
 
-/// Returns a DFT plan for size `n`, using caching and heuristic selection.
-pub fn find_dft(n: usize) -> Arc<dyn DFTBase> {
-    // Cached plan lookup.
-    {
-        let cache = PLAN_CACHE.lock();
-        if let Some(plan) = cache.get(&n) {
-            return plan.clone();
+/// Returns an `f32` DFT plan for size `n` in the given direction.
+///
+/// ```
+/// use fftanalyzer::{find_dft, Direction};
+/// use num_complex::Complex32;
+///
+/// let plan = find_dft(4, Direction::Forward);
+/// let input = [1.0, 0.0, 0.0, 0.0].map(|re| Complex32::new(re, 0.0));
+/// let mut output = [Complex32::default(); 4];
+/// plan.xform(&input, &mut output);
+///
+/// // An impulse at index 0 has a flat spectrum.
+/// assert!(output.iter().all(|c| (c - Complex32::new(1.0, 0.0)).norm() < 1e-6));
+/// ```
+pub fn find_dft(n: usize, direction: Direction) -> Arc<dyn DFTBase> {
+    find_dft_generic::<f32>(n, direction)
+}
+
+/// Returns an `f64` DFT plan for size `n` in the given direction.
+pub fn find_dft_f64(n: usize, direction: Direction) -> Arc<dyn DFTBase<f64>> {
+    find_dft_generic::<f64>(n, direction)
+}
+
+/// Returns a DFT plan for size `n` in the given direction, using caching and
+/// heuristic selection. Forward and inverse plans are cached separately, and
+/// each element type has its own cache.
+///
+/// Concurrent calls are safe and idempotent: each size and direction is
+/// built once, and callers racing for it wait for that plan. The cache
+/// lock is not held while building, so the sub-plans a strategy requests
+/// are built (or waited for) independently.
+///
+/// Panics if `n` is 0. Size 1 is the identity transform.
+pub fn find_dft_generic<T: FftFloat>(n: usize, direction: Direction) -> Arc<dyn DFTBase<T>> {
+    //
+    // No strategy covers an empty transform; Rader would underflow on
+    // `n - 1` trying.
+    //
+    assert!(n > 0, "cannot plan a DFT of size 0");
+
+    //
+    // Claim the key's slot under the lock, then build outside it. Sub-plans
+    // are always other sizes, so a build never waits on its own slot.
+    //
+    let slot = T::plan_cache()
+        .lock()
+        .entry((n, direction))
+        .or_default()
+        .clone();
+    slot.get_or_init(|| build_plan::<T>(n, direction)).clone()
+}
+
+//
+// Largest power of two planned with the iterative radix-2 strategy. It was
+// a few percent faster than split-radix up to here and no faster beyond,
+// where split-radix's lower operation count also keeps its error smaller.
+//
+const ITERATIVE_P2_MAX: usize = 4096;
+
+/// Strategy selection for `find_dft_generic`.
+fn build_plan<T: FftFloat>(n: usize, direction: Direction) -> Arc<dyn DFTBase<T>> {
+    if let Some(plan) = T::external_plan(n, direction) {
+        plan
+    } else {
+        match n {
+            1 => Arc::new(improved::DFTImproved::<T, improved::Kernel1, 1>::new(
+                direction,
+            )),
+            2 => Arc::new(improved::DFTImproved::<T, improved::Kernel2, 2>::new(
+                direction,
+            )),
+            3 => Arc::new(improved::DFTImproved::<T, improved::Kernel3, 3>::new(
+                direction,
+            )),
+            4 => Arc::new(improved::DFTImproved::<T, improved::Kernel4, 4>::new(
+                direction,
+            )),
+            5 => Arc::new(improved::DFTImproved::<T, improved::Kernel5, 5>::new(
+                direction,
+            )),
+            6 => Arc::new(improved::DFTImproved::<T, improved::Kernel6, 6>::new(
+                direction,
+            )),
+            7 => Arc::new(improved::DFTImproved::<T, improved::Kernel7, 7>::new(
+                direction,
+            )),
+            8 => Arc::new(improved::DFTImproved::<T, improved::Kernel8, 8>::new(
+                direction,
+            )),
+            9 => Arc::new(improved::DFTImproved::<T, improved::Kernel9, 9>::new(
+                direction,
+            )),
+            10 => Arc::new(improved::DFTImproved::<T, improved::Kernel10, 10>::new(
+                direction,
+            )),
+            12 => Arc::new(improved::DFTImproved::<T, improved::Kernel12, 12>::new(
+                direction,
+            )),
+            15 => Arc::new(improved::DFTImproved::<T, improved::Kernel15, 15>::new(
+                direction,
+            )),
+            16 => Arc::new(improved::DFTImproved::<T, improved::Kernel16, 16>::new(
+                direction,
+            )),
+            _ if n.is_power_of_two() && n <= ITERATIVE_P2_MAX => {
+                Arc::new(iterative::DFTIterativeP2::new(n, direction))
+            }
+            _ if n.is_power_of_two() => Arc::new(split_radix::DFTSplitRadix::new(n, direction)),
+            _ => {
+                let (factors, count) = prime_cache::get_factors_all(n);
+
+                if count >= 2 && factors.iter().all(|&f| f == factors[0]) {
+                    Arc::new(prime_power::DFTPrimePower::new(n, direction))
+                } else if count >= 2 {
+                    Arc::new(orig::DFTRadix::new(n, direction))
+                } else if count == 0 {
+                    Arc::new(orig::DFTRader::new(n, direction))
+                } else {
+                    let nb = orig::DFTBluestein::<T>::convolution_size(n);
+                    Arc::new(orig::DFTBluestein::new(n, nb, direction))
+                }
+            }
         }
     }
+}
 
-    // Strategy selection.
-    let plan: Arc<dyn DFTBase> = if cfg!(feature = "use_fftw") {
+/// Returns a cached real-input forward plan for size `n`, built once even
+/// when several threads ask at the same time. Panics if `n` is 0.
+pub fn find_rdft(n: usize) -> Arc<dyn RDFTBase> {
+    assert!(n > 0, "cannot plan a real DFT of size 0");
+    let slot = REAL_PLAN_CACHE.lock().entry(n).or_default().clone();
+    slot.get_or_init(|| build_real_plan(n)).clone()
+}
+
+fn build_real_plan(n: usize) -> Arc<dyn RDFTBase> {
+    if cfg!(feature = "use_fftw") {
         #[cfg(feature = "use_fftw")]
-        {
-            Arc::new(fftw::DFT_FFTW::new(n))
+        match fftw::RDFT_FFTW::new(n) {
+            Ok(plan) => Arc::new(plan),
+            Err(err) => {
+                log::warn!("{}; using the native real transform", err);
+                Arc::new(real::DFTReal::new(n))
+            }
         }
         #[cfg(not(feature = "use_fftw"))]
         {
             unreachable!()
         }
     } else {
-        match n {
-            1 => Arc::new(improved::DFTImproved::<improved::Kernel1, 1>::new()),
-            2 => Arc::new(improved::DFTImproved::<improved::Kernel2, 2>::new()),
-            3 => Arc::new(improved::DFTImproved::<improved::Kernel3, 3>::new()),
-            4 => Arc::new(improved::DFTImproved::<improved::Kernel4, 4>::new()),
-            5 => Arc::new(improved::DFTImproved::<improved::Kernel5, 5>::new()),
-            6 => Arc::new(improved::DFTImproved::<improved::Kernel6, 6>::new()),
-            8 => Arc::new(improved::DFTImproved::<improved::Kernel8, 8>::new()),
-            _ => {
-                let (_factors, count) = prime_cache::get_factors_all(n);
+        Arc::new(real::DFTReal::new(n))
+    }
+}
 
-                if count >= 2 {
-                    Arc::new(orig::DFTRadix::new(n))
-                } else {
-                    let nb = (2 * n - 1).next_power_of_two();
-                    if count == 0 {
-                        Arc::new(orig::DFTRader::new(n))
-                    } else {
-                        Arc::new(orig::DFTBluestein::new(n, nb))
+/// Loads saved planner state from `path` so FFTW plans skip re-measuring.
+/// The native strategies keep no such state, so without `use_fftw` this
+/// does nothing and returns `Ok`.
+pub fn import_wisdom(path: &Path) -> io::Result<()> {
+    #[cfg(feature = "use_fftw")]
+    return fftw::import_wisdom(path);
+    #[cfg(not(feature = "use_fftw"))]
+    {
+        let _ = path;
+        Ok(())
+    }
+}
+
+/// Saves FFTW planner state for the plans created so far to `path`; a no-op
+/// returning `Ok` without `use_fftw`.
+pub fn export_wisdom(path: &Path) -> io::Result<()> {
+    #[cfg(feature = "use_fftw")]
+    return fftw::export_wisdom(path);
+    #[cfg(not(feature = "use_fftw"))]
+    {
+        let _ = path;
+        Ok(())
+    }
+}
+
+/// Returns an `f32` plan meant to be shared by up to `pool_size` threads
+/// transforming at the same time.
+///
+/// With `use_fftw` this builds an uncached FFTW plan holding `pool_size`
+/// plan/buffer pairs, so that many callers never block each other. The
+/// native plans are already lock-free, so otherwise this is `find_dft`, as
+/// it is when FFTW fails to plan.
+pub fn find_dft_pooled(n: usize, direction: Direction, pool_size: usize) -> Arc<dyn DFTBase> {
+    #[cfg(feature = "use_fftw")]
+    return match fftw::DFT_FFTW::with_pool(n, direction, pool_size) {
+        Ok(plan) => Arc::new(plan),
+        Err(err) => {
+            log::warn!("{}; using the native planner", err);
+            find_dft(n, direction)
+        }
+    };
+    #[cfg(not(feature = "use_fftw"))]
+    {
+        let _ = pool_size;
+        find_dft(n, direction)
+    }
+}
+
+/// Returns an `f32` 2D DFT plan for a row-major `rows × cols` grid.
+pub fn find_dft_2d(rows: usize, cols: usize, direction: Direction) -> DFT2D {
+    DFT2D::new(rows, cols, direction)
+}
+
+/// Returns a DFT plan for `direction` interpreted under `convention`.
+/// Plans are shared with `find_dft`, since a positive-exponent forward
+/// transform is exactly this crate's inverse.
+pub fn find_dft_with(
+    n: usize,
+    direction: Direction,
+    convention: SignConvention,
+) -> Arc<dyn DFTBase> {
+    find_dft(n, convention.resolve(direction))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_complex::Complex64;
+    use std::sync::Barrier;
+    use std::thread;
+
+    #[test]
+    fn concurrent_requests_share_one_plan_per_size() {
+        //
+        // Sizes no other test plans, covering every strategy and the
+        // nested sub-plans each one requests while being built.
+        //
+        let sizes = [4096 * 3, 2 * 3 * 5 * 7 * 11, 7919, 7907 * 2, 1019];
+        let threads = 16;
+        let barrier = Arc::new(Barrier::new(threads));
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    let mut plans = Vec::new();
+                    for i in 0..sizes.len() {
+                        let n = sizes[(i + t) % sizes.len()];
+                        plans.push((n, find_dft(n, Direction::Forward)));
                     }
+                    plans.push((sizes[0], find_dft(sizes[0], Direction::Forward)));
+                    let real = find_rdft(2 * sizes[t % sizes.len()]);
+                    (plans, real)
+                })
+            })
+            .collect();
+        let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        for (plans, real) in &results {
+            for (n, plan) in plans {
+                assert_eq!(plan.size(), *n);
+                assert!(
+                    Arc::ptr_eq(plan, &find_dft(*n, Direction::Forward)),
+                    "n={} was built more than once",
+                    n
+                );
+            }
+            assert!(Arc::ptr_eq(real, &find_rdft(real.size())));
+        }
+    }
+
+    #[test]
+    fn forward_and_inverse_plans_do_not_collide() {
+        for n in [8, 7, 12] {
+            let fwd = find_dft(n, Direction::Forward);
+            let inv = find_dft(n, Direction::Inverse);
+            assert!(
+                !Arc::ptr_eq(&fwd, &inv),
+                "n={}: plans share a cache slot",
+                n
+            );
+            assert!(Arc::ptr_eq(&fwd, &find_dft(n, Direction::Forward)));
+            assert!(Arc::ptr_eq(&inv, &find_dft(n, Direction::Inverse)));
+
+            //
+            // An impulse at index 1 yields exp(∓2πik/n) depending on direction.
+            //
+            let mut impulse = vec![Complex32::default(); n];
+            impulse[1] = Complex32::new(1.0, 0.0);
+            let mut out_fwd = vec![Complex32::default(); n];
+            let mut out_inv = vec![Complex32::default(); n];
+            fwd.xform(&impulse, &mut out_fwd);
+            inv.xform(&impulse, &mut out_inv);
+
+            for k in 0..n {
+                let angle = 2.0 * std::f32::consts::PI * k as f32 / n as f32;
+                let expected = Complex32::from_polar(1.0, -angle);
+                assert!((out_fwd[k] - expected).norm() < 1e-4, "n={} k={}", n, k);
+                assert!(
+                    (out_inv[k] - expected.conj()).norm() < 1e-4,
+                    "n={} k={}",
+                    n,
+                    k
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_strategy_honours_direction() {
+        let plans: Vec<(Arc<dyn DFTBase>, Direction)> = [Direction::Forward, Direction::Inverse]
+            .into_iter()
+            .flat_map(|d| {
+                [
+                    (Arc::new(improved::DFTImproved::<f32, improved::Kernel5, 5>::new(d))
+                        as Arc<dyn DFTBase>),
+                    Arc::new(orig::DFTRadix::new(10, d)),
+                    Arc::new(orig::DFTRader::new(11, d)),
+                    Arc::new(orig::DFTBluestein::new(7, 16, d)),
+                    Arc::new(split_radix::DFTSplitRadix::new(64, d)),
+                ]
+                .into_iter()
+                .map(move |p| (p, d))
+            })
+            .collect();
+
+        for (plan, direction) in plans {
+            let n = plan.size();
+            let input: Vec<Complex32> = (0..n)
+                .map(|i| Complex32::new((i as f32 * 0.9).sin(), (i as f32 * 0.4).cos()))
+                .collect();
+            let mut output = vec![Complex32::default(); n];
+            plan.xform(&input, &mut output);
+
+            for (k, out) in output.iter().enumerate() {
+                let expected: Complex32 = input
+                    .iter()
+                    .enumerate()
+                    .map(|(j, &v)| {
+                        let angle =
+                            direction.sign() * 2.0 * std::f32::consts::PI * ((j * k) % n) as f32
+                                / n as f32;
+                        v * Complex32::from_polar(1.0, angle)
+                    })
+                    .sum();
+                assert!(
+                    (out - expected).norm() < 1e-4 * n as f32,
+                    "{}: k={}",
+                    plan.name(),
+                    k
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bluestein_is_an_unnormalized_dft() {
+        //
+        // Size 6 has a fixed kernel, so Bluestein can be checked against it
+        // directly and through the planner, with the smallest convolution
+        // size and with extra padding, which must not change the scale.
+        //
+        for direction in [Direction::Forward, Direction::Inverse] {
+            let kernel = improved::DFTImproved::<f32, improved::Kernel6, 6>::new(direction);
+            let kernel64 = improved::DFTImproved::<f64, improved::Kernel6, 6>::new(direction);
+            let input: Vec<Complex32> = (0..6)
+                .map(|i| Complex32::new((i as f32 * 0.7).cos(), (i as f32 * 1.9).sin()))
+                .collect();
+            let input64: Vec<Complex64> = input
+                .iter()
+                .map(|c| Complex64::new(c.re as f64, c.im as f64))
+                .collect();
+
+            let mut expected = vec![Complex32::default(); 6];
+            kernel.xform(&input, &mut expected);
+            let mut planned = vec![Complex32::default(); 6];
+            find_dft(6, direction).xform(&input, &mut planned);
+            let mut expected64 = vec![Complex64::default(); 6];
+            kernel64.xform(&input64, &mut expected64);
+
+            for nb in [11, 16, 64] {
+                let mut output = vec![Complex32::default(); 6];
+                orig::DFTBluestein::new(6, nb, direction).xform(&input, &mut output);
+                let mut output64 = vec![Complex64::default(); 6];
+                orig::DFTBluestein::<f64>::new(6, nb, direction).xform(&input64, &mut output64);
+                for k in 0..6 {
+                    assert!((output[k] - expected[k]).norm() < 1e-5, "nb={} k={}", nb, k);
+                    assert!((output[k] - planned[k]).norm() < 1e-5, "nb={} k={}", nb, k);
+                    assert!(
+                        (output64[k] - expected64[k]).norm() < 1e-12,
+                        "nb={} k={}",
+                        nb,
+                        k
+                    );
                 }
             }
         }
-    };
 
-    // Cache the plan.
-    let mut cache = PLAN_CACHE.lock();
-    cache.insert(n, plan.clone());
-    plan
+        //
+        // An impulse transforms to all ones and a constant to n in bin 0,
+        // so any stray 1/n or 1/nb shows up as a level error.
+        //
+        let plan = orig::DFTBluestein::<f64>::new(13, 32, Direction::Forward);
+        let mut impulse = vec![Complex64::default(); 13];
+        impulse[0] = Complex64::new(1.0, 0.0);
+        let mut output = vec![Complex64::default(); 13];
+        plan.xform(&impulse, &mut output);
+        assert!(output.iter().all(|c| (c - 1.0).norm() < 1e-12));
+        plan.xform(&[Complex64::new(1.0, 0.0); 13], &mut output);
+        assert!((output[0] - 13.0).norm() < 1e-12);
+        assert!(output[1..].iter().all(|c| c.norm() < 1e-12));
+    }
+
+    #[test]
+    #[should_panic(expected = "convolution size")]
+    fn bluestein_rejects_a_short_convolution() {
+        orig::DFTBluestein::<f32>::new(7, 12, Direction::Forward);
+    }
+
+    #[test]
+    #[should_panic(expected = "size 0")]
+    fn empty_dft_is_rejected() {
+        find_dft(0, Direction::Forward);
+    }
+
+    #[test]
+    #[should_panic(expected = "size 0")]
+    fn empty_real_dft_is_rejected() {
+        find_rdft(0);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "istep2 = 8 with count = 3 needs 20 input elements, but it has 16")]
+    fn short_batch_input_names_the_stride() {
+        let plan = find_dft(4, Direction::Forward);
+        let input = [Complex32::default(); 16];
+        let mut output = [Complex32::default(); 64];
+        plan.xform_many(&input, &mut output, 1, 8, 1, 4, 3);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(
+        expected = "ostep = 4 needs 45 output elements for one size-12 transform, but it has 12"
+    )]
+    fn short_strided_output_names_the_stride() {
+        let plan = find_dft(12, Direction::Forward);
+        let input = [Complex32::default(); 12];
+        let mut output = [Complex32::default(); 12];
+        plan.xform_many(&input, &mut output, 1, 0, 4, 0, 1);
+    }
+
+    #[test]
+    fn size_one_is_the_identity() {
+        let x = Complex32::new(0.75, -0.25);
+        for direction in [Direction::Forward, Direction::Inverse] {
+            let plan = find_dft(1, direction);
+            assert_eq!(plan.size(), 1);
+            let mut output = [Complex32::default()];
+            plan.xform(&[x], &mut output);
+            assert_eq!(output, [x]);
+
+            let plan = find_dft_f64(1, direction);
+            let mut output = [Complex64::default()];
+            plan.xform(&[Complex64::new(0.75, -0.25)], &mut output);
+            assert_eq!(output, [Complex64::new(0.75, -0.25)]);
+        }
+
+        let real = find_rdft(1);
+        let mut output = [Complex32::default()];
+        real.xform_real(&[0.5], &mut output);
+        assert_eq!(output, [Complex32::new(0.5, 0.0)]);
+    }
+
+    #[test]
+    fn inverse_round_trip_recovers_input() {
+        for n in [2, 5, 7, 2048] {
+            let plan = find_dft(n, Direction::Forward);
+            let input: Vec<Complex32> = (0..n)
+                .map(|i| Complex32::new((i as f32 * 0.37).sin(), (i as f32 * 1.3).cos()))
+                .collect();
+            let mut spectrum = vec![Complex32::default(); n];
+            let mut output = vec![Complex32::default(); n];
+            plan.xform(&input, &mut spectrum);
+            plan.xform_inverse(&spectrum, &mut output);
+
+            for (k, (got, want)) in output.iter().zip(&input).enumerate() {
+                assert!(
+                    (got - want).norm() < 1e-4,
+                    "{}: k={} got {} want {}",
+                    plan.name(),
+                    k,
+                    got,
+                    want
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn f64_plans_are_more_accurate_than_f32() {
+        let n = 1024;
+        let bin = 37;
+
+        //
+        // A pure complex tone at `bin` transforms to n at that bin, 0 elsewhere.
+        //
+        let tone: Vec<Complex<f64>> = (0..n)
+            .map(|i| {
+                let angle = 2.0 * std::f64::consts::PI * ((bin * i) % n) as f64 / n as f64;
+                Complex::from_polar(1.0, angle)
+            })
+            .collect();
+        let expected = |k: usize| if k == bin { n as f64 } else { 0.0 };
+
+        let input32: Vec<Complex32> = tone
+            .iter()
+            .map(|c| Complex32::new(c.re as f32, c.im as f32))
+            .collect();
+        let mut out32 = vec![Complex32::default(); n];
+        find_dft(n, Direction::Forward).xform(&input32, &mut out32);
+
+        let mut out64 = vec![Complex::<f64>::default(); n];
+        find_dft_f64(n, Direction::Forward).xform(&tone, &mut out64);
+
+        let err32 = (0..n)
+            .map(|k| (Complex::new(out32[k].re as f64, out32[k].im as f64) - expected(k)).norm())
+            .fold(0.0, f64::max);
+        let err64 = (0..n)
+            .map(|k| (out64[k] - expected(k)).norm())
+            .fold(0.0, f64::max);
+
+        assert!(err32 < 1e-2, "f32 error {}", err32);
+        assert!(err64 < err32 * 1e-3, "f64 error {} vs f32 {}", err64, err32);
+    }
+
+    #[test]
+    fn positive_convention_maps_onto_inverse_plans() {
+        let fwd = find_dft_with(8, Direction::Forward, SignConvention::Positive);
+        let inv = find_dft_with(8, Direction::Inverse, SignConvention::Positive);
+        assert!(Arc::ptr_eq(&fwd, &find_dft(8, Direction::Inverse)));
+        assert!(Arc::ptr_eq(&inv, &find_dft(8, Direction::Forward)));
+    }
+
+    #[test]
+    fn native_plans_can_be_shared_across_threads() {
+        let plans: Vec<Arc<dyn DFTBase>> = vec![
+            Arc::new(improved::DFTImproved::<f32, improved::Kernel16, 16>::new(
+                Direction::Forward,
+            )),
+            Arc::new(split_radix::DFTSplitRadix::new(64, Direction::Forward)),
+            Arc::new(orig::DFTRadix::new(60, Direction::Forward)),
+            Arc::new(orig::DFTRader::new(11, Direction::Forward)),
+            Arc::new(orig::DFTBluestein::new(13, 32, Direction::Forward)),
+        ];
+
+        for plan in plans {
+            let n = plan.size();
+            let inputs: Vec<Vec<Complex32>> = (0..8)
+                .map(|t| {
+                    (0..n)
+                        .map(|i| Complex32::new((i as f32 * 0.3 + t as f32).sin(), 0.5))
+                        .collect()
+                })
+                .collect();
+            let expected: Vec<Vec<Complex32>> = inputs
+                .iter()
+                .map(|input| {
+                    let mut out = vec![Complex32::default(); n];
+                    plan.xform(input, &mut out);
+                    out
+                })
+                .collect();
+
+            //
+            // Concurrent callers contend for the same scratch buffers; the
+            // results must not depend on who wins.
+            //
+            std::thread::scope(|s| {
+                for (input, expected) in inputs.iter().zip(&expected) {
+                    let plan = &plan;
+                    s.spawn(move || {
+                        let mut out = vec![Complex32::default(); n];
+                        for _ in 0..200 {
+                            plan.xform(input, &mut out);
+                            assert_eq!(&out, expected, "{}", plan.name());
+                        }
+                    });
+                }
+            });
+        }
+    }
 }
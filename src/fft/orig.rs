@@ -1,75 +1,191 @@
-use super::{find_dft, prime_cache, DFTBase};
-use num_complex::Complex32;
-use std::f32::consts::PI;
+use super::chirpz::ChirpConvolution;
+#[cfg(feature = "parallel")]
+use super::parallel;
+use super::scratch::Scratch;
+use super::{debug_check_strides, find_dft_generic, prime_cache, DFTBase, Direction, FftFloat};
+use num_complex::{Complex, Complex64};
+use std::f64::consts::PI;
 use std::sync::Arc;
 
-fn w(k: usize, n: usize) -> Complex32 {
-    let angle = -2.0 * PI * (k as f32) / (n as f32);
-    Complex32::from_polar(1.0, angle)
+pub(super) fn w<T: FftFloat>(k: usize, n: usize, direction: Direction) -> Complex<T> {
+    //
+    // Reduce the index and evaluate in f64 before narrowing; Bluestein's
+    // chirp passes k² here, whose raw angle is far too large for f32.
+    //
+    let angle = direction.sign() as f64 * 2.0 * PI * ((k % n) as f64) / (n as f64);
+    Complex::new(T::cast(angle.cos()), T::cast(angle.sin()))
+}
+
+/// Maps applied to what the Rader and Bluestein passes read and write. They
+/// are the identity unless `inverse`, where conjugating both ends and
+/// scaling by 1/n turns the plan into its own normalized inverse.
+#[allow(clippy::type_complexity)]
+fn conjugation<T: FftFloat>(
+    inverse: bool,
+    n: usize,
+) -> (
+    impl Fn(Complex<T>) -> Complex<T>,
+    impl Fn(Complex<T>) -> Complex<T>,
+) {
+    let scale = T::one() / T::cast(n as f64);
+    (
+        move |x: Complex<T>| if inverse { x.conj() } else { x },
+        move |x: Complex<T>| if inverse { x.conj() * scale } else { x },
+    )
 }
 
 //
 // Radix-P (Cooley–Tukey) implementation.
 //
-pub struct DFTRadix {
+pub struct DFTRadix<T: FftFloat> {
     n: usize,
     p: usize,
     q: usize,
-    wtable: Vec<Complex32>,
-    dft_p: Option<Arc<dyn DFTBase>>,
-    dft_q: Option<Arc<dyn DFTBase>>,
+    direction: Direction,
+    wtable: Vec<Complex<T>>,
+    dft_p: Option<Arc<dyn DFTBase<T>>>,
+    dft_q: Option<Arc<dyn DFTBase<T>>>,
+
+    //
+    // Column-major copy of one block and its transform, 2n elements.
+    //
+    scratch: Scratch<Complex<T>>,
 }
 
-impl DFTRadix {
-    pub fn new(n: usize) -> Self {
+impl<T: FftFloat> DFTRadix<T> {
+    pub fn new(n: usize, direction: Direction) -> Self {
         //
-        // Select radix factor p and compute q = n / p.
+        // Select radix factor p and compute q = n / p. Powers of two are
+        // peeled off as 8 or 4 where possible so the p-point transforms run
+        // on the SIMD kernels and the chain needs fewer twiddle passes than
+        // with radix 2; otherwise the smallest prime is used.
         //
         let (factors, count) = prime_cache::get_factors_all(n);
-        let p = if count > 0 { factors[0] } else { n };
+        let twos = factors.iter().filter(|&&f| f == 2).count();
+        let p = if twos >= 3 && n > 8 {
+            8
+        } else if twos >= 2 && n > 4 {
+            4
+        } else if count > 0 {
+            factors[0]
+        } else {
+            n
+        };
         let q = n / p;
 
-        let mut wtable = Vec::with_capacity(n);
-
-        //
-        // Precompute twiddle values for each index.
-        //
-        for a in 0..n {
-            wtable.push(w((a % q) * (a / q), n));
-        }
-
         //
         // Initialize sub-transforms for p and q sizes.
         //
-        let dft_p = if p > 1 { Some(find_dft(p)) } else { None };
-        let dft_q = if q > 1 { Some(find_dft(q)) } else { None };
+        let dft_p = if p > 1 {
+            Some(find_dft_generic::<T>(p, direction))
+        } else {
+            None
+        };
+        let dft_q = if q > 1 {
+            Some(find_dft_generic::<T>(q, direction))
+        } else {
+            None
+        };
 
         Self {
             n,
             p,
             q,
-            wtable,
+            direction,
+            wtable: Self::twiddles(n, q, direction),
             dft_p,
             dft_q,
+            scratch: Scratch::with_capacity(2 * n),
         }
     }
+
+    /// Builds one `p × q` step from explicit sub-plans instead of asking the
+    /// planner, e.g. to time a fixed decomposition.
+    pub fn with_sub_plans(
+        n: usize,
+        direction: Direction,
+        dft_p: Arc<dyn DFTBase<T>>,
+        dft_q: Arc<dyn DFTBase<T>>,
+    ) -> Self {
+        let (p, q) = (dft_p.size(), dft_q.size());
+        assert_eq!(p * q, n, "sub-plans of {} and {} do not make {}", p, q, n);
+
+        Self {
+            n,
+            p,
+            q,
+            direction,
+            wtable: Self::twiddles(n, q, direction),
+            dft_p: Some(dft_p),
+            dft_q: Some(dft_q),
+            scratch: Scratch::with_capacity(2 * n),
+        }
+    }
+
+    /// Twiddle values for each index of the intermediate `p × q` block.
+    fn twiddles(n: usize, q: usize, direction: Direction) -> Vec<Complex<T>> {
+        (0..n).map(|a| w((a % q) * (a / q), n, direction)).collect()
+    }
 }
 
-impl DFTBase for DFTRadix {
+impl<T: FftFloat> DFTBase<T> for DFTRadix<T> {
     fn name(&self) -> String {
-        format!("RadixP<{}>({})", self.p, self.n)
+        format!("{}RadixP<{}>({})", self.direction.tag(), self.p, self.n)
     }
     fn size(&self) -> usize {
         self.n
     }
+    fn direction(&self) -> Direction {
+        self.direction
+    }
     fn is_inplace(&self) -> bool {
         false
     }
 
     fn xform_many(
         &self,
-        input: &[Complex32],
-        output: &mut [Complex32],
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+    ) {
+        debug_check_strides(
+            self.size(),
+            input.len(),
+            output.len(),
+            istep,
+            istep2,
+            ostep,
+            ostep2,
+            count,
+        );
+        #[cfg(feature = "parallel")]
+        if count >= parallel::MIN_PARALLEL_COUNT {
+            return parallel::xform_batches(
+                self.n,
+                input,
+                output,
+                istep,
+                istep2,
+                ostep,
+                ostep2,
+                count,
+                |i, o, is, is2, os, os2, c| self.xform_many_serial(i, o, is, is2, os, os2, c),
+            );
+        }
+        self.xform_many_serial(input, output, istep, istep2, ostep, ostep2, count);
+    }
+}
+
+impl<T: FftFloat> DFTRadix<T> {
+    #[allow(clippy::too_many_arguments)]
+    fn xform_many_serial(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
         istep: usize,
         istep2: usize,
         ostep: usize,
@@ -113,31 +229,31 @@ impl DFTBase for DFTRadix {
         // Compute q transforms, each of length p.
         //
         if let Some(dp) = &self.dft_p {
-            for i in 0..count {
-                let out_base = i * ostep2;
-
-                let mut temp_col = vec![Complex32::default(); self.n];
-
-                //
-                // Copy block into column-major buffer.
-                //
-                for k in 0..self.n {
-                    temp_col[k] = output[out_base + k * ostep];
-                }
-
-                //
-                // Execute q transforms of length p.
-                //
-                let temp_in = temp_col.clone();
-                dp.xform_many(&temp_in, &mut temp_col, self.q, 1, self.q, 1, self.q);
-
-                //
-                // Store transformed block back to output.
-                //
-                for k in 0..self.n {
-                    output[out_base + k * ostep] = temp_col[k];
+            self.scratch.with(2 * self.n, |scratch| {
+                let (temp_in, temp_col) = scratch.split_at_mut(self.n);
+                for i in 0..count {
+                    let out_base = i * ostep2;
+
+                    //
+                    // Copy block into column-major buffer.
+                    //
+                    for k in 0..self.n {
+                        temp_in[k] = output[out_base + k * ostep];
+                    }
+
+                    //
+                    // Execute q transforms of length p.
+                    //
+                    dp.xform_many(temp_in, temp_col, self.q, 1, self.q, 1, self.q);
+
+                    //
+                    // Store transformed block back to output.
+                    //
+                    for k in 0..self.n {
+                        output[out_base + k * ostep] = temp_col[k];
+                    }
                 }
-            }
+            });
         }
     }
 }
@@ -145,123 +261,176 @@ impl DFTBase for DFTRadix {
 //
 // Rader’s algorithm for prime-length DFT.
 //
-pub struct DFTRader {
+pub struct DFTRader<T: FftFloat> {
     n: usize,
+    direction: Direction,
     g: usize,
     g_inv: usize,
-    omega: Vec<Complex32>,
-    dft_n1: Arc<dyn DFTBase>,
+    omega: Vec<Complex<T>>,
+    dft_n1: Arc<dyn DFTBase<T>>,
+
+    //
+    // DC terms and permuted blocks, grown to the largest batch seen.
+    //
+    scratch: Scratch<Complex<T>>,
 }
 
-impl DFTRader {
-    pub fn new(n: usize) -> Self {
+impl<T: FftFloat> DFTRader<T> {
+    pub fn new(n: usize, direction: Direction) -> Self {
         //
-        // Find generator g for multiplicative group mod n.
+        // Generator g of the multiplicative group mod n, and its inverse.
         //
-        let (factors, count) = prime_cache::get_factors_all(n - 1);
-        let mut g = 2;
-
-        loop {
-            let mut is_gen = true;
-            for i in 0..count {
-                if powermod(g, (n - 1) / factors[i], n) == 1 {
-                    is_gen = false;
-                    break;
-                }
-            }
-            if is_gen {
-                break;
-            }
-            g += 1;
-        }
-
-        let g_inv = powermod(g, n - 2, n);
+        let g = prime_cache::primitive_root(n);
+        let g_inv = prime_cache::powermod(g, n - 2, n);
 
         //
-        // Build reordered twiddle sequence.
+        // Build reordered twiddle sequence. Only this kernel depends on the
+        // direction; the internal convolution always runs forward.
         //
-        let mut omega = vec![Complex32::default(); n - 1];
+        let mut omega = vec![Complex::default(); n - 1];
         let mut gp = 1;
-        for i in 0..n - 1 {
-            omega[i] = w(gp, n);
+        for o in omega.iter_mut() {
+            *o = w(gp, n, direction);
             gp = (gp * g_inv) % n;
         }
 
         //
         // Compute transformed kernel vector.
         //
-        let dft_n1 = find_dft(n - 1);
+        let dft_n1 = find_dft_generic::<T>(n - 1, Direction::Forward);
         dft_n1.xform_inplace(&mut omega);
 
         //
         // Normalize kernel coefficients.
         //
         for x in &mut omega {
-            *x /= (n - 1) as f32;
+            *x /= T::cast((n - 1) as f64);
         }
 
         Self {
             n,
+            direction,
             g,
             g_inv,
             omega,
             dft_n1,
+            scratch: Scratch::with_capacity(1 + 2 * (n - 1)),
         }
     }
 }
 
-fn powermod(mut base: usize, mut exp: usize, modulus: usize) -> usize {
-    let mut result = 1;
-    base %= modulus;
-    while exp > 0 {
-        if exp % 2 == 1 {
-            result = (result * base) % modulus;
-        }
-        base = (base * base) % modulus;
-        exp /= 2;
-    }
-    result
-}
-
-impl DFTBase for DFTRader {
+impl<T: FftFloat> DFTBase<T> for DFTRader<T> {
     fn name(&self) -> String {
-        format!("Rader({})", self.n)
+        format!("{}Rader({})", self.direction.tag(), self.n)
     }
     fn size(&self) -> usize {
         self.n
     }
+    fn direction(&self) -> Direction {
+        self.direction
+    }
     fn is_inplace(&self) -> bool {
         true
     }
 
     fn xform_many(
         &self,
-        input: &[Complex32],
-        output: &mut [Complex32],
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+    ) {
+        debug_check_strides(
+            self.size(),
+            input.len(),
+            output.len(),
+            istep,
+            istep2,
+            ostep,
+            ostep2,
+            count,
+        );
+        let n_minus_1 = self.n - 1;
+        self.scratch.with(count + n_minus_1 * count * 2, |buf| {
+            self.xform_with_buffer(
+                input, output, istep, istep2, ostep, ostep2, count, buf, false,
+            )
+        });
+    }
+
+    /// Runs this plan on conjugated input and conjugates and scales the
+    /// result, so the inverse needs no second plan or extra buffer.
+    fn xform_many_inverse(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
         istep: usize,
         istep2: usize,
         ostep: usize,
         ostep2: usize,
         count: usize,
     ) {
+        debug_check_strides(
+            self.size(),
+            input.len(),
+            output.len(),
+            istep,
+            istep2,
+            ostep,
+            ostep2,
+            count,
+        );
         let n_minus_1 = self.n - 1;
+        self.scratch.with(count + n_minus_1 * count * 2, |buf| {
+            self.xform_with_buffer(
+                input, output, istep, istep2, ostep, ostep2, count, buf, true,
+            )
+        });
+    }
+}
+
+impl<T: FftFloat> DFTRader<T> {
+    /// Runs the transform in `buf`, which holds `count` DC terms followed by
+    /// two sets of `count` permuted blocks; every element is written before
+    /// it is read. With `inverse`, computes the normalized opposite-direction
+    /// transform as conj(DFT(conj(x))) / n instead.
+    #[allow(clippy::too_many_arguments)]
+    fn xform_with_buffer(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+        buf: &mut [Complex<T>],
+        inverse: bool,
+    ) {
+        let n_minus_1 = self.n - 1;
+        let (load, store) = conjugation(inverse, self.n);
 
         //
-        // Allocate working buffer for all transforms.
+        // Split the buffer into its three regions up front so each block is
+        // addressed relative to its own region: `dc[i]`, and block `i` at
+        // `i * n_minus_1` in `permuted` and `spectra`.
         //
-        let mut buf = vec![Complex32::default(); count + n_minus_1 * count * 2];
+        let (dc, blocks) = buf[..count + 2 * n_minus_1 * count].split_at_mut(count);
+        let (permuted, spectra) = blocks.split_at_mut(n_minus_1 * count);
 
         //
         // Apply Rader permutation and extract DC terms.
         //
         for i in 0..count {
-            buf[i] = input[i * istep2];
+            let x = &input[i * istep2..];
+            dc[i] = load(x[0]);
 
             let mut gp = 1;
-            for k in 0..n_minus_1 {
-                let in_idx = i * istep2 + gp * istep;
-                let buf_idx = count + k + i * n_minus_1;
-                buf[buf_idx] = input[in_idx];
+            for p in &mut permuted[i * n_minus_1..(i + 1) * n_minus_1] {
+                *p = load(x[gp * istep]);
                 gp = (gp * self.g) % self.n;
             }
         }
@@ -269,60 +438,37 @@ impl DFTBase for DFTRader {
         //
         // Forward DFT of permuted blocks.
         //
-        let split_idx = count + n_minus_1 * count;
-        let (buf_lower, buf_upper) = buf.split_at_mut(split_idx);
-
-        self.dft_n1.xform_many(
-            &buf_lower[count..],
-            buf_upper,
-            1,
-            n_minus_1,
-            1,
-            n_minus_1,
-            count,
-        );
+        self.dft_n1
+            .xform_many(permuted, spectra, 1, n_minus_1, 1, n_minus_1, count);
 
         //
-        // Multiply by kernel and compute DC correction.
+        // Multiply by kernel. Bin 0 of each block holds the sum of the
+        // non-DC inputs, which completes X[0]; adding x[0] to the product's
+        // bin 0 adds it to every other output after the inverse DFT.
         //
-        for i in 0..count {
-            let dc_term = buf[i] + buf[count + n_minus_1 * (i + count)];
-            output[i * ostep2] = dc_term;
+        for (i, block) in spectra.chunks_exact_mut(n_minus_1).enumerate() {
+            output[i * ostep2] = store(dc[i] + block[0]);
 
-            for k in 0..n_minus_1 {
-                let idx_src = count + k + n_minus_1 * (i + count);
-                let val = buf[idx_src];
-                let w = self.omega[k];
-                let correction = if k == 0 { buf[i] } else { Complex32::default() };
-
-                buf[idx_src] = (val * w + correction).conj();
+            for (v, &w) in block.iter_mut().zip(&self.omega) {
+                *v = (*v * w).conj();
             }
+            block[0] += dc[i].conj();
         }
 
         //
         // Inverse DFT of modified sequence.
         //
-        let (buf_lower, buf_upper) = buf.split_at_mut(split_idx);
-
-        self.dft_n1.xform_many(
-            buf_upper,
-            &mut buf_lower[count..],
-            1,
-            n_minus_1,
-            1,
-            n_minus_1,
-            count,
-        );
+        self.dft_n1
+            .xform_many(spectra, permuted, 1, n_minus_1, 1, n_minus_1, count);
 
         //
         // Apply inverse permutation and write result.
         //
-        for i in 0..count {
+        for (i, block) in permuted.chunks_exact(n_minus_1).enumerate() {
+            let out = &mut output[i * ostep2..];
             let mut gp = 1;
-            for k in 0..n_minus_1 {
-                let buf_idx = count + k + i * n_minus_1;
-                let out_idx = i * ostep2 + gp * ostep;
-                output[out_idx] = buf[buf_idx].conj();
+            for v in block {
+                out[gp * ostep] = store(v.conj());
                 gp = (gp * self.g_inv) % self.n;
             }
         }
@@ -332,117 +478,154 @@ impl DFTBase for DFTRader {
 //
 // Bluestein’s algorithm for arbitrary sizes.
 //
-pub struct DFTBluestein {
+// The DFT as a chirp-z transform with m = n, a = 1 and w = exp(∓2πi/n), run
+// on the shared `ChirpConvolution` in an `nb`-point circular convolution.
+// Output is the unnormalized DFT in the plan's direction, the same scale as
+// every other strategy.
+//
+pub struct DFTBluestein<T: FftFloat> {
     n: usize,
-    nb: usize,
-    w0: Vec<Complex32>,
-    w1: Vec<Complex32>,
-    dft_nb: Arc<dyn DFTBase>,
+    direction: Direction,
+    conv: ChirpConvolution<T>,
 }
 
-impl DFTBluestein {
-    pub fn new(n: usize, nb: usize) -> Self {
-        //
-        // Generate chirp sequence w0.
-        //
-        let mut w0 = Vec::with_capacity(n);
-        for k in 0..n {
-            w0.push(w(k * k, 2 * n));
+impl<T: FftFloat> DFTBluestein<T> {
+    /// Convolution size the planner uses for an `n`-point plan. The
+    /// smallest 7-smooth size at least `2n - 1` needs a mixed-radix `nb`
+    /// transform, which runs about half as fast per point as a power of two
+    /// in `benches/fft.rs`, so it is taken only when it is close to half
+    /// the next power of two.
+    pub fn convolution_size(n: usize) -> usize {
+        let min = 2 * n - 1;
+        let pow2 = min.next_power_of_two();
+        let smooth = prime_cache::next_7_smooth(min);
+        if 20 * smooth <= 11 * pow2 {
+            smooth
+        } else {
+            pow2
         }
+    }
 
-        //
-        // Build convolution kernel w1 padded to nb.
-        //
-        let mut w1 = vec![Complex32::default(); nb];
-        for k in 0..n {
-            w1[k] = w0[k] / (nb as f32);
-        }
-        for k in 1..n {
-            w1[nb - k] = w1[k];
-        }
+    pub fn new(n: usize, nb: usize, direction: Direction) -> Self {
+        assert!(
+            n > 0 && nb >= 2 * n - 1,
+            "Bluestein({}) needs a convolution size of at least {}, got {}",
+            n,
+            2 * n - 1,
+            nb
+        );
 
         //
-        // Transform kernel in frequency domain.
+        // Chirp w^(k²/2) = exp(∓πik²/n); its sign selects the direction.
         //
-        let dft_nb = find_dft(nb);
-        dft_nb.xform_inplace(&mut w1);
-
-        Self {
-            n,
-            nb,
-            w0,
-            w1,
-            dft_nb,
-        }
+        let chirp = |k: usize| w::<f64>(k * k, 2 * n, direction);
+        let conv = ChirpConvolution::new(n, n, nb, |_| Complex64::new(1.0, 0.0), chirp);
+        Self { n, direction, conv }
     }
 }
 
-impl DFTBase for DFTBluestein {
+impl<T: FftFloat> DFTBase<T> for DFTBluestein<T> {
     fn name(&self) -> String {
-        format!("Bluestein({})", self.n)
+        format!("{}Bluestein({})", self.direction.tag(), self.n)
     }
     fn size(&self) -> usize {
         self.n
     }
+    fn direction(&self) -> Direction {
+        self.direction
+    }
     fn is_inplace(&self) -> bool {
         true
     }
 
     fn xform_many(
         &self,
-        input: &[Complex32],
-        output: &mut [Complex32],
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
         istep: usize,
         istep2: usize,
         ostep: usize,
         ostep2: usize,
         count: usize,
     ) {
-        //
-        // Allocate contiguous buffer for all transforms.
-        //
-        let mut buf = vec![Complex32::default(); self.nb * count * 2];
-        let (slice1, slice2) = buf.split_at_mut(self.nb * count);
-
-        //
-        // Apply initial modulation using chirp sequence.
-        //
-        for i in 0..count {
-            for k in 0..self.n {
-                slice1[k + i * self.nb] = input[k * istep + i * istep2] * self.w0[k];
-            }
-        }
+        self.xform_many_directed(input, output, istep, istep2, ostep, ostep2, count, false);
+    }
 
-        //
-        // Forward DFT of modulated blocks.
-        //
-        self.dft_nb
-            .xform_many(slice1, slice2, 1, self.nb, 1, self.nb, count);
+    /// Runs this plan on conjugated input and conjugates and scales the
+    /// result, as `DFTRader` does.
+    fn xform_many_inverse(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+    ) {
+        self.xform_many_directed(input, output, istep, istep2, ostep, ostep2, count, true);
+    }
+}
 
-        //
-        // Multiply by precomputed kernel in frequency domain.
-        //
-        for i in 0..count {
-            for j in 0..self.nb {
-                let idx = j + i * self.nb;
-                slice2[idx] = slice2[idx].conj() * self.w1[j];
-            }
+impl<T: FftFloat> DFTBluestein<T> {
+    /// Checks strides and splits large batches across threads, for either
+    /// direction; `inverse` is as for `DFTRader::xform_with_buffer`.
+    #[allow(clippy::too_many_arguments)]
+    fn xform_many_directed(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+        inverse: bool,
+    ) {
+        debug_check_strides(
+            self.size(),
+            input.len(),
+            output.len(),
+            istep,
+            istep2,
+            ostep,
+            ostep2,
+            count,
+        );
+        #[cfg(feature = "parallel")]
+        if count >= parallel::MIN_PARALLEL_COUNT {
+            return parallel::xform_batches(
+                self.n,
+                input,
+                output,
+                istep,
+                istep2,
+                ostep,
+                ostep2,
+                count,
+                |i, o, is, is2, os, os2, c| {
+                    self.xform_many_serial(i, o, is, is2, os, os2, c, inverse)
+                },
+            );
         }
+        self.xform_many_serial(input, output, istep, istep2, ostep, ostep2, count, inverse);
+    }
 
-        //
-        // Inverse DFT of product.
-        //
-        self.dft_nb
-            .xform_many(slice2, slice1, 1, self.nb, 1, self.nb, count);
-
-        //
-        // Final modulation and output write-back.
-        //
-        for i in 0..count {
-            for k in 0..self.n {
-                let val = slice1[k + i * self.nb].conj();
-                output[k * ostep + i * ostep2] = val * self.w0[k];
-            }
-        }
+    #[allow(clippy::too_many_arguments)]
+    fn xform_many_serial(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+        inverse: bool,
+    ) {
+        let (load, store) = conjugation(inverse, self.n);
+        self.conv.xform_many(
+            input, output, istep, istep2, ostep, ostep2, count, load, store,
+        );
     }
 }
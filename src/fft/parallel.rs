@@ -0,0 +1,104 @@
+use super::FftFloat;
+use num_complex::Complex;
+use rayon::prelude::*;
+
+/// Batches smaller than this run serially; splitting them costs more than
+/// it saves.
+pub const MIN_PARALLEL_COUNT: usize = 8;
+
+/// Splits `count` strided transforms of length `n` across rayon threads.
+///
+/// Each thread runs `serial` on a contiguous range of transforms, writing to
+/// a private buffer with unit output stride; the results are then scattered
+/// into `output` using the caller's `ostep`/`ostep2`. Every transform sees
+/// exactly the same arithmetic as on the serial path, so results are
+/// bit-for-bit identical.
+#[allow(clippy::too_many_arguments)]
+pub fn xform_batches<T, F>(
+    n: usize,
+    input: &[Complex<T>],
+    output: &mut [Complex<T>],
+    istep: usize,
+    istep2: usize,
+    ostep: usize,
+    ostep2: usize,
+    count: usize,
+    serial: F,
+) where
+    T: FftFloat,
+    F: Fn(&[Complex<T>], &mut [Complex<T>], usize, usize, usize, usize, usize) + Sync,
+{
+    let chunk = count.div_ceil(rayon::current_num_threads()).max(1);
+
+    let blocks: Vec<(usize, Vec<Complex<T>>)> = (0..count)
+        .step_by(chunk)
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|first| {
+            let len = chunk.min(count - first);
+            let mut block = vec![Complex::default(); n * len];
+            serial(
+                &input[first * istep2..],
+                &mut block,
+                istep,
+                istep2,
+                1,
+                n,
+                len,
+            );
+            (first, block)
+        })
+        .collect();
+
+    for (first, block) in blocks {
+        for (i, transform) in block.chunks_exact(n).enumerate() {
+            let base = (first + i) * ostep2;
+            for (k, &v) in transform.iter().enumerate() {
+                output[base + k * ostep] = v;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fft::orig::{DFTBluestein, DFTRadix};
+    use crate::fft::DFTBase;
+    use num_complex::Complex32;
+
+    //
+    // One batched call (parallel) against `count` single calls (serial),
+    // with interleaved, non-unit strides on both sides.
+    //
+    fn check(plan: &dyn DFTBase, count: usize) {
+        let n = plan.size();
+        let (istep, istep2) = (count + 1, 1);
+        let (ostep, ostep2) = (2, 2 * n + 1);
+        let input: Vec<Complex32> = (0..n * istep + count)
+            .map(|i| Complex32::new((i as f32 * 0.7).sin(), (i as f32 * 0.3).cos()))
+            .collect();
+
+        let mut batched = vec![Complex32::default(); count * ostep2 + n * ostep];
+        plan.xform_many(&input, &mut batched, istep, istep2, ostep, ostep2, count);
+
+        let mut serial = vec![Complex32::default(); batched.len()];
+        for i in 0..count {
+            plan.xform_many(
+                &input[i * istep2..],
+                &mut serial[i * ostep2..],
+                istep,
+                0,
+                ostep,
+                0,
+                1,
+            );
+        }
+        assert!(batched == serial, "{} differs from serial", plan.name());
+    }
+
+    #[test]
+    fn parallel_batches_match_serial_bit_for_bit() {
+        check(&DFTRadix::new(360, crate::Direction::Forward), 37);
+        check(&DFTBluestein::new(101, 256, crate::Direction::Inverse), 23);
+    }
+}
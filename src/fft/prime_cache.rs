@@ -13,6 +13,7 @@ struct PrimeLore {
     smallest_factors: HashMap<usize, usize>,
     primes: Vec<usize>, // Ordered list of known primes.
     last_prime: usize,
+    generators: HashMap<usize, usize>, // Smallest primitive root per prime.
 }
 
 impl PrimeLore {
@@ -21,6 +22,7 @@ impl PrimeLore {
             smallest_factors: HashMap::from([(0, 0), (1, 1), (2, 2)]),
             primes: vec![2],
             last_prime: 2,
+            generators: HashMap::new(),
         };
 
         //
@@ -32,6 +34,7 @@ impl PrimeLore {
         s
     }
 
+    #[allow(clippy::manual_is_multiple_of, clippy::collapsible_else_if)]
     fn find(&mut self, n: usize) -> usize {
         //
         // Return cached result if available.
@@ -89,6 +92,83 @@ impl PrimeLore {
         self.smallest_factors.insert(n, solution);
         solution
     }
+
+    /// Smallest primitive root of prime `p`, searched once and cached.
+    fn generator(&mut self, p: usize) -> usize {
+        if let Some(&g) = self.generators.get(&p) {
+            return g;
+        }
+
+        //
+        // Distinct prime factors of the group order p - 1.
+        //
+        let mut factors = Vec::new();
+        let mut rest = p - 1;
+        while rest > 1 {
+            let f = self.find(rest);
+            if factors.last() != Some(&f) {
+                factors.push(f);
+            }
+            rest /= f;
+        }
+
+        //
+        // g generates the group iff g^((p-1)/f) != 1 for every such f.
+        //
+        let g = (2..p)
+            .find(|&g| factors.iter().all(|&f| powermod(g, (p - 1) / f, p) != 1))
+            .unwrap_or(1);
+        self.generators.insert(p, g);
+        g
+    }
+}
+
+/// `base^exp mod modulus` by repeated squaring.
+pub fn powermod(mut base: usize, mut exp: usize, modulus: usize) -> usize {
+    let mut result = 1;
+    base %= modulus;
+    while exp > 0 {
+        if exp % 2 == 1 {
+            result = (result * base) % modulus;
+        }
+        base = (base * base) % modulus;
+        exp /= 2;
+    }
+    result
+}
+
+/// Smallest primitive root modulo prime `p`, i.e. a generator of the
+/// multiplicative group used by Rader's algorithm. Results are cached, so
+/// building several plans for one prime searches only once.
+pub fn primitive_root(p: usize) -> usize {
+    PRIME_LORE.lock().unwrap().generator(p)
+}
+
+/// Smallest 7-smooth number (only prime factors 2, 3, 5 and 7) that is at
+/// least `min`. Never larger than `min.next_power_of_two()`, which is one.
+pub fn next_7_smooth(min: usize) -> usize {
+    let mut best = min.next_power_of_two();
+    let mut p7 = 1;
+    while p7 < best {
+        let mut p5 = p7;
+        while p5 < best {
+            let mut p3 = p5;
+            while p3 < best {
+                //
+                // Doubling up from each odd 7-smooth base covers the rest.
+                //
+                let mut candidate = p3;
+                while candidate < min {
+                    candidate *= 2;
+                }
+                best = best.min(candidate);
+                p3 *= 3;
+            }
+            p5 *= 5;
+        }
+        p7 *= 7;
+    }
+    best
 }
 
 pub fn get_factors_all(mut n: usize) -> (Vec<usize>, usize) {
@@ -119,3 +199,46 @@ pub fn get_factors_all(mut n: usize) -> (Vec<usize>, usize) {
     }
     (factors, count)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_generators_are_primitive_roots() {
+        for p in [3, 5, 7, 11, 13, 17, 31, 97, 257, 4099] {
+            let g = primitive_root(p);
+            assert_eq!(primitive_root(p), g);
+
+            //
+            // The powers g^1..g^(p-1) visit every nonzero residue once.
+            //
+            let mut seen = vec![false; p];
+            let mut x = 1;
+            for _ in 1..p {
+                x = (x * g) % p;
+                assert!(!seen[x], "{} repeats modulo {}", g, p);
+                seen[x] = true;
+            }
+            assert_eq!(x, 1);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::manual_is_multiple_of)]
+    fn next_7_smooth_matches_a_linear_search() {
+        let smooth = |mut n: usize| {
+            for p in [2, 3, 5, 7] {
+                while n % p == 0 {
+                    n /= p;
+                }
+            }
+            n == 1
+        };
+        for min in 0..5000 {
+            let expected = (min.max(1)..).find(|&n| smooth(n)).unwrap();
+            assert_eq!(next_7_smooth(min), expected, "min={}", min);
+        }
+        assert_eq!(next_7_smooth(2 * 4099 - 1), 8232);
+    }
+}
@@ -0,0 +1,257 @@
+use super::orig::w;
+#[cfg(feature = "parallel")]
+use super::parallel;
+use super::scratch::Scratch;
+use super::{debug_check_strides, find_dft_generic, prime_cache, DFTBase, Direction, FftFloat};
+use num_complex::Complex;
+use std::sync::Arc;
+
+/// Iterative mixed-radix DFT for `n = p^k`, `k >= 2`.
+///
+/// The input is gathered in digit-reversed order, then each stage twiddles
+/// and transforms groups of `radix` sub-transforms with one batched call to
+/// a small kernel plan. Stages run over two contiguous buffers and read
+/// their twiddles from per-stage tables, so unlike a `DFTRadix` chain
+/// there is no recursion and no column copy per level. Powers of three run
+/// 9-point stages, with one 3-point stage when `k` is odd.
+pub struct DFTPrimePower<T: FftFloat> {
+    n: usize,
+    p: usize,
+    direction: Direction,
+
+    //
+    // Output slot k of the gathered input reads input sample digitrev[k].
+    //
+    digitrev: Vec<usize>,
+    stages: Vec<Stage<T>>,
+
+    //
+    // The gathered input and each stage's output, 2n elements.
+    //
+    scratch: Scratch<Complex<T>>,
+}
+
+//
+// One pass combining `radix` transforms of `span / radix` points into each
+// block of `span`. `twiddles[(t - 1) * (span / radix) + j]` is W_span^(t·j).
+//
+struct Stage<T: FftFloat> {
+    radix: usize,
+    span: usize,
+    kernel: Arc<dyn DFTBase<T>>,
+    twiddles: Vec<Complex<T>>,
+}
+
+impl<T: FftFloat> DFTPrimePower<T> {
+    pub fn new(n: usize, direction: Direction) -> Self {
+        let (factors, count) = prime_cache::get_factors_all(n);
+        assert!(
+            count >= 2 && factors.iter().all(|&f| f == factors[0]),
+            "prime-power size must be p^k with k >= 2, got {}",
+            n
+        );
+        let p = factors[0];
+
+        //
+        // Pair up factors of three for the 9-point kernel; other primes
+        // have no kernel for p², so each stage is a single factor.
+        //
+        let mut radices = Vec::with_capacity(count);
+        let mut rest = count;
+        while p == 3 && rest >= 2 {
+            radices.push(9);
+            rest -= 2;
+        }
+        radices.extend(std::iter::repeat_n(p, rest));
+
+        let mut stages = Vec::with_capacity(radices.len());
+        let mut span = 1;
+        for &radix in &radices {
+            let sub = span;
+            span *= radix;
+            stages.push(Stage {
+                radix,
+                span,
+                kernel: find_dft_generic::<T>(radix, direction),
+                twiddles: (1..radix)
+                    .flat_map(|t| (0..sub).map(move |j| w(t * j, span, direction)))
+                    .collect(),
+            });
+        }
+
+        Self {
+            n,
+            p,
+            direction,
+            digitrev: digit_reversal(&radices),
+            stages,
+            scratch: Scratch::with_capacity(2 * n),
+        }
+    }
+}
+
+/// Input index read by each slot, so that every stage finds its `radix`
+/// sub-transforms as contiguous blocks. The last radix splits the input
+/// into its decimated subsequences first, as decimation in time does.
+fn digit_reversal(radices: &[usize]) -> Vec<usize> {
+    fn gather(offset: usize, stride: usize, radices: &[usize], out: &mut Vec<usize>) {
+        match radices.split_last() {
+            None => out.push(offset),
+            Some((&radix, inner)) => {
+                for t in 0..radix {
+                    gather(offset + t * stride, stride * radix, inner, out);
+                }
+            }
+        }
+    }
+    let mut out = Vec::with_capacity(radices.iter().product());
+    gather(0, 1, radices, &mut out);
+    out
+}
+
+impl<T: FftFloat> DFTBase<T> for DFTPrimePower<T> {
+    fn name(&self) -> String {
+        format!("{}PrimePower<{}>({})", self.direction.tag(), self.p, self.n)
+    }
+    fn size(&self) -> usize {
+        self.n
+    }
+    fn direction(&self) -> Direction {
+        self.direction
+    }
+    fn is_inplace(&self) -> bool {
+        false
+    }
+
+    fn xform_many(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+    ) {
+        debug_check_strides(
+            self.size(),
+            input.len(),
+            output.len(),
+            istep,
+            istep2,
+            ostep,
+            ostep2,
+            count,
+        );
+        #[cfg(feature = "parallel")]
+        if count >= parallel::MIN_PARALLEL_COUNT {
+            return parallel::xform_batches(
+                self.n,
+                input,
+                output,
+                istep,
+                istep2,
+                ostep,
+                ostep2,
+                count,
+                |i, o, is, is2, os, os2, c| self.xform_many_serial(i, o, is, is2, os, os2, c),
+            );
+        }
+        self.xform_many_serial(input, output, istep, istep2, ostep, ostep2, count);
+    }
+}
+
+impl<T: FftFloat> DFTPrimePower<T> {
+    #[allow(clippy::too_many_arguments)]
+    fn xform_many_serial(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+    ) {
+        let n = self.n;
+        self.scratch.with(2 * n, |scratch| {
+            let (mut src, mut dst) = scratch.split_at_mut(n);
+            for i in 0..count {
+                let in_base = i * istep2;
+                for (slot, &k) in src.iter_mut().zip(&self.digitrev) {
+                    *slot = input[in_base + k * istep];
+                }
+
+                for stage in &self.stages {
+                    let sub = stage.span / stage.radix;
+                    for block in (0..n).step_by(stage.span) {
+                        //
+                        // Twiddle sub-transforms 1.. of the block; the first
+                        // stage's sub-transforms are single points.
+                        //
+                        if sub > 1 {
+                            let rest = &mut src[block + sub..block + stage.span];
+                            for (x, &tw) in rest.iter_mut().zip(&stage.twiddles) {
+                                *x *= tw;
+                            }
+                        }
+
+                        //
+                        // `sub` radix-point transforms across the
+                        // sub-transforms, bin j reading every `sub`-th point.
+                        //
+                        stage.kernel.xform_many(
+                            &src[block..block + stage.span],
+                            &mut dst[block..block + stage.span],
+                            sub,
+                            1,
+                            sub,
+                            1,
+                            sub,
+                        );
+                    }
+                    std::mem::swap(&mut src, &mut dst);
+                }
+
+                let out_base = i * ostep2;
+                for (k, &x) in src.iter().enumerate() {
+                    output[out_base + k * ostep] = x;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_complex::Complex32;
+
+    #[test]
+    fn strided_batches_match_single_transforms() {
+        //
+        // Two interleaved 27-point inputs written to padded blocks, as a
+        // step of a larger composite plan would ask for them.
+        //
+        let n = 27;
+        let plan = DFTPrimePower::<f32>::new(n, Direction::Forward);
+        let input: Vec<Complex32> = (0..2 * n)
+            .map(|i| Complex32::new((i as f32 * 0.7).sin(), (i as f32 * 0.3).cos()))
+            .collect();
+        let mut batched = vec![Complex32::default(); 2 * (n + 5)];
+        plan.xform_many(&input, &mut batched, 2, 1, 1, n + 5, 2);
+
+        for i in 0..2 {
+            let column: Vec<Complex32> = input.iter().skip(i).step_by(2).copied().collect();
+            let mut single = vec![Complex32::default(); n];
+            plan.xform(&column, &mut single);
+            assert_eq!(&batched[i * (n + 5)..i * (n + 5) + n], &single[..]);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "prime-power size")]
+    fn mixed_factors_are_rejected() {
+        DFTPrimePower::<f32>::new(45, Direction::Forward);
+    }
+}
@@ -0,0 +1,156 @@
+use super::scratch::Scratch;
+use super::{find_dft, DFTBase, Direction, RDFTBase};
+use num_complex::Complex32;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+/// Real-input DFT built on a half-size complex plan.
+/// For even `n`, pairs of samples are packed as `z[m] = x[2m] + i·x[2m+1]`,
+/// transformed with an `n/2` complex DFT and separated into the `n/2 + 1`
+/// non-redundant bins with the split formula. Odd sizes fall back to a full
+/// complex transform of the zero-imaginary input.
+pub struct DFTReal {
+    n: usize,
+    inner: Arc<dyn DFTBase>,
+
+    //
+    // Split twiddles exp(-2πik/n) for k in 0..=n/2, empty for odd n.
+    //
+    twiddle: Vec<Complex32>,
+
+    //
+    // Packed input and its inner transform, n elements either way.
+    //
+    scratch: Scratch<Complex32>,
+}
+
+impl DFTReal {
+    pub fn new(n: usize) -> Self {
+        if !n.is_multiple_of(2) {
+            return Self {
+                n,
+                inner: find_dft(n, Direction::Forward),
+                twiddle: Vec::new(),
+                scratch: Scratch::with_capacity(2 * n),
+            };
+        }
+
+        let twiddle = (0..=n / 2)
+            .map(|k| Complex32::from_polar(1.0, -2.0 * PI * k as f32 / n as f32))
+            .collect();
+
+        Self {
+            n,
+            inner: find_dft(n / 2, Direction::Forward),
+            twiddle,
+            scratch: Scratch::with_capacity(n),
+        }
+    }
+}
+
+impl RDFTBase for DFTReal {
+    fn name(&self) -> String {
+        format!("Real[{}]", self.inner.name())
+    }
+    fn size(&self) -> usize {
+        self.n
+    }
+
+    fn xform_real(&self, input: &[f32], output: &mut [Complex32]) {
+        let n = self.n;
+
+        //
+        // Odd length: plain complex transform, keep the lower half.
+        //
+        if self.twiddle.is_empty() {
+            self.scratch.with(2 * n, |scratch| {
+                let (buf, spectrum) = scratch.split_at_mut(n);
+                for (b, &x) in buf.iter_mut().zip(&input[..n]) {
+                    *b = Complex32::new(x, 0.0);
+                }
+                self.inner.xform(buf, spectrum);
+                output[..n / 2 + 1].copy_from_slice(&spectrum[..n / 2 + 1]);
+            });
+            return;
+        }
+
+        self.scratch
+            .with(n, |scratch| self.split(input, output, scratch));
+    }
+}
+
+impl DFTReal {
+    /// Even-length path, using `scratch` for the `n/2` packed samples and
+    /// their transform.
+    fn split(&self, input: &[f32], output: &mut [Complex32], scratch: &mut [Complex32]) {
+        let n = self.n;
+
+        //
+        // Pack even/odd samples into real/imaginary parts and transform.
+        //
+        let m = n / 2;
+        let (packed, z) = scratch.split_at_mut(m);
+        for (c, p) in packed.iter_mut().zip(input[..n].chunks_exact(2)) {
+            *c = Complex32::new(p[0], p[1]);
+        }
+        self.inner.xform(packed, z);
+
+        //
+        // Split into even (E) and odd (O) sample spectra and recombine:
+        // X[k] = E[k] + W^k·O[k], with Z[m] aliased to Z[0].
+        //
+        for (k, (out, &w)) in output[..=m].iter_mut().zip(&self.twiddle).enumerate() {
+            let a = z[k % m];
+            let b = z[(m - k) % m].conj();
+            let even = (a + b) * 0.5;
+            let odd = (a - b) * Complex32::new(0.0, -0.5);
+            *out = even + w * odd;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn real_path_matches_complex_on_sine_sweep() {
+        for n in [2048, 16, 9] {
+            let rdft = DFTReal::new(n);
+            let cdft = find_dft(n, Direction::Forward);
+
+            //
+            // Hann-windowed linear sweep from DC towards Nyquist.
+            //
+            let input: Vec<f32> = (0..n)
+                .map(|i| {
+                    let t = i as f32 / n as f32;
+                    let window = 0.5 * (1.0 - (2.0 * PI * t).cos());
+                    window * (PI * 0.5 * n as f32 * t * t).sin()
+                })
+                .collect();
+
+            let mut real_out = vec![Complex32::default(); n / 2 + 1];
+            rdft.xform_real(&input, &mut real_out);
+
+            let mut complex_out: Vec<Complex32> =
+                input.iter().map(|&x| Complex32::new(x, 0.0)).collect();
+            cdft.xform_inplace(&mut complex_out);
+
+            //
+            // Compare each bin as a complex value, so a phase error fails
+            // as well as a magnitude one.
+            //
+            for (k, (r, c)) in real_out.iter().zip(&complex_out).enumerate() {
+                assert!(
+                    (r - c).norm() < 1e-4 * c.norm().max(1.0),
+                    "{}: k={} real {} complex {}",
+                    rdft.name(),
+                    k,
+                    r,
+                    c
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use parking_lot::Mutex;
+
+/// Working buffer kept by a plan between calls.
+///
+/// The buffer grows to the largest length requested and is then reused, so
+/// steady-state transforms do not touch the allocator. A call that finds the
+/// buffer already in use, e.g. the same plan run from several threads at
+/// once, gets a temporary allocation instead of waiting.
+pub(crate) struct Scratch<E> {
+    buf: Mutex<Vec<E>>,
+}
+
+impl<E: Copy + Default> Scratch<E> {
+    /// Creates a scratch buffer with room for `len` elements up front.
+    pub fn with_capacity(len: usize) -> Self {
+        Self {
+            buf: Mutex::new(vec![E::default(); len]),
+        }
+    }
+
+    /// Runs `f` on a buffer of exactly `len` elements. Contents left over
+    /// from earlier calls are not cleared.
+    pub fn with<R>(&self, len: usize, f: impl FnOnce(&mut [E]) -> R) -> R {
+        match self.buf.try_lock() {
+            Some(mut buf) => {
+                if buf.len() < len {
+                    buf.resize(len, E::default());
+                }
+                f(&mut buf[..len])
+            }
+            None => f(&mut vec![E::default(); len]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reentrant_use_falls_back_to_a_temporary() {
+        let scratch = Scratch::<u32>::with_capacity(4);
+        scratch.with(4, |outer| {
+            outer.fill(7);
+            scratch.with(8, |inner| {
+                assert_eq!(inner.len(), 8);
+                assert!(inner.iter().all(|&x| x == 0));
+            });
+        });
+
+        //
+        // The held buffer grows on demand and keeps its contents.
+        //
+        scratch.with(6, |buf| assert_eq!(&buf[..4], &[7; 4]));
+    }
+}
@@ -0,0 +1,212 @@
+use super::orig::w;
+#[cfg(feature = "parallel")]
+use super::parallel;
+use super::{debug_check_strides, find_dft_generic, DFTBase, Direction, FftFloat};
+use num_complex::Complex;
+use std::sync::Arc;
+
+/// Split-radix decimation-in-time DFT for `n = 2^k`.
+///
+/// Each level splits the input into one half-length transform of the even
+/// samples and two quarter-length transforms of the samples at `4m + 1` and
+/// `4m + 3`, then merges them with the L-shaped butterfly. Only the odd
+/// quarters are twiddled, which brings the cost down to about
+/// `4n·log2(n) - 6n + 8` real operations against `5n·log2(n)` for a chain of
+/// radix-2 steps. Sizes up to 4096 use the iterative radix-2 plan; this
+/// plan handles larger powers of two. Its sub-transforms come from the
+/// planner, so they switch to the iterative plan at 4096 points, and a plan
+/// built directly at a smaller size bottoms out in the improved kernels.
+pub struct DFTSplitRadix<T: FftFloat> {
+    n: usize,
+    direction: Direction,
+
+    //
+    // W^k and W^3k for k in 0..n/4.
+    //
+    w1: Vec<Complex<T>>,
+    w3: Vec<Complex<T>>,
+    dft_half: Arc<dyn DFTBase<T>>,
+    dft_quarter: Arc<dyn DFTBase<T>>,
+}
+
+impl<T: FftFloat> DFTSplitRadix<T> {
+    pub fn new(n: usize, direction: Direction) -> Self {
+        assert!(
+            n.is_power_of_two() && n >= 8,
+            "split-radix size must be a power of two >= 8, got {}",
+            n
+        );
+
+        let quarter = n / 4;
+        Self {
+            n,
+            direction,
+            w1: (0..quarter).map(|k| w(k, n, direction)).collect(),
+            w3: (0..quarter).map(|k| w(3 * k, n, direction)).collect(),
+            dft_half: find_dft_generic::<T>(n / 2, direction),
+            dft_quarter: find_dft_generic::<T>(quarter, direction),
+        }
+    }
+}
+
+impl<T: FftFloat> DFTBase<T> for DFTSplitRadix<T> {
+    fn name(&self) -> String {
+        format!("{}SplitRadix({})", self.direction.tag(), self.n)
+    }
+    fn size(&self) -> usize {
+        self.n
+    }
+    fn direction(&self) -> Direction {
+        self.direction
+    }
+    fn is_inplace(&self) -> bool {
+        false
+    }
+
+    fn xform_many(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+    ) {
+        debug_check_strides(
+            self.size(),
+            input.len(),
+            output.len(),
+            istep,
+            istep2,
+            ostep,
+            ostep2,
+            count,
+        );
+        #[cfg(feature = "parallel")]
+        if count >= parallel::MIN_PARALLEL_COUNT {
+            return parallel::xform_batches(
+                self.n,
+                input,
+                output,
+                istep,
+                istep2,
+                ostep,
+                ostep2,
+                count,
+                |i, o, is, is2, os, os2, c| self.xform_many_serial(i, o, is, is2, os, os2, c),
+            );
+        }
+        self.xform_many_serial(input, output, istep, istep2, ostep, ostep2, count);
+    }
+}
+
+impl<T: FftFloat> DFTSplitRadix<T> {
+    #[allow(clippy::too_many_arguments)]
+    fn xform_many_serial(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        istep: usize,
+        istep2: usize,
+        ostep: usize,
+        ostep2: usize,
+        count: usize,
+    ) {
+        let n = self.n;
+        let half = n / 2;
+        let quarter = n / 4;
+
+        //
+        // Multiplying by W^(n/4) is a quarter turn: -i forward, +i inverse.
+        //
+        let rot = Complex::new(T::zero(), T::cast(self.direction.sign() as f64));
+
+        for i in 0..count {
+            let in_base = i * istep2;
+            let out_base = i * ostep2;
+
+            //
+            // Even samples land in the first half of the output, the 4m+1
+            // and 4m+3 samples in the third and fourth quarters.
+            //
+            self.dft_half.xform_many(
+                &input[in_base..],
+                &mut output[out_base..],
+                2 * istep,
+                0,
+                ostep,
+                0,
+                1,
+            );
+            self.dft_quarter.xform_many(
+                &input[in_base + istep..],
+                &mut output[out_base + half * ostep..],
+                4 * istep,
+                0,
+                ostep,
+                0,
+                1,
+            );
+            self.dft_quarter.xform_many(
+                &input[in_base + 3 * istep..],
+                &mut output[out_base + (half + quarter) * ostep..],
+                4 * istep,
+                0,
+                ostep,
+                0,
+                1,
+            );
+
+            //
+            // L-shaped butterfly. Every output bin reads and writes the same
+            // four slots, so the merge runs in place.
+            //
+            for k in 0..quarter {
+                let i0 = out_base + k * ostep;
+                let i1 = i0 + quarter * ostep;
+                let i2 = i0 + half * ostep;
+                let i3 = i2 + quarter * ostep;
+
+                let a = output[i2] * self.w1[k];
+                let b = output[i3] * self.w3[k];
+                let sum = a + b;
+                let diff = rot * (a - b);
+
+                let (e0, e1) = (output[i0], output[i1]);
+                output[i0] = e0 + sum;
+                output[i2] = e0 - sum;
+                output[i1] = e1 + diff;
+                output[i3] = e1 - diff;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_complex::Complex32;
+
+    #[test]
+    fn round_trip_recovers_the_input() {
+        for n in [32, 64, 256, 2048, 8192] {
+            let input: Vec<Complex32> = (0..n)
+                .map(|i| Complex32::new((i as f32 * 0.7).sin(), (i as f32 * 0.3).cos()))
+                .collect();
+
+            let plan = DFTSplitRadix::new(n, Direction::Forward);
+            let mut spectrum = vec![Complex32::default(); n];
+            let mut restored = vec![Complex32::default(); n];
+            plan.xform(&input, &mut spectrum);
+            plan.xform_inverse(&spectrum, &mut restored);
+
+            let err = input
+                .iter()
+                .zip(&restored)
+                .map(|(a, b)| (a - b).norm())
+                .fold(0.0, f32::max);
+            assert!(err < 1e-5, "n={}: round-trip error {}", n, err);
+        }
+    }
+}
@@ -1,289 +1,3052 @@
 pub mod theme;
 
-use crate::fft::DFTBase;
 use eframe::egui;
-use num_complex::Complex32;
-use ringbuf::Consumer;
+use fftanalyzer::audio::{
+    self, AudioSource, ChannelMode, GeneratorSettings, SampleConsumer, Waveform,
+};
+use fftanalyzer::dsp::average::AveragingMode;
+use fftanalyzer::dsp::bands::{self, BandAnalyzer, BandResolution};
+use fftanalyzer::dsp::engine::{DspStats, SpectrumEngine};
+use fftanalyzer::dsp::logger::BandLogger;
+use fftanalyzer::dsp::noise_floor::NoiseFloor;
+use fftanalyzer::dsp::phase;
+use fftanalyzer::dsp::pitch::{self, PitchSmoother, DEFAULT_A4_HZ};
+use fftanalyzer::dsp::weighting::Weighting;
+use fftanalyzer::dsp::window::{WindowFunction, DEFAULT_KAISER_BETA};
+use fftanalyzer::dsp::worker::{SpectrumFrame, SpectrumWorker};
 use std::collections::VecDeque;
-use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::f32::consts::{PI, TAU};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Default display range for dB normalization, and the narrowest span the
+// floor and ceiling sliders may close to.
+const DEFAULT_MIN_DB: f32 = -100.0;
+const DEFAULT_MAX_DB: f32 = 0.0;
+const MIN_DB_SPAN: f32 = 10.0;
+
+// Default range either side of the reference in relative display.
+const DEFAULT_RELATIVE_SPAN_DB: f32 = 24.0;
+
+// Trace color of the second channel in stereo mode.
+const RIGHT_CHANNEL_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 120, 0);
+
+// Trace color of a frozen snapshot drawn behind the live spectrum.
+const SNAPSHOT_COLOR: egui::Color32 = egui::Color32::from_rgb(150, 80, 200);
+
+// Lower edge of the logarithmic frequency axis.
+const LOG_MIN_HZ: f32 = 20.0;
+
+// Narrowest band a zoom selection may cover, in bins, and the shortest
+// drag in pixels that counts as a selection rather than a click.
+const MIN_ZOOM_BINS: f32 = 4.0;
+const MIN_ZOOM_DRAG: f32 = 4.0;
+
+// How fast the noise floor estimate may rise, and the bins either side of
+// the peak whose floors the SNR readout takes the median of.
+const NOISE_FLOOR_RISE_DB_PER_SEC: f32 = 6.0;
+const SNR_FLOOR_RADIUS: usize = 16;
+
+// Color of frequency markers, and how close in pixels a right-click must
+// land to remove one.
+const MARKER_COLOR: egui::Color32 = egui::Color32::from_rgb(0, 120, 60);
+const MARKER_HIT_RADIUS: f32 = 5.0;
+
+// Message shown over the waterfall when no input arrives and the capture
+// reported no error.
+const NO_SIGNAL_HINT: &str = "NO SIGNAL\nCheck Privacy Settings\nAllow Desktop Apps Access";
+
+// Space reserved around the instantaneous plot for axis labels.
+const AXIS_LEFT_MARGIN: f32 = 32.0;
+const AXIS_BOTTOM_MARGIN: f32 = 14.0;
+
+// Waterfall history kept for scrolling back while paused, and the range
+// offered in the Display menu. A row costs one byte per texture column, so
+// the default holds 16 MiB at the largest FFT size.
+const DEFAULT_HISTORY_ROWS: usize = 4096;
+const MAX_HISTORY_ROWS: usize = 65536;
+
+// Rows in the waterfall texture by default and the range offered in the
+// Display menu. The texture is stretched to whatever height it is drawn at.
+const DEFAULT_WATERFALL_ROWS: usize = 256;
+const MIN_WATERFALL_ROWS: usize = 32;
+const MAX_WATERFALL_ROWS: usize = 2048;
+
+// Limits on the waterfall's width in texture columns when it follows the
+// window rather than the FFT size.
+const MIN_WATERFALL_COLUMNS: usize = 64;
+const MAX_WATERFALL_COLUMNS: usize = 4096;
+
+// Waterfall rows per second of input by default and the range offered in
+// the Display menu.
+const DEFAULT_WATERFALL_ROW_RATE: f32 = 30.0;
+const MIN_WATERFALL_ROW_RATE: f32 = 1.0;
+const MAX_WATERFALL_ROW_RATE: f32 = 200.0;
+
+// Space kept below the waterfall for the instantaneous plot, its heading
+// and the history slider, and the least height the waterfall shrinks to.
+const WATERFALL_RESERVED_HEIGHT: f32 = 190.0;
+const MIN_WATERFALL_DISPLAY_HEIGHT: f32 = 80.0;
+
+// Height of the optional phase plot, and the extra space reserved below
+// the waterfall for it and its heading.
+const PHASE_PLOT_HEIGHT: f32 = 80.0;
+const PHASE_RESERVED_HEIGHT: f32 = 110.0;
+
+// Spectrum frames kept for persistence trails by default and at most, and
+// the opacity of the newest trail.
+const DEFAULT_PERSISTENCE_FRAMES: usize = 16;
+const MAX_PERSISTENCE_FRAMES: usize = 64;
+const PERSISTENCE_ALPHA: f32 = 0.5;
+
+// Smoothing time constant of the tuner reading, and the offset within
+// which the note counts as in tune.
+const TUNER_SMOOTHING_SECS: f32 = 0.15;
+const TUNER_IN_TUNE_CENTS: f32 = 5.0;
+
+// Overlapping frames a Welch PSD averages by default and at most.
+const DEFAULT_WELCH_SEGMENTS: usize = 16;
+const MAX_WELCH_SEGMENTS: usize = 256;
+
+// Hop sizes offered in the Analysis menu, as divisors of the FFT size.
+const HOP_DIVISORS: [(usize, &str); 4] = [
+    (1, "100% (no overlap)"),
+    (2, "50%"),
+    (4, "25%"),
+    (8, "12.5%"),
+];
+
+// Smallest hop the menu allows: a sixteenth of the FFT size, and never
+// fewer than `MIN_HOP_SAMPLES`, so the worker is not asked for a full FFT
+// every few input samples.
+const MIN_HOP_DIVISOR: usize = 16;
+const MIN_HOP_SAMPLES: usize = 32;
+
+// Low-cut frequency offered when the filter is first switched on.
+const DEFAULT_LOW_CUT_HZ: f32 = 20.0;
+
+// FFT sizes offered in the Analysis menu.
+const FFT_SIZES: [usize; 5] = [512, 1024, 2048, 4096, crate::MAX_DFT_SIZE];
+
+// How long the bin-resolution note stays up after the FFT size is set.
+const RESOLUTION_NOTE_DURATION: Duration = Duration::from_secs(4);
+
+/// Mapping from frequency to horizontal position in the plots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrequencyScale {
+    Linear,
+    Logarithmic,
+}
+
+impl FrequencyScale {
+    pub const ALL: [FrequencyScale; 2] = [FrequencyScale::Linear, FrequencyScale::Logarithmic];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FrequencyScale::Linear => "Linear",
+            FrequencyScale::Logarithmic => "Logarithmic",
+        }
+    }
+}
+
+/// Quantity each bin is shown as, in the plot and in new waterfall rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpectrumUnit {
+    /// 20·log10 of the magnitude |X|.
+    AmplitudeDb,
+    /// 10·log10 of the power |X|². The same number as `AmplitudeDb` for
+    /// any one bin; it is the unit noise density is quoted in per Hz.
+    PowerDb,
+    /// |X| itself, on a linear axis from zero up to the ceiling.
+    LinearMagnitude,
+}
+
+impl SpectrumUnit {
+    pub const ALL: [SpectrumUnit; 3] = [
+        SpectrumUnit::AmplitudeDb,
+        SpectrumUnit::PowerDb,
+        SpectrumUnit::LinearMagnitude,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SpectrumUnit::AmplitudeDb => "Amplitude (dB)",
+            SpectrumUnit::PowerDb => "Power (dB)",
+            SpectrumUnit::LinearMagnitude => "Linear magnitude",
+        }
+    }
+
+    /// Axis unit, per root hertz or per hertz for noise density.
+    pub fn label(self, noise_density: bool) -> &'static str {
+        match (self, noise_density) {
+            (SpectrumUnit::AmplitudeDb, false) => "dBFS",
+            (SpectrumUnit::AmplitudeDb, true) => "dBFS/√Hz",
+            (SpectrumUnit::PowerDb, false) => "dB power",
+            (SpectrumUnit::PowerDb, true) => "dB/Hz",
+            (SpectrumUnit::LinearMagnitude, false) => "FS",
+            (SpectrumUnit::LinearMagnitude, true) => "FS/√Hz",
+        }
+    }
+
+    /// Maps a bin's power |X|², raised by `gain_db`, to `0.0..=1.0` over the
+    /// display range. The linear axis starts at zero, so only `max_db`
+    /// matters to it.
+    pub fn normalize(self, power: f32, gain_db: f32, min_db: f32, max_db: f32) -> f32 {
+        let t = match self {
+            SpectrumUnit::AmplitudeDb => {
+                let db = 20.0 * power.sqrt().max(1e-9).log10() + gain_db;
+                (db - min_db) / (max_db - min_db)
+            }
+            SpectrumUnit::PowerDb => {
+                let db = 10.0 * power.max(1e-18).log10() + gain_db;
+                (db - min_db) / (max_db - min_db)
+            }
+            SpectrumUnit::LinearMagnitude => power.sqrt() * 10f32.powf((gain_db - max_db) / 20.0),
+        };
+        t.clamp(0.0, 1.0)
+    }
+
+    /// Inverse of `normalize`: the reading at normalized level `t`, in
+    /// dB or in full-scale units.
+    pub fn denormalize(self, t: f32, min_db: f32, max_db: f32) -> f32 {
+        match self {
+            SpectrumUnit::AmplitudeDb | SpectrumUnit::PowerDb => min_db + t * (max_db - min_db),
+            SpectrumUnit::LinearMagnitude => t * 10f32.powf(max_db / 20.0),
+        }
+    }
+}
+
+/// One waterfall history row: levels after the intensity curve, with the
+/// contrast and brightness that curve used.
+#[derive(Clone)]
+struct WaterfallRow {
+    levels: Vec<u8>,
+    contrast: f32,
+    brightness: f32,
+}
+
+/// A labeled frequency the user pinned to the plots.
+struct Marker {
+    hz: f32,
+    label: String,
+}
 
 pub struct AnalyzerApp {
     //
-    // Audio input and processing components.
+    // Audio input and the DSP engine turning it into spectra.
+    //
+    audio_source: AudioSource,
+    worker: SpectrumWorker,
+
+    //
+    // Newest frame taken from the worker, which every plot draws from, and
+    // the name of the FFT plan computing them.
+    //
+    frame: SpectrumFrame,
+    plan_name: String,
+
+    //
+    // Test-signal settings, and while the generator runs, the source it
+    // replaced with that source's streams. The parked streams are drained
+    // every update so the source does not overrun meanwhile.
     //
-    audio_consumer: Consumer<f32, Arc<ringbuf::HeapRb<f32>>>,
-    _audio_stream: cpal::Stream,
-    fft_plan: Arc<dyn DFTBase>,
+    generator_settings: GeneratorSettings,
+    parked_source: Option<(AudioSource, Vec<SampleConsumer>)>,
 
     //
-    // DSP buffers for time-domain and frequency-domain processing.
+    // Latest spectrum normalized to the display dB range.
     //
-    dft_size: usize,
-    time_domain_buf: VecDeque<f32>,
     freq_domain_buf: Vec<f32>,
 
     //
-    // Waterfall visualization buffers and texture handle.
+    // Second channel of a stereo engine, normalized the same way and drawn
+    // over the first; empty for mono. The waterfall and peaks follow the
+    // first channel only.
+    //
+    overlay_buf: Vec<f32>,
+
+    //
+    // Per-bin phase of the latest frame for the optional phase plot,
+    // unwrapped across bins when `phase_unwrap` is set, and the second
+    // channel's in stereo mode.
+    //
+    phase_enabled: bool,
+    phase_unwrap: bool,
+    phase_buf: Vec<f32>,
+    phase_overlay_buf: Vec<f32>,
+
+    //
+    // Freezes the spectrum and waterfall while input keeps draining.
+    //
+    paused: bool,
+
+    //
+    // Display range in dB and the unit levels are shown in, shared by the
+    // plot and new waterfall rows.
+    //
+    min_db: f32,
+    max_db: f32,
+    unit: SpectrumUnit,
+
+    //
+    // Captured reference levels in dB per bin, whether the plot shows levels
+    // relative to them, and the range either side of the reference it spans.
+    //
+    reference_db: Option<Vec<f32>>,
+    show_relative: bool,
+    relative_span_db: f32,
+
+    //
+    // Frozen copy of the first channel's power and weighting curve for
+    // comparison with the live spectrum, whether it is drawn, and its
+    // trace normalized like `freq_domain_buf`. The power is kept rather
+    // than the normalized trace so both follow changes of unit and range.
+    //
+    snapshot: Option<(Vec<f32>, Vec<f32>)>,
+    show_snapshot: bool,
+    snapshot_buf: Vec<f32>,
+
+    //
+    // Octave or third-octave band bars in place of the first channel's
+    // trace, the bin-to-band mapping, rebuilt only when the resolution,
+    // FFT size or rate changes, and the bar heights normalized like
+    // `freq_domain_buf`.
+    //
+    band_view: Option<BandResolution>,
+    band_analyzer: Option<BandAnalyzer>,
+    band_buf: Vec<f32>,
+
+    //
+    // Parameters offered for each averaging mode in the menu.
+    //
+    averaging_alpha: f32,
+    averaging_count: usize,
+
+    //
+    // Kaiser β offered in the Window menu.
+    //
+    kaiser_beta: f32,
+
+    //
+    // Low-cut frequency offered in the Analysis menu, kept while the cut is
+    // off.
+    //
+    low_cut_hz: f32,
+
+    //
+    // Show levels as noise density, divided by the bin's ENBW in Hz.
+    //
+    noise_density: bool,
+
+    //
+    // Welch PSD display: the mean of the last `welch_segments` overlapping
+    // frames as a one-sided density, and the averaging it replaced.
+    //
+    welch_enabled: bool,
+    welch_segments: usize,
+    welch_saved_averaging: AveragingMode,
+
+    //
+    // Tuning reference for naming the dominant frequency's note.
+    //
+    a4_hz: f32,
+
+    //
+    // Tuner view in place of the spectrogram, and its smoothed reading of
+    // the dominant frequency; `None` while there is no signal.
+    //
+    tuner_enabled: bool,
+    tuner: PitchSmoother,
+    tuner_hz: Option<f32>,
+    last_tuner_update: Instant,
+
+    //
+    // Per-bin maximum of `freq_domain_buf`, decaying at `peak_decay_db`
+    // per second.
+    //
+    peak_hold_enabled: bool,
+    peak_hold: Vec<f32>,
+    peak_decay_db: f32,
+    last_peak_update: Instant,
+
+    //
+    // Estimated noise floor of the first channel, normalized like
+    // `freq_domain_buf` for the dashed trace, and the dominant peak's SNR
+    // over it.
+    //
+    noise_floor_enabled: bool,
+    noise_floor: NoiseFloor,
+    noise_floor_buf: Vec<f32>,
+    snr_db: Option<f32>,
+
+    //
+    // Recent copies of `freq_domain_buf`, newest first, drawn as fading
+    // trails behind the live spectrum.
+    //
+    persistence_enabled: bool,
+    persistence: VecDeque<Vec<f32>>,
+    persistence_frames: usize,
+
+    //
+    // Waterfall visualization buffers and texture handle. The texture shows
+    // `waterfall_height` rows of `waterfall_history` (normalized levels,
+    // newest first, at most `waterfall_history_rows`), starting
+    // `waterfall_scroll` rows back from the newest.
     //
     waterfall_buf: Vec<u8>,
+    waterfall_history: VecDeque<WaterfallRow>,
+    waterfall_history_rows: usize,
+    waterfall_scroll: usize,
     waterfall_height: usize,
+
+    //
+    // Columns of the waterfall texture: one per bin, drawn with nearest
+    // filtering, or with `waterfall_fit_width` the pixel width it is drawn
+    // at, binned or interpolated from the bins. History rows keep the
+    // width they were written at and are resampled when drawn.
+    //
+    waterfall_width: usize,
+    waterfall_fit_width: bool,
     texture: Option<egui::TextureHandle>,
+    colormap: theme::Colormap,
+
+    //
+    // Gamma of each colormap, indexed by discriminant. Applied when rows
+    // are colored rather than stored, so changing it recolors the history.
+    //
+    colormap_gamma: [f32; theme::Colormap::ALL.len()],
+
+    //
+    // Space the colormap by perceived color difference rather than
+    // position, so equal dB steps look equally far apart.
+    //
+    heatmap_uniform: bool,
+
+    //
+    // Contrast (as a gamma) and brightness baked into each waterfall row
+    // as it is written, so the history keeps the setting of its capture.
+    // Each row records them for the legend.
+    //
+    waterfall_contrast: f32,
+    waterfall_brightness: f32,
+
+    //
+    // Frequency axis shared by the waterfall and the instantaneous plot.
+    //
+    frequency_scale: FrequencyScale,
+
+    //
+    // Band `[low, high]` in Hz the instantaneous plot is zoomed to, and
+    // whether new waterfall rows crop to it too. `zoom_drag` holds the
+    // frequency where an in-progress selection started.
+    //
+    zoom: Option<(f32, f32)>,
+    zoom_waterfall: bool,
+    zoom_drag: Option<f32>,
+
+    //
+    // User-placed frequency markers, kept in Hz so they survive zoom, scale
+    // and FFT size changes, and whether the waterfall shows them too. Labels
+    // are numbered from a counter that never goes back, so removing a marker
+    // doesn't reuse its name.
+    //
+    markers: Vec<Marker>,
+    markers_placed: usize,
+    markers_on_waterfall: bool,
+
+    //
+    // Power accumulated over the FFTs since the last waterfall row. A row
+    // is due every `sample_rate / waterfall_row_rate` input samples, and
+    // `waterfall_row_samples` counts the input covered since the last one,
+    // so scrolling follows the audio clock rather than the repaint rate.
+    //
+    waterfall_row_rate: f32,
+    waterfall_accum: Vec<f32>,
+    waterfall_accum_count: usize,
+    waterfall_row_samples: f32,
 
     //
-    // Statistics and diagnostic information.
+    // Statistics and diagnostic information. `dsp_stats` is refreshed
+    // with the once-a-second log line and shown in the statistics window
+    // while `show_stats` is set.
     //
     last_stats_time: Instant,
-    samples_processed: usize,
-    max_input_peak: f32,
-    max_fft_peak: f32,
+    last_dropped: usize,
+    last_dropped_frames: usize,
+    dsp_stats: DspStats,
+    show_stats: bool,
+
+    //
+    // Bin-resolution annotation shown briefly after the FFT size is set.
+    //
+    resolution_note_until: Instant,
 
     //
-    // Silence detection state.
+    // Rolling band-level logger for unattended monitoring.
     //
-    no_signal_timer: Instant,
-    is_silence: bool,
+    band_logger: Option<BandLogger>,
+    band_log_resolution: BandResolution,
+    band_log_interval_secs: f32,
 }
 
 impl AnalyzerApp {
     pub fn new(
         _cc: &eframe::CreationContext,
-        audio_consumer: Consumer<f32, Arc<ringbuf::HeapRb<f32>>>,
-        audio_stream: cpal::Stream,
-        fft_plan: Arc<dyn DFTBase>,
-        dft_size: usize,
+        audio_source: AudioSource,
+        engine: SpectrumEngine,
     ) -> Self {
-        let waterfall_height = 256;
+        let waterfall_height = DEFAULT_WATERFALL_ROWS;
+        let dft_size = engine.dft_size();
+        let frame = SpectrumFrame::from_engine(&engine);
+        let plan_name = engine.plan_name();
 
         Self {
-            audio_consumer,
-            _audio_stream: audio_stream,
-            fft_plan,
-            dft_size,
+            audio_source,
+            worker: SpectrumWorker::spawn(engine),
+            frame,
+            plan_name,
+            generator_settings: GeneratorSettings::default(),
+            parked_source: None,
 
             //
-            // Initialize DSP buffers.
+            // Initialize display buffers.
             //
-            time_domain_buf: VecDeque::from(vec![0.0; dft_size]),
             freq_domain_buf: vec![0.0; dft_size / 2],
+            overlay_buf: Vec::new(),
+            phase_enabled: false,
+            phase_unwrap: false,
+            phase_buf: Vec::new(),
+            phase_overlay_buf: Vec::new(),
+            paused: false,
+            min_db: DEFAULT_MIN_DB,
+            max_db: DEFAULT_MAX_DB,
+            unit: SpectrumUnit::AmplitudeDb,
+            reference_db: None,
+            show_relative: false,
+            relative_span_db: DEFAULT_RELATIVE_SPAN_DB,
+            snapshot: None,
+            show_snapshot: false,
+            snapshot_buf: Vec::new(),
+            band_view: None,
+            band_analyzer: None,
+            band_buf: Vec::new(),
+            averaging_alpha: 0.3,
+            averaging_count: 8,
+            kaiser_beta: DEFAULT_KAISER_BETA,
+            low_cut_hz: DEFAULT_LOW_CUT_HZ,
+            noise_density: false,
+            welch_enabled: false,
+            welch_segments: DEFAULT_WELCH_SEGMENTS,
+            welch_saved_averaging: AveragingMode::None,
+            a4_hz: DEFAULT_A4_HZ,
+            tuner_enabled: false,
+            tuner: PitchSmoother::new(TUNER_SMOOTHING_SECS),
+            tuner_hz: None,
+            last_tuner_update: Instant::now(),
+            peak_hold_enabled: false,
+            noise_floor_enabled: false,
+            noise_floor: NoiseFloor::new(NOISE_FLOOR_RISE_DB_PER_SEC),
+            noise_floor_buf: Vec::new(),
+            snr_db: None,
+            peak_hold: vec![0.0; dft_size / 2],
+            peak_decay_db: 10.0,
+            last_peak_update: Instant::now(),
+            persistence_enabled: false,
+            persistence: VecDeque::new(),
+            persistence_frames: DEFAULT_PERSISTENCE_FRAMES,
 
             //
             // Allocate waterfall buffer (RGBA).
             //
             waterfall_buf: vec![0; (dft_size / 2) * waterfall_height * 4],
+            waterfall_width: dft_size / 2,
+            waterfall_fit_width: false,
+            waterfall_history: VecDeque::new(),
+            waterfall_history_rows: DEFAULT_HISTORY_ROWS,
+            waterfall_scroll: 0,
             waterfall_height,
             texture: None,
+            heatmap_uniform: false,
+            waterfall_contrast: 1.0,
+            waterfall_brightness: 1.0,
+            colormap: theme::Colormap::Jet,
+            colormap_gamma: theme::Colormap::ALL.map(theme::Colormap::default_gamma),
+            frequency_scale: FrequencyScale::Linear,
+            zoom: None,
+            zoom_waterfall: false,
+            zoom_drag: None,
+            markers: Vec::new(),
+            markers_placed: 0,
+            markers_on_waterfall: true,
+            waterfall_row_rate: DEFAULT_WATERFALL_ROW_RATE,
+            waterfall_accum: vec![0.0; dft_size / 2],
+            waterfall_accum_count: 0,
+            waterfall_row_samples: 0.0,
 
             //
-            // Initialize statistics and silence state.
+            // Initialize statistics.
             //
             last_stats_time: Instant::now(),
-            samples_processed: 0,
-            max_input_peak: 0.0,
-            max_fft_peak: 0.0,
-            no_signal_timer: Instant::now(),
-            is_silence: true,
+            dsp_stats: DspStats::default(),
+            show_stats: false,
+            last_dropped: 0,
+            last_dropped_frames: 0,
+            resolution_note_until: Instant::now() + RESOLUTION_NOTE_DURATION,
+            band_logger: None,
+            band_log_resolution: BandResolution::ThirdOctave,
+            band_log_interval_secs: 10.0,
         }
     }
 
     fn update_dsp(&mut self) {
-        let mut max_in_batch = 0.0;
+        if let Some((_, consumers)) = &mut self.parked_source {
+            for consumer in consumers {
+                consumer.clear();
+            }
+        }
 
         //
-        // Ingest audio samples from ring buffer.
+        // Handle every frame the worker finished since the last repaint, so
+        // nothing is skipped while the GUI keeps up with its queue. While
+        // paused the worker still drains the input so the ring buffer cannot
+        // overflow, but the display buffers keep the frozen frame.
         //
-        while let Some(sample) = self.audio_consumer.pop() {
-            self.time_domain_buf.pop_front();
-            self.time_domain_buf.push_back(sample);
-            self.samples_processed += 1;
+        self.worker.set_paused(self.paused);
+        if self.paused {
+            while self.worker.try_recv().is_some() {}
 
-            let abs_sample = sample.abs();
-            if abs_sample > self.max_input_peak {
-                self.max_input_peak = abs_sample;
-            }
-            if abs_sample > max_in_batch {
-                max_in_batch = abs_sample;
+            //
+            // Held peaks resume decaying from where they froze.
+            //
+            self.last_peak_update = Instant::now();
+        } else if self.waterfall_scroll > 0 {
+            //
+            // Resuming returns a scrolled-back waterfall to the newest rows
+            // before new ones arrive.
+            //
+            self.waterfall_scroll = 0;
+            self.recolor_waterfall();
+        } else {
+            //
+            // Frames may still arrive at a size just switched away from;
+            // the display buffers no longer fit them.
+            //
+            while let Some(frame) = self.worker.try_recv() {
+                if frame.dft_size != self.frame.dft_size {
+                    self.worker.recycle(frame);
+                    continue;
+                }
+                let spent = std::mem::replace(&mut self.frame, frame);
+                self.worker.recycle(spent);
+                self.process_frame();
             }
         }
 
         //
-        // Silence detection (−80 dB threshold, 2-second timeout).
+        // Periodic DSP statistics logging.
         //
-        if max_in_batch > 0.0001 {
-            self.no_signal_timer = Instant::now();
-            self.is_silence = false;
-        } else if self.no_signal_timer.elapsed() > Duration::from_secs(2) {
-            self.is_silence = true;
+        let elapsed = self.last_stats_time.elapsed();
+        if elapsed > Duration::from_secs(1) {
+            let mut stats = self
+                .worker
+                .call(move |engine| engine.take_dsp_stats(elapsed));
+
+            //
+            // Overruns mean the reader fell more than a ring buffer behind.
+            //
+            let dropped = self.audio_source.dropped_samples();
+            stats.dropped_samples = dropped.saturating_sub(self.last_dropped);
+            log::info!(
+                "DSP | {:.0} samples/s | {:.1} frames/s | Max Peak: {:.5} | \
+                 Floor: {:.1} dB | Silence: {}",
+                stats.samples_per_sec,
+                stats.frames_per_sec,
+                stats.max_peak,
+                stats.noise_floor_db,
+                stats.silent
+            );
+            self.dsp_stats = stats;
+            if dropped > self.last_dropped {
+                log::warn!(
+                    "Input overrun: {} samples dropped ({} total)",
+                    dropped - self.last_dropped,
+                    dropped
+                );
+                self.last_dropped = dropped;
+            }
+
+            //
+            // Dropped frames mean the GUI fell a whole frame queue behind.
+            //
+            let dropped_frames = self.worker.dropped_frames();
+            if dropped_frames > self.last_dropped_frames {
+                log::warn!(
+                    "GUI overrun: {} frames dropped ({} total)",
+                    dropped_frames - self.last_dropped_frames,
+                    dropped_frames
+                );
+                self.last_dropped_frames = dropped_frames;
+            }
+            self.last_stats_time = Instant::now();
+        }
+    }
+
+    /// Replaces the current source with the signal generator, at the
+    /// engine's sample rate and with as many streams as it has channels.
+    fn start_generator(&mut self) {
+        if self.parked_source.is_some() {
+            return;
+        }
+        let mode = if self.frame.channel_count() > 1 {
+            ChannelMode::Stereo
+        } else {
+            ChannelMode::Mono
+        };
+        let (generator, consumers) = audio::start_generator(
+            self.generator_settings,
+            self.frame.sample_rate,
+            mode,
+            crate::MAX_DFT_SIZE,
+        );
+        let previous = self
+            .worker
+            .call(move |engine| engine.replace_inputs(consumers));
+        let source = std::mem::replace(&mut self.audio_source, generator.into());
+        self.parked_source = Some((source, previous));
+        self.source_changed();
+    }
+
+    /// Stops the generator and returns to the source it replaced.
+    fn stop_generator(&mut self) {
+        if let Some((source, consumers)) = self.parked_source.take() {
+            self.worker.send(move |engine| {
+                engine.replace_inputs(consumers);
+            });
+            self.audio_source = source;
+            self.source_changed();
         }
+    }
+
+    fn source_changed(&mut self) {
+        self.last_dropped = self.audio_source.dropped_samples();
+        self.peak_hold.fill(0.0);
+    }
+
+    /// Updates the instantaneous spectrum and peaks from the worker's latest
+    /// frame and feeds it to the logger and the waterfall.
+    fn process_frame(&mut self) {
+        let width = self.frame.dft_size / 2;
 
         //
-        // Apply window function and prepare complex FFT input.
+        // Normalize weighted power to the display range. The buffers are
+        // taken out while they are filled from the frame.
         //
-        let mut complex_in: Vec<Complex32> = self
-            .time_domain_buf
-            .iter()
-            .enumerate()
-            .map(|(i, &x)| {
-                let window = 0.5
-                    * (1.0
-                        - (2.0 * std::f32::consts::PI * i as f32 / (self.dft_size - 1) as f32)
-                            .cos());
-                Complex32::new(x * window, 0.0)
-            })
-            .collect();
+        let mut buf = std::mem::take(&mut self.freq_domain_buf);
+        self.normalize_spectrum(&self.frame.power[0], &mut buf);
+        self.freq_domain_buf = buf;
+        if self.frame.channel_count() > 1 {
+            let mut buf = std::mem::take(&mut self.overlay_buf);
+            buf.resize(width, 0.0);
+            self.normalize_spectrum(&self.frame.power[1], &mut buf);
+            self.overlay_buf = buf;
+        }
+        self.update_phase();
+        self.update_noise_floor();
+        self.update_snapshot();
+        self.update_bands();
 
         //
-        // Execute FFT.
+        // Peak hold: let the held peaks fall, then raise them to any bin
+        // that now exceeds them.
         //
-        self.fft_plan.xform_inplace(&mut complex_in);
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_peak_update).as_secs_f32();
+        self.last_peak_update = now;
+        if self.peak_hold_enabled {
+            let decay = self.peak_decay_db * dt / (self.max_db - self.min_db);
+            for (peak, &val) in self.peak_hold.iter_mut().zip(&self.freq_domain_buf) {
+                *peak = (*peak - decay).max(val);
+            }
+        }
 
         //
-        // Convert magnitudes to normalized dB values.
+        // Persistence: keep a copy of this frame, reusing the storage of
+        // the one that ages out.
         //
-        let width = self.dft_size / 2;
-        let min_db = -100.0;
-        let max_db = 0.0;
+        if self.persistence_enabled {
+            let mut snapshot = if self.persistence.len() >= self.persistence_frames {
+                self.persistence.pop_back().unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            snapshot.clear();
+            snapshot.extend_from_slice(&self.freq_domain_buf);
+            self.persistence.push_front(snapshot);
+            self.persistence.truncate(self.persistence_frames);
+        }
 
-        for i in 0..width {
-            let mag = complex_in[i].norm();
-            if mag > self.max_fft_peak {
-                self.max_fft_peak = mag;
+        //
+        // Append band levels to the rolling log; stop logging on I/O errors.
+        //
+        let enbw_bins = self.enbw_bins();
+        if let Some(logger) = &mut self.band_logger {
+            let frame = &self.frame;
+            if let Err(err) = logger.record(&frame.power[0], &frame.weighting_db, enbw_bins) {
+                log::error!("Band logger stopped: {}", err);
+                self.band_logger = None;
             }
-
-            let db = 20.0 * mag.max(1e-9).log10();
-            let range = max_db - min_db;
-            let norm = ((db - min_db) / range).clamp(0.0, 1.0);
-
-            self.freq_domain_buf[i] = norm;
         }
 
         //
-        // Periodic DSP statistics logging.
+        // Integrate power until the frame's hop of input makes rows due.
+        // Rates above one row per hop repeat the latest spectrum, so each
+        // row always stands for the same span of time.
         //
-        if self.last_stats_time.elapsed() > Duration::from_secs(1) {
-            log::info!(
-                "DSP | Processed: {} | Max Peak: {:.5} | Silence: {}",
-                self.samples_processed,
-                self.max_input_peak,
-                self.is_silence
-            );
-            self.samples_processed = 0;
-            self.max_input_peak = 0.0;
-            self.max_fft_peak = 0.0;
-            self.last_stats_time = Instant::now();
+        for (acc, &p) in self.waterfall_accum.iter_mut().zip(&self.frame.power[0]) {
+            *acc += p;
+        }
+        self.waterfall_accum_count += 1;
+        self.waterfall_row_samples += self.frame.hop as f32;
+        let interval = self.frame.sample_rate as f32 / self.waterfall_row_rate;
+        let due = (self.waterfall_row_samples / interval) as usize;
+        if due == 0 {
+            return;
         }
+        self.waterfall_row_samples -= due as f32 * interval;
+        let rows = due.min(self.waterfall_height);
 
         //
-        // Update waterfall: scroll up one row and write new spectrum colors.
+        // Update waterfall: scroll up and write new spectrum colors. Levels
+        // go into the history so it can be scrolled back through and
+        // recolored when the colormap changes. A full history hands its
+        // oldest row over for reuse.
         //
-        let row_size = width * 4;
+        let columns = self.waterfall_width;
+        let row_size = columns * 4;
         let buf_len = self.waterfall_buf.len();
         self.waterfall_buf
-            .copy_within(0..buf_len - row_size, row_size);
+            .copy_within(0..buf_len - rows * row_size, rows * row_size);
+        let mut levels = if self.waterfall_history.len() >= self.waterfall_history_rows {
+            self.waterfall_history
+                .pop_back()
+                .map(|row| row.levels)
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        levels.resize(columns, 0);
 
-        for i in 0..width {
-            let val = self.freq_domain_buf[i];
-            let (r, g, b) = theme::get_heatmap_color(val);
+        let scale = 1.0 / self.waterfall_accum_count as f32;
+        let offset = self.level_offset_db();
+        self.waterfall_accum_count = 0;
+
+        //
+        // Each texture column covers the bins between its edges on the active
+        // frequency scale; keep the strongest so narrow tones stay visible
+        // where many bins share a column. A column fitted to the window
+        // that spans less than a bin interpolates between its neighbours
+        // instead of repeating one.
+        //
+        let range = self.waterfall_range();
+        let gamma = self.heatmap_gamma();
+        let normalized = |bin: usize| {
+            self.unit.normalize(
+                self.waterfall_accum[bin] * scale,
+                self.frame.weighting_db[bin] + offset,
+                self.min_db,
+                self.max_db,
+            )
+        };
+        for (i, level) in levels.iter_mut().enumerate() {
+            let start = self.fraction_to_bin(i as f32 / columns as f32, range);
+            let end = self.fraction_to_bin((i + 1) as f32 / columns as f32, range);
+            let val = if self.waterfall_fit_width && end - start < 1.0 {
+                let center = (0.5 * (start + end)).clamp(0.0, (width - 1) as f32);
+                let lo = (center as usize).min(width.saturating_sub(2));
+                let t = (center - lo as f32).min(1.0);
+                normalized(lo) * (1.0 - t) + normalized((lo + 1).min(width - 1)) * t
+            } else {
+                let lo = (start as usize).min(width - 1);
+                let hi = (end.ceil() as usize).clamp(lo + 1, width);
+                (lo..hi).map(normalized).fold(0.0, f32::max)
+            };
+            let val =
+                theme::adjust_intensity(val, self.waterfall_contrast, self.waterfall_brightness);
+            *level = (val * 255.0).round() as u8;
+            let (r, g, b) = theme::map_heatmap_color(
+                self.colormap,
+                *level as f32 / 255.0,
+                gamma,
+                self.heatmap_uniform,
+            );
             self.waterfall_buf[i * 4] = r;
             self.waterfall_buf[i * 4 + 1] = g;
             self.waterfall_buf[i * 4 + 2] = b;
             self.waterfall_buf[i * 4 + 3] = 255;
         }
+        let row = WaterfallRow {
+            levels,
+            contrast: self.waterfall_contrast,
+            brightness: self.waterfall_brightness,
+        };
+        for i in 1..rows {
+            self.waterfall_buf.copy_within(0..row_size, i * row_size);
+            self.waterfall_history.push_front(row.clone());
+        }
+        self.waterfall_history.push_front(row);
+        self.waterfall_history.truncate(self.waterfall_history_rows);
+        self.waterfall_accum.fill(0.0);
     }
-}
 
-impl eframe::App for AnalyzerApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        //
-        // Run DSP update and request GUI repaint.
-        //
-        self.update_dsp();
-        ctx.request_repaint();
+    /// Tracks the first channel's noise floor over the frame's hop of input
+    /// and reads the dominant peak's SNR against the floor around it.
+    fn update_noise_floor(&mut self) {
+        if !self.noise_floor_enabled {
+            return;
+        }
+        let dt = self.frame.hop as f32 / self.frame.sample_rate as f32;
+        self.noise_floor.update(&self.frame.power[0], dt);
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            //
-            // Draw top menu bar.
-            //
-            theme::draw_menu_bar(ui, &self.fft_plan.name());
-            ui.add_space(4.0);
+        let mut buf = std::mem::take(&mut self.noise_floor_buf);
+        buf.resize(self.freq_domain_buf.len(), 0.0);
+        self.normalize_spectrum(self.noise_floor.floor(), &mut buf);
+        self.noise_floor_buf = buf;
 
-            //
-            // Frequency-domain visualization window.
-            //
-            theme::draw_platinum_window(ui, "Frequency Domain", |ui| {
-                ui.heading("Spectrogram");
+        self.snr_db = match self.frame.dominant_hz {
+            Some(hz) if !self.frame.silent => {
+                let bin = self.frame.hz_to_bin(hz).round() as usize;
+                self.noise_floor
+                    .snr_db(&self.frame.power[0], bin, SNR_FLOOR_RADIUS)
+            }
+            _ => None,
+        };
+    }
 
-                //
-                // Upload waterfall buffer to texture each frame.
-                //
-                let width = self.dft_size / 2;
-                let height = self.waterfall_height;
-                let image =
-                    egui::ColorImage::from_rgba_unmultiplied([width, height], &self.waterfall_buf);
+    /// Maps one channel's weighted power to `0.0..=1.0` for the plot: in the
+    /// chosen unit over the display range, or in dB relative to the
+    /// captured reference when that is shown.
+    fn normalize_spectrum(&self, power: &[f32], out: &mut [f32]) {
+        self.normalize_weighted(power, &self.frame.weighting_db, out);
+    }
 
-                if let Some(texture) = &mut self.texture {
-                    texture.set(image, egui::TextureOptions::NEAREST);
-                } else {
-                    self.texture = Some(ui.ctx().load_texture(
-                        "waterfall",
-                        image,
-                        egui::TextureOptions::NEAREST,
-                    ));
+    /// `normalize_spectrum` with the given weighting curve in place of the
+    /// frame's.
+    fn normalize_weighted(&self, power: &[f32], weighting_db: &[f32], out: &mut [f32]) {
+        let offset = self.level_offset_db();
+        let reference = self.relative_reference();
+        let span = self.relative_span_db;
+        for (bin, (norm, (&power, &gain))) in out
+            .iter_mut()
+            .zip(power.iter().zip(weighting_db))
+            .enumerate()
+        {
+            *norm = match reference {
+                Some(reference) => {
+                    let db = 10.0 * power.max(1e-18).log10() + gain + offset - reference[bin];
+                    ((db + span) / (2.0 * span)).clamp(0.0, 1.0)
                 }
+                None => self
+                    .unit
+                    .normalize(power, gain + offset, self.min_db, self.max_db),
+            };
+        }
+    }
 
-                //
-                // Draw waterfall texture and overlay silence warning.
-                //
-                if let Some(tex) = &self.texture {
-                    let r = ui.image((tex.id(), egui::vec2(ui.available_width(), 200.0)));
-
-                    if self.is_silence {
-                        ui.painter().text(
-                            r.rect.center(),
-                            egui::Align2::CENTER_CENTER,
-                            "NO SIGNAL\nCheck Privacy Settings\nAllow Desktop Apps Access",
-                            egui::FontId::proportional(20.0),
-                            egui::Color32::RED,
-                        );
-                    }
-                }
+    /// Stores the latest frame's weighted levels in dB as the reference for
+    /// relative display. Levels include the noise density offset in effect.
+    fn capture_reference(&mut self) {
+        let offset = self.level_offset_db();
+        self.reference_db = Some(
+            self.frame.power[0]
+                .iter()
+                .zip(&self.frame.weighting_db)
+                .take(self.freq_domain_buf.len())
+                .map(|(&power, &gain)| 10.0 * power.max(1e-18).log10() + gain + offset)
+                .collect(),
+        );
+    }
 
-                ui.separator();
-                ui.heading("Instantaneous");
+    /// Freezes the first channel of the latest frame as the snapshot and
+    /// shows it.
+    fn take_snapshot(&mut self) {
+        self.snapshot = Some((self.frame.power[0].clone(), self.frame.weighting_db.clone()));
+        self.show_snapshot = true;
+        self.update_snapshot();
+    }
 
-                //
-                // Draw instantaneous spectrum plot.
-                //
-                egui::Frame::canvas(ui.style()).show(ui, |ui| {
-                    let (_rect, response) = ui.allocate_exact_size(
-                        egui::vec2(ui.available_width(), 100.0),
-                        egui::Sense::hover(),
-                    );
+    /// Normalizes the snapshot for the plot with the display settings in
+    /// effect, each with the weighting it was taken with. A snapshot from
+    /// another FFT size is not drawn.
+    fn update_snapshot(&mut self) {
+        let mut buf = std::mem::take(&mut self.snapshot_buf);
+        buf.clear();
+        if let Some((power, weighting_db)) = &self.snapshot {
+            if self.show_snapshot && power.len() >= self.freq_domain_buf.len() {
+                buf.resize(self.freq_domain_buf.len(), 0.0);
+                self.normalize_weighted(power, weighting_db, &mut buf);
+            }
+        }
+        self.snapshot_buf = buf;
+    }
 
-                    ui.painter().rect_stroke(
-                        response.rect,
-                        egui::Rounding::ZERO,
-                        egui::Stroke::new(1.0, egui::Color32::GRAY),
-                    );
+    /// Sums the first channel's power into the bands of `band_view`, as
+    /// `BandAnalyzer::calibrated_powers`, and normalizes the bar heights.
+    /// In relative display each band is compared with the reference summed
+    /// over the same bins, without the ENBW, which would cancel.
+    fn update_bands(&mut self) {
+        self.band_buf.clear();
+        let Some(resolution) = self.band_view else {
+            return;
+        };
+        let (sample_rate, n) = (self.frame.sample_rate, self.frame.dft_size);
+        let analyzer = match self.band_analyzer.take() {
+            Some(analyzer)
+                if analyzer.resolution() == resolution
+                    && analyzer.sample_rate() == sample_rate
+                    && analyzer.dft_size() == n =>
+            {
+                analyzer
+            }
+            _ => BandAnalyzer::new(resolution, sample_rate, n),
+        };
 
-                    let points: Vec<egui::Pos2> = self
-                        .freq_domain_buf
-                        .iter()
-                        .enumerate()
-                        .map(|(i, &val)| {
-                            let x = response.rect.min.x
-                                + (i as f32 / width as f32) * response.rect.width();
-                            let y = response.rect.max.y - (val * response.rect.height());
-                            egui::Pos2::new(x, y)
-                        })
-                        .collect();
-
-                    ui.painter().add(egui::Shape::line(
-                        points,
-                        egui::Stroke::new(1.0, egui::Color32::DARK_BLUE),
+        let to_power = |db: f32| 10f32.powf(db / 10.0);
+        let (power, weighting_db) = (&self.frame.power[0], &self.frame.weighting_db);
+        match self.relative_reference() {
+            Some(reference) => {
+                let powers = analyzer.calibrated_powers(power, weighting_db, 1.0);
+                let reference: Vec<f32> = reference.iter().map(|&db| to_power(db)).collect();
+                let offset = self.level_offset_db();
+                let span = self.relative_span_db;
+                self.band_buf
+                    .extend(powers.iter().zip(analyzer.powers(&reference)).map(
+                        |(&power, reference)| {
+                            let db =
+                                10.0 * (power.max(1e-18) / reference.max(1e-18)).log10() + offset;
+                            ((db + span) / (2.0 * span)).clamp(0.0, 1.0)
+                        },
                     ));
-                });
+            }
+            None => {
+                let powers = analyzer.calibrated_powers(power, weighting_db, self.enbw_bins());
+                self.band_buf.extend(
+                    powers
+                        .iter()
+                        .map(|&power| self.unit.normalize(power, 0.0, self.min_db, self.max_db)),
+                );
+            }
+        }
+        self.band_analyzer = Some(analyzer);
+    }
+
+    /// The reference the plot is drawn relative to, if relative display is
+    /// on and a reference at the current FFT size has been captured.
+    /// Otherwise the plot falls back to absolute levels.
+    fn relative_reference(&self) -> Option<&[f32]> {
+        match &self.reference_db {
+            Some(reference)
+                if self.show_relative && reference.len() == self.freq_domain_buf.len() =>
+            {
+                Some(reference)
+            }
+            _ => None,
+        }
+    }
+
+    /// Copies the latest frame's phase into the plot buffers, unwrapped if
+    /// asked. The frozen frame is kept while paused, so this can
+    /// rerun when the unwrap option changes.
+    fn update_phase(&mut self) {
+        self.phase_buf.clear();
+        self.phase_overlay_buf.clear();
+        if !self.phase_enabled {
+            return;
+        }
+        self.phase_buf.extend_from_slice(&self.frame.phase[0]);
+        if self.frame.channel_count() > 1 {
+            self.phase_overlay_buf
+                .extend_from_slice(&self.frame.phase[1]);
+        }
+        if self.phase_unwrap {
+            phase::unwrap(&mut self.phase_buf);
+            phase::unwrap(&mut self.phase_overlay_buf);
+        }
+    }
+
+    /// Switches to an `n`-point FFT, resizing every display buffer sized
+    /// from it.
+    fn set_dft_size(&mut self, n: usize) {
+        if n == self.frame.dft_size {
+            return;
+        }
+        log::info!("Switching FFT size {} -> {}", self.frame.dft_size, n);
+        let (plan_name, frame) = self.worker.call(move |engine| {
+            engine.set_dft_size(n);
+            if engine.hop() < min_hop(n) {
+                engine.set_hop(min_hop(n));
+            }
+            (engine.plan_name(), SpectrumFrame::from_engine(engine))
+        });
+        self.plan_name = plan_name;
+        self.frame = frame;
+
+        self.freq_domain_buf = vec![0.0; n / 2];
+        self.overlay_buf.clear();
+        self.phase_buf.clear();
+        self.phase_overlay_buf.clear();
+        self.peak_hold = vec![0.0; n / 2];
+        self.persistence.clear();
+        self.reference_db = None;
+        self.snapshot = None;
+        self.snapshot_buf.clear();
+        self.noise_floor.reset();
+        self.noise_floor_buf.clear();
+        self.snr_db = None;
+
+        //
+        // The waterfall restarts with the new bins; a texture of the old size
+        // cannot be reused. A width fitted to the window stays.
+        //
+        if !self.waterfall_fit_width {
+            self.waterfall_width = n / 2;
+        }
+        self.waterfall_buf = vec![0; self.waterfall_width * self.waterfall_height * 4];
+        self.waterfall_history.clear();
+        self.waterfall_scroll = 0;
+        self.waterfall_accum = vec![0.0; n / 2];
+        self.waterfall_accum_count = 0;
+        self.waterfall_row_samples = 0.0;
+        self.texture = None;
+
+        //
+        // The logger's bands were binned for the old size.
+        //
+        if let Some(logger) = self.band_logger.take() {
+            log::info!(
+                "Band logger stopped after {} rows (FFT size changed)",
+                logger.rows()
+            );
+        }
+
+        self.resolution_note_until = Instant::now() + RESOLUTION_NOTE_DURATION;
+    }
+
+    /// Repaints the visible waterfall from the history, starting
+    /// `waterfall_scroll` rows back. Rows past the oldest one stay black,
+    /// and rows of another width are resampled to the nearest column.
+    fn recolor_waterfall(&mut self) {
+        let columns = self.waterfall_width;
+        let gamma = self.heatmap_gamma();
+        for (row, pixels) in self.waterfall_buf.chunks_exact_mut(columns * 4).enumerate() {
+            let Some(WaterfallRow { levels, .. }) =
+                self.waterfall_history.get(self.waterfall_scroll + row)
+            else {
+                pixels.fill(0);
+                continue;
+            };
+            for (i, px) in pixels.chunks_exact_mut(4).enumerate() {
+                let Some(&level) = levels.get(i * levels.len() / columns) else {
+                    px.fill(0);
+                    continue;
+                };
+                let (r, g, b) = theme::map_heatmap_color(
+                    self.colormap,
+                    level as f32 / 255.0,
+                    gamma,
+                    self.heatmap_uniform,
+                );
+                px.copy_from_slice(&[r, g, b, 255]);
+            }
+        }
+    }
+
+    /// Resizes the waterfall texture to `rows` rows and repaints it from the
+    /// history, so the rows already recorded stay visible.
+    fn set_waterfall_height(&mut self, rows: usize) {
+        let rows = rows.clamp(MIN_WATERFALL_ROWS, MAX_WATERFALL_ROWS);
+        if rows == self.waterfall_height {
+            return;
+        }
+        self.waterfall_height = rows;
+        self.waterfall_buf = vec![0; self.waterfall_width * rows * 4];
+        self.waterfall_history_rows = self.waterfall_history_rows.max(rows);
+        self.waterfall_scroll = self.waterfall_scroll.min(self.max_waterfall_scroll());
+        self.texture = None;
+        self.recolor_waterfall();
+    }
+
+    /// Gives the waterfall texture `columns` columns and repaints it from
+    /// the history.
+    fn set_waterfall_width(&mut self, columns: usize) {
+        if columns == self.waterfall_width {
+            return;
+        }
+        self.waterfall_width = columns;
+        self.waterfall_buf = vec![0; columns * self.waterfall_height * 4];
+        self.texture = None;
+        self.recolor_waterfall();
+    }
+
+    /// Furthest the waterfall can scroll back while still showing a full
+    /// window of history.
+    fn max_waterfall_scroll(&self) -> usize {
+        self.waterfall_history
+            .len()
+            .saturating_sub(self.waterfall_height)
+    }
+
+    /// Scrolls the waterfall to `rows` back from the newest row.
+    fn scroll_waterfall(&mut self, rows: usize) {
+        let rows = rows.min(self.max_waterfall_scroll());
+        if rows != self.waterfall_scroll {
+            self.waterfall_scroll = rows;
+            self.recolor_waterfall();
+        }
+    }
+
+    /// Gamma of the selected colormap.
+    fn heatmap_gamma(&self) -> f32 {
+        self.colormap_gamma[self.colormap as usize]
+    }
+
+    /// Contrast and brightness of the newest row on screen, which the
+    /// legend describes; the current setting while the waterfall is empty.
+    fn legend_intensity(&self) -> (f32, f32) {
+        match self.waterfall_history.get(self.waterfall_scroll) {
+            Some(row) => (row.contrast, row.brightness),
+            None => (self.waterfall_contrast, self.waterfall_brightness),
+        }
+    }
+
+    /// Frequency spacing between adjacent FFT bins.
+    fn bin_width_hz(&self) -> f32 {
+        self.frame.bin_width_hz()
+    }
+
+    /// The window's equivalent noise bandwidth in bins.
+    fn enbw_bins(&self) -> f32 {
+        self.frame.enbw_hz / self.bin_width_hz()
+    }
+
+    /// Band shown by the instantaneous plot: the zoom selection, or
+    /// everything up to Nyquist.
+    fn view_range(&self) -> (f32, f32) {
+        self.zoom.unwrap_or((0.0, self.frame.nyquist_hz()))
+    }
+
+    /// Band covered by new waterfall rows.
+    fn waterfall_range(&self) -> (f32, f32) {
+        if self.zoom_waterfall {
+            self.view_range()
+        } else {
+            (0.0, self.frame.nyquist_hz())
+        }
+    }
+
+    /// Lower edge of `range` on the active axis; the log axis cannot reach
+    /// below `LOG_MIN_HZ`.
+    fn axis_low(&self, (low, high): (f32, f32)) -> f32 {
+        match self.frequency_scale {
+            FrequencyScale::Linear => low,
+            FrequencyScale::Logarithmic => low.max(LOG_MIN_HZ).min(high * 0.5),
+        }
+    }
+
+    /// Maps a (fractional) bin index to an x position inside `rect` on the
+    /// plot's zoomed axis. Bins outside the zoom land outside `rect`.
+    fn bin_to_x(&self, bin: f32, rect: egui::Rect) -> f32 {
+        rect.min.x + self.bin_to_fraction(bin, self.view_range()) * rect.width()
+    }
+
+    /// Maps a frequency to an x position inside `rect`, as `bin_to_x`.
+    fn hz_to_x(&self, hz: f32, rect: egui::Rect) -> f32 {
+        self.bin_to_x(self.frame.hz_to_bin(hz), rect)
+    }
+
+    /// Position of a bin's center frequency along an axis spanning `range`,
+    /// `0.0..=1.0` inside it. On the log axis everything below `LOG_MIN_HZ`,
+    /// DC included, sits at `LOG_MIN_HZ`.
+    fn bin_to_fraction(&self, bin: f32, range: (f32, f32)) -> f32 {
+        let low = self.axis_low(range);
+        let high = range.1;
+        let hz = self.frame.bin_to_hz(bin);
+        match self.frequency_scale {
+            FrequencyScale::Linear => (hz - low) / (high - low),
+            FrequencyScale::Logarithmic => {
+                (hz.max(LOG_MIN_HZ) / low).log10() / (high / low).log10()
+            }
+        }
+    }
+
+    /// Inverse of `bin_to_fraction`: the fractional bin at axis position `t`.
+    fn fraction_to_bin(&self, t: f32, range: (f32, f32)) -> f32 {
+        let low = self.axis_low(range);
+        let high = range.1;
+        let hz = match self.frequency_scale {
+            FrequencyScale::Linear => low + t * (high - low),
+            FrequencyScale::Logarithmic => low * (high / low).powf(t),
+        };
+        self.frame.hz_to_bin(hz)
+    }
+
+    /// Frequency under x position `x` inside `plot`.
+    fn x_to_hz(&self, x: f32, plot: egui::Rect) -> f32 {
+        let t = (x - plot.min.x) / plot.width();
+        self.frame
+            .bin_to_hz(self.fraction_to_bin(t, self.view_range()))
+    }
+
+    /// Zooms the plot to `[low, high]`, widened to at least `MIN_ZOOM_BINS`
+    /// and kept inside `0..=nyquist`.
+    fn set_zoom(&mut self, low: f32, high: f32) {
+        let nyquist = self.frame.nyquist_hz();
+        let min_span = (MIN_ZOOM_BINS * self.bin_width_hz()).min(nyquist);
+        let (mut low, mut high) = (low.min(high).max(0.0), high.max(low).min(nyquist));
+        if high - low < min_span {
+            let center = 0.5 * (low + high);
+            low = (center - 0.5 * min_span).clamp(0.0, nyquist - min_span);
+            high = low + min_span;
+        }
+        log::info!("Zoom {:.1} Hz - {:.1} Hz", low, high);
+        self.zoom = Some((low, high));
+        if self.zoom_waterfall {
+            self.clear_waterfall();
+        }
+    }
+
+    /// Returns the plot to the full band.
+    fn reset_zoom(&mut self) {
+        if self.zoom.take().is_some() && self.zoom_waterfall {
+            self.clear_waterfall();
+        }
+    }
+
+    /// Blanks the waterfall history, e.g. when its frequency axis changes
+    /// and old rows would no longer line up.
+    fn clear_waterfall(&mut self) {
+        self.waterfall_buf.fill(0);
+        self.waterfall_history.clear();
+        self.waterfall_scroll = 0;
+    }
+
+    /// Draws the frequency and dB grid with its labels around `plot`.
+    /// Ticks are derived from the current FFT size and frequency scale on
+    /// every call, so they follow changes to either.
+    fn draw_axes(&self, painter: &egui::Painter, plot: egui::Rect) {
+        let grid = egui::Stroke::new(1.0, egui::Color32::from_black_alpha(30));
+        let tick = egui::Stroke::new(1.0, egui::Color32::BLACK);
+        let font = egui::FontId::proportional(10.0);
+        let range = self.view_range();
+        let (low, high) = (self.axis_low(range), range.1);
+
+        //
+        // Frequency ticks: 1-2-5 steps across the span on the linear axis,
+        // 1-2-5 multiples of each decade on the log axis. A log zoom too
+        // narrow to hold a few of those falls back to linear steps.
+        //
+        let linear_ticks = || {
+            let target = (plot.width() / 80.0).max(1.0);
+            let step = nice_step((high - low) / target);
+            ((low / step).floor() as i64 + 1..)
+                .map(|k| k as f32 * step)
+                .take_while(|&f| f < high)
+                .collect::<Vec<f32>>()
+        };
+        let freqs: Vec<f32> = match self.frequency_scale {
+            FrequencyScale::Linear => linear_ticks(),
+            FrequencyScale::Logarithmic => {
+                let decades: Vec<f32> = (1..6)
+                    .flat_map(|e| [1.0, 2.0, 5.0].map(|m| m * 10f32.powi(e)))
+                    .filter(|&f| f > low && f < high)
+                    .collect();
+                if decades.len() >= 3 {
+                    decades
+                } else {
+                    linear_ticks()
+                }
+            }
+        };
+        for hz in freqs {
+            let x = self.hz_to_x(hz, plot);
+            painter.line_segment([egui::pos2(x, plot.min.y), egui::pos2(x, plot.max.y)], grid);
+            painter.line_segment(
+                [egui::pos2(x, plot.max.y), egui::pos2(x, plot.max.y + 3.0)],
+                tick,
+            );
+            painter.text(
+                egui::pos2(x, plot.max.y + 3.0),
+                egui::Align2::CENTER_TOP,
+                format_hz(hz),
+                font.clone(),
+                egui::Color32::BLACK,
+            );
+        }
+
+        //
+        // Level ticks over the display range, matching the waterfall legend
+        // unless levels are relative, and the unit they are in.
+        //
+        let relative = self.relative_reference().is_some();
+        let ticks = if relative {
+            self.relative_ticks()
+        } else {
+            self.level_ticks()
+        };
+        for (t, label) in ticks {
+            let y = plot.max.y - t * plot.height();
+            painter.line_segment([egui::pos2(plot.min.x, y), egui::pos2(plot.max.x, y)], grid);
+            painter.line_segment(
+                [egui::pos2(plot.min.x - 3.0, y), egui::pos2(plot.min.x, y)],
+                tick,
+            );
+            painter.text(
+                egui::pos2(plot.min.x - 5.0, y),
+                egui::Align2::RIGHT_CENTER,
+                label,
+                font.clone(),
+                egui::Color32::BLACK,
+            );
+        }
+        painter.text(
+            egui::pos2(plot.max.x - 4.0, plot.min.y + 4.0),
+            egui::Align2::RIGHT_TOP,
+            if relative {
+                "dB rel"
+            } else {
+                self.unit.label(self.per_hz())
+            },
+            font,
+            egui::Color32::from_black_alpha(160),
+        );
+    }
+
+    /// Ticks for relative display, across the span either side of the
+    /// reference, as `level_ticks`.
+    fn relative_ticks(&self) -> Vec<(f32, String)> {
+        let span = self.relative_span_db;
+        let step = nice_step(span / 3.0);
+        let steps = (span / step).floor() as i64;
+        (-steps..=steps)
+            .map(|k| k as f32 * step)
+            .map(|db| ((db + span) / (2.0 * span), format!("{:.0}", db)))
+            .collect()
+    }
+
+    /// Level ticks as (height in `0.0..=1.0`, label) pairs: every 10 or 20
+    /// dB on the dB axes, 1-2-5 steps of full scale on the linear one.
+    fn level_ticks(&self) -> Vec<(f32, String)> {
+        match self.unit {
+            SpectrumUnit::AmplitudeDb | SpectrumUnit::PowerDb => {
+                let range = self.max_db - self.min_db;
+                let step = if range > 60.0 { 20.0 } else { 10.0 };
+                ((self.min_db / step).ceil() as i64..)
+                    .map(|k| k as f32 * step)
+                    .take_while(|&db| db <= self.max_db + 1e-3)
+                    .map(|db| ((db - self.min_db) / range, format!("{:.0}", db)))
+                    .collect()
+            }
+            SpectrumUnit::LinearMagnitude => {
+                let ceiling = 10f32.powf(self.max_db / 20.0);
+                let step = nice_step(ceiling / 5.0);
+                let decimals = (-step.log10().floor()).max(0.0) as usize;
+                (0..)
+                    .map(|k| k as f32 * step)
+                    .take_while(|&v| v <= ceiling * (1.0 + 1e-3))
+                    .map(|v| (v / ceiling, format!("{:.*}", decimals, v)))
+                    .collect()
+            }
+        }
+    }
+
+    /// Shows the last second's `DspStats` in a small floating window while
+    /// View → Statistics is on.
+    fn draw_stats_window(&mut self, ctx: &egui::Context) {
+        let stats = self.dsp_stats;
+        let dropped_frames = self.last_dropped_frames;
+        egui::Window::new("Statistics")
+            .open(&mut self.show_stats)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("dsp_stats").num_columns(2).show(ui, |ui| {
+                    let peak_db = 20.0 * stats.max_peak.max(1e-9).log10();
+                    let rows = [
+                        ("Input", format!("{:.0} samples/s", stats.samples_per_sec)),
+                        ("FFT frames", format!("{:.1} /s", stats.frames_per_sec)),
+                        ("Max peak", format!("{:.1} dBFS", peak_db)),
+                        ("Noise floor", format!("{:.1} dB", stats.noise_floor_db)),
+                        ("Dropped samples", stats.dropped_samples.to_string()),
+                        ("Dropped frames", dropped_frames.to_string()),
+                        (
+                            "Silence",
+                            if stats.silent { "yes" } else { "no" }.to_string(),
+                        ),
+                    ];
+                    for (name, value) in rows {
+                        ui.label(name);
+                        ui.monospace(value);
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    /// Draws the phase plot: wrapped phase on a fixed ±π axis, unwrapped
+    /// phase scaled to its visible extent. Bins at or below the display
+    /// floor are left out, since their phase is only noise.
+    fn draw_phase(&self, painter: &egui::Painter, plot: egui::Rect) {
+        let grid = egui::Stroke::new(1.0, egui::Color32::from_black_alpha(30));
+        let font = egui::FontId::proportional(10.0);
+        let width = self.freq_domain_buf.len();
+        let (first, last) = {
+            let range = self.view_range();
+            let first = self.frame.hz_to_bin(range.0).floor().max(0.0) as usize;
+            let last = (self.frame.hz_to_bin(range.1).ceil() as usize + 1).min(width);
+            (first.min(last), last)
+        };
+        let shown = |levels: &[f32], i: usize| levels.get(i).is_some_and(|&v| v > 0.0);
+
+        //
+        // Vertical extent, at least one full turn so a flat curve does not
+        // fill the plot with noise.
+        //
+        let (low, high) = if self.phase_unwrap {
+            let visible = [
+                (&self.phase_buf, &self.freq_domain_buf),
+                (&self.phase_overlay_buf, &self.overlay_buf),
+            ]
+            .into_iter()
+            .flat_map(|(phase, levels)| {
+                (first..last.min(phase.len()))
+                    .filter(move |&i| shown(levels, i))
+                    .map(move |i| phase[i])
+            });
+            let (low, high) = visible.fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), p| {
+                (lo.min(p), hi.max(p))
+            });
+            if low > high {
+                (-PI, PI)
+            } else {
+                let pad = (TAU - (high - low)).max(0.0) / 2.0;
+                (low - pad, high + pad)
+            }
+        } else {
+            (-PI, PI)
+        };
+        let to_y = |p: f32| plot.max.y - (p - low) / (high - low) * plot.height();
+
+        painter.rect_stroke(
+            plot,
+            egui::Rounding::ZERO,
+            egui::Stroke::new(1.0, egui::Color32::GRAY),
+        );
+        let labels: Vec<(f32, String)> = if self.phase_unwrap {
+            vec![(high, format!("{:.0}", high)), (low, format!("{:.0}", low))]
+        } else {
+            vec![(PI, "π".into()), (0.0, "0".into()), (-PI, "−π".into())]
+        };
+        for (p, label) in labels {
+            let y = to_y(p);
+            painter.line_segment([egui::pos2(plot.min.x, y), egui::pos2(plot.max.x, y)], grid);
+            let align = if p >= high {
+                egui::Align2::RIGHT_TOP
+            } else if p <= low {
+                egui::Align2::RIGHT_BOTTOM
+            } else {
+                egui::Align2::RIGHT_CENTER
+            };
+            painter.text(
+                egui::pos2(plot.min.x - 4.0, y),
+                align,
+                label,
+                font.clone(),
+                egui::Color32::BLACK,
+            );
+        }
+
+        //
+        // One polyline per run of bins above the floor.
+        //
+        let painter = painter.with_clip_rect(plot);
+        let traces = [
+            (
+                &self.phase_buf,
+                &self.freq_domain_buf,
+                egui::Color32::DARK_BLUE,
+            ),
+            (
+                &self.phase_overlay_buf,
+                &self.overlay_buf,
+                RIGHT_CHANNEL_COLOR,
+            ),
+        ];
+        for (phase, levels, color) in traces {
+            let mut run: Vec<egui::Pos2> = Vec::new();
+            for (i, &p) in phase.iter().enumerate().take(last).skip(first) {
+                if shown(levels, i) {
+                    run.push(egui::pos2(self.bin_to_x(i as f32, plot), to_y(p)));
+                } else if !run.is_empty() {
+                    painter.add(egui::Shape::line(
+                        std::mem::take(&mut run),
+                        egui::Stroke::new(1.0, color),
+                    ));
+                }
+            }
+            if !run.is_empty() {
+                painter.add(egui::Shape::line(run, egui::Stroke::new(1.0, color)));
+            }
+        }
+    }
+
+    /// Draws faint ticks at bin boundaries, starting at the left edge of the
+    /// view and stopping once neighbouring boundaries get too close to tell
+    /// apart or the right edge is reached.
+    fn draw_bin_ticks(&self, painter: &egui::Painter, rect: egui::Rect) {
+        let min_spacing = 4.0;
+        let stroke = egui::Stroke::new(1.0, egui::Color32::from_black_alpha(50));
+        let first = self.frame.hz_to_bin(self.view_range().0).floor() as usize;
+
+        for bin in first..self.frame.dft_size / 2 {
+            let x = self.bin_to_x(bin as f32 + 0.5, rect);
+            let next = self.bin_to_x(bin as f32 + 1.5, rect);
+            if next - x < min_spacing || x > rect.max.x {
+                break;
+            }
+            if x < rect.min.x {
+                continue;
+            }
+            painter.line_segment(
+                [egui::pos2(x, rect.max.y - 6.0), egui::pos2(x, rect.max.y)],
+                stroke,
+            );
+        }
+    }
+
+    /// dB added to every displayed level: 0 for tonal amplitude, or minus
+    /// the bin's ENBW in dB-Hz for noise density. Welch PSD further undoes
+    /// the engine's peak calibration, so levels match `welch_psd`: single-
+    /// sided power is halved from peak² to mean square, two-sided power is
+    /// folded by doubling. DC, which is never folded, reads 3 dB off; the
+    /// Nyquist bin is not shown.
+    fn level_offset_db(&self) -> f32 {
+        if self.welch_enabled {
+            let fold = if self.frame.single_sided { 0.5 } else { 2.0 };
+            10.0 * (fold / self.frame.enbw_hz).log10()
+        } else if self.noise_density {
+            -10.0 * self.frame.enbw_hz.log10()
+        } else {
+            0.0
+        }
+    }
+
+    /// Whether levels are shown per Hz, by Welch PSD or noise density.
+    fn per_hz(&self) -> bool {
+        self.welch_enabled || self.noise_density
+    }
+
+    /// Adds a marker at `hz`, labeled with its number.
+    fn add_marker(&mut self, hz: f32) {
+        self.markers_placed += 1;
+        let label = format!("M{}", self.markers_placed);
+        log::info!("Marker {} at {:.1} Hz", label, hz);
+        self.markers.push(Marker { hz, label });
+    }
+
+    /// Removes the marker drawn nearest to `x` inside `plot`, if one lies
+    /// within `MARKER_HIT_RADIUS`.
+    fn remove_marker_near(&mut self, x: f32, plot: egui::Rect) {
+        let nearest = self
+            .markers
+            .iter()
+            .map(|marker| (self.hz_to_x(marker.hz, plot) - x).abs())
+            .enumerate()
+            .filter(|&(_, distance)| distance <= MARKER_HIT_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((index, _)) = nearest {
+            self.markers.remove(index);
+        }
+    }
+
+    /// Draws every marker inside `rect` as a line with its label, placing
+    /// each frequency with `to_x`. Markers outside `rect` are skipped.
+    fn draw_markers(&self, painter: &egui::Painter, rect: egui::Rect, to_x: impl Fn(f32) -> f32) {
+        let stroke = egui::Stroke::new(1.0, MARKER_COLOR);
+        for marker in &self.markers {
+            let x = to_x(marker.hz);
+            if x < rect.min.x || x > rect.max.x {
+                continue;
+            }
+            painter.line_segment(
+                [egui::pos2(x, rect.min.y), egui::pos2(x, rect.max.y)],
+                stroke,
+            );
+            let align = if x > rect.center().x {
+                egui::Align2::RIGHT_TOP
+            } else {
+                egui::Align2::LEFT_TOP
+            };
+            let offset = if x > rect.center().x { -3.0 } else { 3.0 };
+            painter.text(
+                egui::pos2(x + offset, rect.min.y + 16.0),
+                align,
+                &marker.label,
+                egui::FontId::proportional(10.0),
+                MARKER_COLOR,
+            );
+        }
+    }
+
+    /// Draws a marker at the bin under pointer position `x` with its
+    /// frequency and level, using the inverse of the plot's x-mapping.
+    fn draw_cursor(&self, painter: &egui::Painter, plot: egui::Rect, x: f32) {
+        let t = (x - plot.min.x) / plot.width();
+        let bin = (self.fraction_to_bin(t, self.view_range()).round() as usize)
+            .min(self.freq_domain_buf.len() - 1);
+        let hz = self.frame.bin_to_hz(bin as f32);
+        let t = self.freq_domain_buf[bin];
+        let span = self.relative_span_db;
+        let relative = self.relative_reference().is_some();
+        let level = if relative {
+            (2.0 * t - 1.0) * span
+        } else {
+            self.unit.denormalize(t, self.min_db, self.max_db)
+        };
+
+        //
+        // Snap the marker to the bin so it sits on the plotted point.
+        //
+        let x = self.bin_to_x(bin as f32, plot);
+        painter.line_segment(
+            [egui::pos2(x, plot.min.y), egui::pos2(x, plot.max.y)],
+            egui::Stroke::new(1.0, egui::Color32::from_black_alpha(160)),
+        );
+
+        //
+        // Put the label on whichever side of the marker keeps it inside the plot.
+        //
+        let align = if x > plot.center().x {
+            egui::Align2::RIGHT_TOP
+        } else {
+            egui::Align2::LEFT_TOP
+        };
+        let offset = if x > plot.center().x { -4.0 } else { 4.0 };
+        painter.text(
+            egui::pos2(x + offset, plot.min.y + 4.0),
+            align,
+            match self.unit {
+                _ if relative => format!("{:.1} Hz\n{:+.1} dB rel", hz, level),
+                SpectrumUnit::LinearMagnitude => format!(
+                    "{:.1} Hz\n{:.4} {}",
+                    hz,
+                    level,
+                    self.unit.label(self.per_hz())
+                ),
+                _ => format!(
+                    "{:.1} Hz\n{:.1} {}",
+                    hz,
+                    level,
+                    self.unit.label(self.per_hz())
+                ),
+            },
+            egui::FontId::proportional(11.0),
+            egui::Color32::BLACK,
+        );
+    }
+
+    /// Smooths the dominant frequency for the tuner. Silence clears the
+    /// reading, so the next note starts fresh instead of gliding in.
+    fn update_tuner(&mut self) {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_tuner_update).as_secs_f32();
+        self.last_tuner_update = now;
+        if !self.tuner_enabled || self.paused {
+            return;
+        }
+        self.tuner_hz = match self.frame.dominant_hz {
+            Some(hz) if !self.frame.silent => Some(self.tuner.update(hz, dt)),
+            _ => {
+                self.tuner.reset();
+                None
+            }
+        };
+    }
+
+    /// Draws `band_buf` as filled bars spanning each band's edges on the
+    /// plot's frequency axis, cut off at Nyquist. Draws nothing while no
+    /// band view is selected.
+    fn draw_bands(&self, painter: &egui::Painter, plot: egui::Rect) {
+        let Some(analyzer) = &self.band_analyzer else {
+            return;
+        };
+        let nyquist = self.frame.nyquist_hz();
+        for (band, &val) in analyzer.bands().iter().zip(&self.band_buf) {
+            let left = self.hz_to_x(band.lower_hz, plot);
+            let right = self.hz_to_x(band.upper_hz.min(nyquist), plot);
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(left + 1.0, plot.max.y - val * plot.height()),
+                egui::pos2((right - 1.0).max(left + 2.0), plot.max.y),
+            );
+            painter.rect_filled(bar, 0.0, egui::Color32::DARK_BLUE.gamma_multiply(0.6));
+            painter.rect_stroke(bar, 0.0, egui::Stroke::new(1.0, egui::Color32::DARK_BLUE));
+        }
+    }
+
+    /// Draws the tuner: the nearest note in large type above a ±50 cent
+    /// scale with a needle at the smoothed reading.
+    fn draw_tuner(&self, painter: &egui::Painter, rect: egui::Rect) {
+        let note = self
+            .tuner_hz
+            .and_then(|hz| Some((hz, pitch::nearest_note(hz, self.a4_hz)?)));
+        let color = match note {
+            Some((_, note)) if note.cents.abs() <= TUNER_IN_TUNE_CENTS => {
+                egui::Color32::from_rgb(0, 160, 0)
+            }
+            Some(_) => egui::Color32::from_rgb(220, 120, 0),
+            None => egui::Color32::GRAY,
+        };
+
+        //
+        // Note name and octave, with the exact reading underneath.
+        //
+        let name_size = (rect.height() * 0.3).clamp(24.0, 96.0);
+        let (name, detail) = match note {
+            Some((hz, note)) => (
+                format!("{}{}", note.name(), note.octave()),
+                format!("{:+.0} cents   {:.2} Hz", note.cents, hz),
+            ),
+            None => ("--".to_string(), "No pitch".to_string()),
+        };
+        painter.text(
+            egui::pos2(rect.center().x, rect.top() + rect.height() * 0.3),
+            egui::Align2::CENTER_CENTER,
+            name,
+            egui::FontId::proportional(name_size),
+            color,
+        );
+        painter.text(
+            egui::pos2(rect.center().x, rect.top() + rect.height() * 0.55),
+            egui::Align2::CENTER_CENTER,
+            detail,
+            egui::FontId::monospace(14.0),
+            egui::Color32::BLACK,
+        );
+
+        //
+        // Cent scale with ticks every 10 cents and the in-tune zone shaded.
+        //
+        let scale = egui::Rect::from_center_size(
+            egui::pos2(rect.center().x, rect.top() + rect.height() * 0.8),
+            egui::vec2(rect.width() * 0.8, rect.height() * 0.2),
+        );
+        let cents_to_x = |cents: f32| scale.center().x + cents / 50.0 * scale.width() / 2.0;
+        painter.rect_filled(
+            egui::Rect::from_x_y_ranges(
+                cents_to_x(-TUNER_IN_TUNE_CENTS)..=cents_to_x(TUNER_IN_TUNE_CENTS),
+                scale.y_range(),
+            ),
+            egui::Rounding::ZERO,
+            egui::Color32::from_rgba_unmultiplied(0, 160, 0, 40),
+        );
+        for cents in (-50..=50).step_by(10) {
+            let x = cents_to_x(cents as f32);
+            let top = if cents % 50 == 0 {
+                scale.top()
+            } else {
+                scale.center().y
+            };
+            painter.line_segment(
+                [egui::pos2(x, top), egui::pos2(x, scale.bottom())],
+                egui::Stroke::new(1.0, egui::Color32::DARK_GRAY),
+            );
+            if cents % 25 == 0 {
+                painter.text(
+                    egui::pos2(x, scale.bottom() + 2.0),
+                    egui::Align2::CENTER_TOP,
+                    format!("{:+}", cents),
+                    egui::FontId::proportional(10.0),
+                    egui::Color32::DARK_GRAY,
+                );
+            }
+        }
+        if let Some((_, note)) = note {
+            let x = cents_to_x(note.cents.clamp(-50.0, 50.0));
+            painter.line_segment(
+                [
+                    egui::pos2(x, scale.top() - 6.0),
+                    egui::pos2(x, scale.bottom()),
+                ],
+                egui::Stroke::new(3.0, color),
+            );
+        }
+    }
+
+    /// Draws the bin spacing and count for the current FFT size, fading out
+    /// over the last second of `RESOLUTION_NOTE_DURATION`.
+    fn draw_resolution_note(&self, painter: &egui::Painter, rect: egui::Rect) {
+        let remaining = self
+            .resolution_note_until
+            .saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return;
+        }
+
+        let alpha = remaining.as_secs_f32().min(1.0);
+        painter.text(
+            rect.right_top() + egui::vec2(-4.0, 4.0),
+            egui::Align2::RIGHT_TOP,
+            format!(
+                "{}-point FFT: {:.2} Hz/bin, {} bins",
+                self.frame.dft_size,
+                self.bin_width_hz(),
+                self.frame.dft_size / 2
+            ),
+            egui::FontId::proportional(11.0),
+            egui::Color32::from_black_alpha((alpha * 255.0) as u8),
+        );
+    }
+
+    /// Starts a rolling band-level log in a timestamped CSV file in the
+    /// working directory, or stops the running one.
+    fn toggle_band_logger(&mut self) {
+        if let Some(logger) = self.band_logger.take() {
+            log::info!("Band logger stopped after {} rows", logger.rows());
+            return;
+        }
+
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = format!("band_log_{}.csv", stamp);
+
+        match BandLogger::create(
+            Path::new(&path),
+            self.band_log_resolution,
+            self.frame.sample_rate,
+            self.frame.dft_size,
+            Duration::from_secs_f32(self.band_log_interval_secs),
+        ) {
+            Ok(logger) => {
+                log::info!("Logging band levels to {}", path);
+                self.band_logger = Some(logger);
+            }
+            Err(err) => log::error!("Failed to start band logger at {}: {}", path, err),
+        }
+    }
+
+    /// Writes the current spectrum as ISO band levels to a timestamped CSV
+    /// file in the working directory.
+    fn export_band_csv(&self, resolution: BandResolution) {
+        let analyzer = BandAnalyzer::new(resolution, self.frame.sample_rate, self.frame.dft_size);
+        let levels = analyzer.levels_db(
+            &self.frame.power[0],
+            &self.frame.weighting_db,
+            self.enbw_bins(),
+        );
+
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let kind = match resolution {
+            BandResolution::Octave => "octave",
+            BandResolution::ThirdOctave => "third_octave",
+        };
+        let path = format!("rta_{}_{}.csv", kind, stamp);
+
+        let result = File::create(&path)
+            .and_then(|file| bands::write_csv(BufWriter::new(file), analyzer.bands(), &levels));
+        match result {
+            Ok(()) => log::info!("Exported {} band levels to {}", levels.len(), path),
+            Err(err) => log::error!("Failed to export band levels to {}: {}", path, err),
+        }
+    }
+}
+
+/// Smallest hop offered for an `n`-point FFT.
+fn min_hop(n: usize) -> usize {
+    (n / MIN_HOP_DIVISOR).max(MIN_HOP_SAMPLES).min(n)
+}
+
+/// Rounds `raw` up to the next 1, 2 or 5 times a power of ten.
+fn nice_step(raw: f32) -> f32 {
+    let magnitude = 10f32.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|&s| s >= raw)
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// Formats a tick frequency compactly, e.g. "500" or "2.5k".
+fn format_hz(hz: f32) -> String {
+    if hz >= 1000.0 {
+        let k = hz / 1000.0;
+        if k.fract() == 0.0 {
+            format!("{:.0}k", k)
+        } else {
+            format!("{:.1}k", k)
+        }
+    } else {
+        format!("{:.0}", hz)
+    }
+}
+
+impl eframe::App for AnalyzerApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        //
+        // Run DSP update and request GUI repaint.
+        //
+        if ctx.input(|i| i.key_pressed(egui::Key::Space)) {
+            self.paused = !self.paused;
+        }
+        self.update_dsp();
+        self.update_tuner();
+        ctx.request_repaint();
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            //
+            // Draw top menu bar.
+            //
+            let peak = match self.frame.dominant_hz {
+                Some(hz) => match pitch::nearest_note(hz, self.a4_hz) {
+                    Some(note) => format!("Peak: {:.1} Hz ({})", hz, note),
+                    None => format!("Peak: {:.1} Hz", hz),
+                },
+                None => String::from("Peak: --"),
+            };
+            let peak = match self.snr_db {
+                Some(snr) if self.noise_floor_enabled => format!("{} | SNR {:.1} dB", peak, snr),
+                _ => peak,
+            };
+            theme::draw_menu_bar(ui, &self.plan_name.clone(), &peak, |ui| {
+                ui.menu_button("View", |ui| {
+                    ui.radio_value(&mut self.tuner_enabled, false, "Spectrogram");
+                    ui.radio_value(&mut self.tuner_enabled, true, "Tuner");
+                    ui.separator();
+                    ui.checkbox(&mut self.show_stats, "Statistics");
+                });
+                ui.menu_button("Source", |ui| {
+                    let mut generating = self.parked_source.is_some();
+                    ui.radio_value(&mut generating, false, "Input");
+                    ui.radio_value(&mut generating, true, "Signal generator");
+                    if generating != self.parked_source.is_some() {
+                        if generating {
+                            self.start_generator();
+                        } else {
+                            self.stop_generator();
+                        }
+                    }
+                    ui.separator();
+
+                    //
+                    // Settings apply to a running generator right away and
+                    // are kept for the next start.
+                    //
+                    let mut settings = self.generator_settings;
+                    for waveform in Waveform::ALL {
+                        ui.radio_value(&mut settings.waveform, waveform, waveform.name());
+                    }
+                    let nyquist = self.frame.nyquist_hz();
+                    let label = if settings.waveform == Waveform::Sweep {
+                        "Start (Hz)"
+                    } else {
+                        "Frequency (Hz)"
+                    };
+                    ui.add(
+                        egui::Slider::new(&mut settings.frequency, 1.0..=nyquist)
+                            .logarithmic(true)
+                            .text(label),
+                    );
+                    if settings.waveform == Waveform::Sweep {
+                        ui.add(
+                            egui::Slider::new(&mut settings.sweep_end, 1.0..=nyquist)
+                                .logarithmic(true)
+                                .text("End (Hz)"),
+                        );
+                        ui.add(
+                            egui::Slider::new(&mut settings.sweep_secs, 0.5..=60.0)
+                                .logarithmic(true)
+                                .text("Sweep time (s)"),
+                        );
+                    }
+                    ui.add(egui::Slider::new(&mut settings.amplitude, 0.0..=1.0).text("Amplitude"));
+                    if settings != self.generator_settings {
+                        self.generator_settings = settings;
+                        if let AudioSource::Generator(generator) = &self.audio_source {
+                            generator.set_settings(settings);
+                        }
+                    }
+                });
+                ui.menu_button("Display", |ui| {
+                    ui.menu_button("Frequency scale", |ui| {
+                        for scale in FrequencyScale::ALL {
+                            if ui
+                                .radio_value(&mut self.frequency_scale, scale, scale.name())
+                                .changed()
+                            {
+                                self.clear_waterfall();
+                            }
+                        }
+                    });
+                    if ui
+                        .checkbox(&mut self.zoom_waterfall, "Crop waterfall to zoom")
+                        .changed()
+                        && self.zoom.is_some()
+                    {
+                        self.clear_waterfall();
+                    }
+                    if ui
+                        .add_enabled(self.zoom.is_some(), egui::Button::new("Reset zoom"))
+                        .on_hover_text("Drag across the plot to zoom; double-click to reset")
+                        .clicked()
+                    {
+                        self.reset_zoom();
+                    }
+                    let phase_plot = ui.checkbox(&mut self.phase_enabled, "Phase plot");
+                    let unwrap = ui.add_enabled(
+                        self.phase_enabled,
+                        egui::Checkbox::new(&mut self.phase_unwrap, "Unwrap phase"),
+                    );
+                    if phase_plot.changed() || unwrap.changed() {
+                        self.update_phase();
+                    }
+                    ui.checkbox(&mut self.peak_hold_enabled, "Peak hold");
+                    ui.add_enabled(
+                        self.peak_hold_enabled,
+                        egui::Slider::new(&mut self.peak_decay_db, 0.0..=60.0)
+                            .text("Peak decay (dB/s)"),
+                    );
+                    if ui.button("Reset peaks").clicked() {
+                        self.peak_hold.fill(0.0);
+                    }
+                    if ui
+                        .checkbox(&mut self.noise_floor_enabled, "Noise floor and SNR")
+                        .on_hover_text(
+                            "Track each bin's quietest recent level as a dashed trace, and \
+                             show the peak's level over the floor around it",
+                        )
+                        .changed()
+                    {
+                        self.noise_floor.reset();
+                        self.noise_floor_buf.clear();
+                        self.snr_db = None;
+                    }
+                    if ui
+                        .checkbox(&mut self.persistence_enabled, "Persistence")
+                        .changed()
+                    {
+                        self.persistence.clear();
+                    }
+                    ui.add_enabled(
+                        self.persistence_enabled,
+                        egui::Slider::new(&mut self.persistence_frames, 2..=MAX_PERSISTENCE_FRAMES)
+                            .text("Persistence (frames)"),
+                    );
+                    ui.menu_button("Spectrum view", |ui| {
+                        let mut view = self.band_view;
+                        ui.radio_value(&mut view, None, "FFT bins");
+                        ui.radio_value(&mut view, Some(BandResolution::Octave), "Octave bands");
+                        ui.radio_value(
+                            &mut view,
+                            Some(BandResolution::ThirdOctave),
+                            "Third-octave bands",
+                        );
+                        if view != self.band_view {
+                            self.band_view = view;
+                            self.update_bands();
+                        }
+                    });
+                    ui.separator();
+                    ui.menu_button("Units", |ui| {
+                        for unit in SpectrumUnit::ALL {
+                            if ui.radio_value(&mut self.unit, unit, unit.name()).changed() {
+                                //
+                                // Rows, trails and held peaks were normalized
+                                // in the old unit.
+                                //
+                                self.clear_waterfall();
+                                self.persistence.clear();
+                                self.peak_hold.fill(0.0);
+                            }
+                        }
+                    });
+                    let floor = ui.add(
+                        egui::Slider::new(&mut self.min_db, -160.0..=-MIN_DB_SPAN)
+                            .text("Floor (dB)"),
+                    );
+                    let ceiling = ui.add(
+                        egui::Slider::new(&mut self.max_db, -150.0..=20.0).text("Ceiling (dB)"),
+                    );
+                    if floor.changed() || ceiling.changed() {
+                        //
+                        // Keep the floor below the ceiling, moving whichever
+                        // one the user is not dragging.
+                        //
+                        if floor.changed() {
+                            self.max_db = self.max_db.max(self.min_db + MIN_DB_SPAN);
+                        } else {
+                            self.min_db = self.min_db.min(self.max_db - MIN_DB_SPAN);
+                        }
+
+                        //
+                        // Held peaks are stored normalized to the old range.
+                        //
+                        self.peak_hold.fill(0.0);
+                    }
+                    ui.separator();
+                    if ui
+                        .button("Snapshot")
+                        .on_hover_text(
+                            "Freeze the current spectrum and draw it behind the live one",
+                        )
+                        .clicked()
+                    {
+                        self.take_snapshot();
+                    }
+                    let show_snapshot = ui.add_enabled(
+                        self.snapshot.is_some(),
+                        egui::Checkbox::new(&mut self.show_snapshot, "Show snapshot"),
+                    );
+                    if show_snapshot.changed() {
+                        self.update_snapshot();
+                    }
+                    if ui
+                        .add_enabled(self.snapshot.is_some(), egui::Button::new("Clear snapshot"))
+                        .clicked()
+                    {
+                        self.snapshot = None;
+                        self.show_snapshot = false;
+                        self.snapshot_buf.clear();
+                    }
+                    ui.separator();
+                    if ui
+                        .button("Capture reference")
+                        .on_hover_text(
+                            "Store the current spectrum as the reference for relative display",
+                        )
+                        .clicked()
+                    {
+                        self.capture_reference();
+                    }
+                    let relative = ui
+                        .add_enabled(
+                            self.reference_db.is_some(),
+                            egui::Checkbox::new(&mut self.show_relative, "Show relative"),
+                        )
+                        .on_hover_text("Plot each bin in dB relative to the captured reference");
+                    let span = ui.add_enabled(
+                        self.show_relative,
+                        egui::Slider::new(&mut self.relative_span_db, MIN_DB_SPAN..=60.0)
+                            .text("Relative range (±dB)"),
+                    );
+                    if relative.changed() || span.changed() {
+                        //
+                        // Held peaks and trails were normalized to the old axis.
+                        //
+                        self.peak_hold.fill(0.0);
+                        self.persistence.clear();
+                    }
+                    ui.menu_button("Weighting", |ui| {
+                        let mut settings = *self.worker.settings();
+                        for weighting in Weighting::ALL {
+                            ui.radio_value(&mut settings.weighting, weighting, weighting.name());
+                        }
+                        if settings.weighting != self.worker.settings().weighting {
+                            self.worker.set_settings(settings);
+
+                            //
+                            // Held peaks were measured with the old curve.
+                            //
+                            self.peak_hold.fill(0.0);
+                        }
+                    });
+                    ui.menu_button("Markers", |ui| {
+                        ui.label(
+                            "Shift-click the spectrum to add a marker, right-click one to \
+                             remove it.",
+                        );
+                        ui.checkbox(&mut self.markers_on_waterfall, "Show on waterfall");
+                        ui.separator();
+                        let mut remove = None;
+                        for (index, marker) in self.markers.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut marker.label)
+                                        .desired_width(80.0),
+                                );
+                                ui.label(format!("{:.1} Hz", marker.hz));
+                                if ui.small_button("✕").clicked() {
+                                    remove = Some(index);
+                                }
+                            });
+                        }
+                        if let Some(index) = remove {
+                            self.markers.remove(index);
+                        }
+                        let clear = egui::Button::new("Clear markers");
+                        if ui.add_enabled(!self.markers.is_empty(), clear).clicked() {
+                            self.markers.clear();
+                        }
+                    });
+                    ui.separator();
+                    ui.menu_button("Colormap", |ui| {
+                        for colormap in theme::Colormap::ALL {
+                            if ui
+                                .radio_value(&mut self.colormap, colormap, colormap.name())
+                                .changed()
+                            {
+                                self.recolor_waterfall();
+                            }
+                        }
+                        ui.separator();
+                        let default_gamma = self.colormap.default_gamma();
+                        let gamma = &mut self.colormap_gamma[self.colormap as usize];
+                        let mut changed = ui
+                            .add(
+                                egui::Slider::new(gamma, 0.2..=5.0)
+                                    .logarithmic(true)
+                                    .text("Colormap gamma"),
+                            )
+                            .on_hover_text(
+                                "Kept per colormap and applied to the whole waterfall and \
+                                 the legend",
+                            )
+                            .changed();
+                        if ui.button("Reset gamma").clicked() {
+                            *gamma = default_gamma;
+                            changed = true;
+                        }
+                        if changed {
+                            self.recolor_waterfall();
+                        }
+                    });
+                    if ui
+                        .checkbox(&mut self.heatmap_uniform, "Perceptually uniform colors")
+                        .on_hover_text(
+                            "Space the colormap so equal dB steps give equal steps in \
+                             perceived color",
+                        )
+                        .changed()
+                    {
+                        self.recolor_waterfall();
+                    }
+                    ui.menu_button("Waterfall intensity", |ui| {
+                        ui.label("Applies to rows written from now on");
+                        ui.add(
+                            egui::Slider::new(&mut self.waterfall_contrast, 0.2..=5.0)
+                                .logarithmic(true)
+                                .text("Contrast (γ)"),
+                        );
+                        ui.add(
+                            egui::Slider::new(&mut self.waterfall_brightness, 0.2..=5.0)
+                                .logarithmic(true)
+                                .text("Brightness"),
+                        );
+                        if ui.button("Reset").clicked() {
+                            self.waterfall_contrast = 1.0;
+                            self.waterfall_brightness = 1.0;
+                        }
+                    });
+                    ui.add(
+                        egui::Slider::new(
+                            &mut self.waterfall_row_rate,
+                            MIN_WATERFALL_ROW_RATE..=MAX_WATERFALL_ROW_RATE,
+                        )
+                        .logarithmic(true)
+                        .text("Waterfall speed (rows/s)"),
+                    )
+                    .on_hover_text(
+                        "Rows per second of input. FFTs between rows are averaged; \
+                         faster than one row per FFT hop repeats rows.",
+                    );
+                    let mut rows = self.waterfall_height;
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut rows, MIN_WATERFALL_ROWS..=MAX_WATERFALL_ROWS)
+                                .logarithmic(true)
+                                .text("Waterfall rows"),
+                        )
+                        .on_hover_text("Rows on screen; the image is scaled to fit the window")
+                        .changed()
+                    {
+                        self.set_waterfall_height(rows);
+                    }
+                    ui.checkbox(&mut self.waterfall_fit_width, "Fit waterfall to window")
+                        .on_hover_text(
+                            "Draw one column per screen pixel, combining or interpolating \
+                             bins, instead of one per bin scaled to fit",
+                        );
+                    if ui
+                        .add(
+                            egui::Slider::new(
+                                &mut self.waterfall_history_rows,
+                                self.waterfall_height..=MAX_HISTORY_ROWS,
+                            )
+                            .logarithmic(true)
+                            .text("History rows"),
+                        )
+                        .on_hover_text("Rows kept for scrolling back while paused")
+                        .changed()
+                    {
+                        self.waterfall_history.truncate(self.waterfall_history_rows);
+                        self.scroll_waterfall(self.waterfall_scroll);
+                    }
+                });
+                ui.menu_button("Analysis", |ui| {
+                    ui.menu_button("FFT size", |ui| {
+                        let mut size = self.frame.dft_size;
+                        for n in FFT_SIZES {
+                            ui.radio_value(&mut size, n, n.to_string());
+                        }
+                        self.set_dft_size(size);
+                    });
+                    ui.menu_button("Hop size", |ui| {
+                        let mut settings = *self.worker.settings();
+                        let n = settings.dft_size;
+                        let hop = &mut settings.hop;
+                        for (divisor, label) in HOP_DIVISORS {
+                            ui.radio_value(hop, n / divisor, label);
+                        }
+                        ui.separator();
+                        ui.add(
+                            egui::Slider::new(hop, min_hop(n)..=n)
+                                .logarithmic(true)
+                                .text("Hop (samples)"),
+                        )
+                        .on_hover_text(
+                            "Input between FFT frames. Smaller hops give smoother \
+                             averages and waterfalls for proportionally more CPU.",
+                        );
+                        self.worker.set_settings(settings);
+                    });
+                    ui.menu_button("Window", |ui| {
+                        let mut settings = *self.worker.settings();
+                        let selected = &mut settings.window_function;
+                        for window_fn in WindowFunction::ALL {
+                            let window_fn = match window_fn {
+                                WindowFunction::Kaiser { .. } => WindowFunction::Kaiser {
+                                    beta: self.kaiser_beta,
+                                },
+                                other => other,
+                            };
+                            ui.radio_value(selected, window_fn, window_fn.name());
+                        }
+                        ui.separator();
+                        ui.add(
+                            egui::Slider::new(&mut self.kaiser_beta, 0.0..=20.0).text("Kaiser β"),
+                        );
+
+                        //
+                        // Pick up slider changes for Kaiser; the table is
+                        // rebuilt only when the window actually changes.
+                        //
+                        if let WindowFunction::Kaiser { .. } = selected {
+                            *selected = WindowFunction::Kaiser {
+                                beta: self.kaiser_beta,
+                            };
+                        }
+                        self.worker.set_settings(settings);
+                    });
+                    let mut settings = *self.worker.settings();
+                    ui.checkbox(&mut settings.single_sided, "Single-sided amplitude (×2)");
+                    {
+                        let mut low_cut = settings.low_cut_hz.is_some();
+                        ui.checkbox(&mut low_cut, "Low cut").on_hover_text(
+                            "Zero DC and the bins below the cutoff, so offset and rumble \
+                             do not set the top of the display or the peak readout.",
+                        );
+                        ui.add_enabled(
+                            low_cut,
+                            egui::Slider::new(&mut self.low_cut_hz, 1.0..=500.0)
+                                .logarithmic(true)
+                                .text("Low cut (Hz)"),
+                        );
+                        settings.low_cut_hz = low_cut.then_some(self.low_cut_hz);
+                    }
+                    self.worker.set_settings(settings);
+                    ui.add_enabled(
+                        !self.welch_enabled,
+                        egui::Checkbox::new(&mut self.noise_density, "Noise density (per Hz)"),
+                    )
+                    .on_hover_text(
+                        "Divide by the window's equivalent noise bandwidth, so broadband \
+                         noise reads the same at any window and FFT size. Tones then \
+                         read low by the same amount.",
+                    );
+                    if ui
+                        .checkbox(&mut self.welch_enabled, "Welch PSD")
+                        .on_hover_text(
+                            "Average the last overlapping frames into a one-sided power \
+                             spectral density per Hz, in place of the Averaging setting.",
+                        )
+                        .changed()
+                    {
+                        let mut settings = *self.worker.settings();
+                        if self.welch_enabled {
+                            self.welch_saved_averaging = settings.averaging;
+                        } else {
+                            settings.averaging = self.welch_saved_averaging;
+                            self.worker.set_settings(settings);
+                        }
+                    }
+                    ui.add_enabled(
+                        self.welch_enabled,
+                        egui::Slider::new(&mut self.welch_segments, 2..=MAX_WELCH_SEGMENTS)
+                            .text("Welch segments"),
+                    );
+                    if self.welch_enabled {
+                        let mut settings = *self.worker.settings();
+                        settings.averaging = AveragingMode::LinearN(self.welch_segments);
+                        self.worker.set_settings(settings);
+                    }
+                    ui.add_enabled_ui(!self.welch_enabled, |ui| {
+                        ui.menu_button("Averaging", |ui| {
+                            let mut mode = self.worker.settings().averaging;
+                            ui.radio_value(&mut mode, AveragingMode::None, "None");
+                            ui.radio_value(
+                                &mut mode,
+                                AveragingMode::Exponential(self.averaging_alpha),
+                                "Exponential",
+                            );
+                            ui.radio_value(
+                                &mut mode,
+                                AveragingMode::LinearN(self.averaging_count),
+                                "Linear",
+                            );
+                            ui.separator();
+                            ui.add(
+                                egui::Slider::new(&mut self.averaging_alpha, 0.01..=1.0)
+                                    .logarithmic(true)
+                                    .text("Exponential α"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut self.averaging_count, 2..=64)
+                                    .text("Linear frames"),
+                            );
+
+                            //
+                            // Pick up slider changes for the active mode.
+                            //
+                            mode = match mode {
+                                AveragingMode::None => AveragingMode::None,
+                                AveragingMode::Exponential(_) => {
+                                    AveragingMode::Exponential(self.averaging_alpha)
+                                }
+                                AveragingMode::LinearN(_) => {
+                                    AveragingMode::LinearN(self.averaging_count)
+                                }
+                            };
+                            let mut settings = *self.worker.settings();
+                            settings.averaging = mode;
+                            self.worker.set_settings(settings);
+                        });
+                    });
+                    ui.add(
+                        egui::Slider::new(&mut self.a4_hz, 400.0..=480.0).text("A4 reference (Hz)"),
+                    );
+                    ui.separator();
+                    {
+                        let mut settings = *self.worker.settings();
+                        ui.add(
+                            egui::Slider::new(&mut settings.silence_enter_db, -120.0..=0.0)
+                                .text("Silence enter (dBFS)"),
+                        );
+                        ui.add(
+                            egui::Slider::new(&mut settings.silence_exit_db, -120.0..=0.0)
+                                .text("Silence exit (dBFS)"),
+                        );
+                        let mut hold = settings.silence_timeout.as_secs_f32();
+                        if ui
+                            .add(egui::Slider::new(&mut hold, 0.1..=10.0).text("Silence hold (s)"))
+                            .changed()
+                        {
+                            settings.silence_timeout = Duration::from_secs_f32(hold);
+                        }
+                        self.worker.set_settings(settings);
+                    }
+                    ui.separator();
+                    ui.menu_button("Export RTA bands (CSV)", |ui| {
+                        if ui.button("Octave").clicked() {
+                            self.export_band_csv(BandResolution::Octave);
+                            ui.close_menu();
+                        }
+                        if ui.button("Third-octave").clicked() {
+                            self.export_band_csv(BandResolution::ThirdOctave);
+                            ui.close_menu();
+                        }
+                    });
+                    ui.menu_button("Band level logger", |ui| {
+                        let running = self.band_logger.is_some();
+                        ui.add_enabled_ui(!running, |ui| {
+                            ui.radio_value(
+                                &mut self.band_log_resolution,
+                                BandResolution::Octave,
+                                "Octave",
+                            );
+                            ui.radio_value(
+                                &mut self.band_log_resolution,
+                                BandResolution::ThirdOctave,
+                                "Third-octave",
+                            );
+                        });
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut self.band_log_interval_secs, 1.0..=600.0)
+                                    .logarithmic(true)
+                                    .text("Interval (s)"),
+                            )
+                            .changed()
+                        {
+                            if let Some(logger) = &mut self.band_logger {
+                                logger.set_interval(Duration::from_secs_f32(
+                                    self.band_log_interval_secs,
+                                ));
+                            }
+                        }
+                        let label = if running {
+                            "Stop logging"
+                        } else {
+                            "Start logging"
+                        };
+                        if ui.button(label).clicked() {
+                            self.toggle_band_logger();
+                        }
+                    });
+                });
+                ui.toggle_value(&mut self.paused, "Pause")
+                    .on_hover_text("Freeze the display (Space)");
+            });
+            ui.add_space(4.0);
+
+            //
+            // Input level meter with clip latch and overrun count. The
+            // meter reads after the input trim, which is shown beside it.
+            //
+            ui.horizontal(|ui| {
+                ui.label("Input");
+                let mut settings = *self.worker.settings();
+                ui.add(
+                    egui::Slider::new(&mut settings.input_gain_db, -20.0..=40.0)
+                        .text("Input trim (dB)"),
+                )
+                .on_hover_text(
+                    "Gain applied to the input before analysis; the meter and clip \
+                     indicator include it. Independent of the dB display range.",
+                );
+                self.worker.set_settings(settings);
+                let dropped = self.audio_source.dropped_samples();
+                if dropped > 0 {
+                    ui.colored_label(egui::Color32::RED, format!("{} dropped", dropped))
+                        .on_hover_text("Samples lost because the analyzer fell behind the input");
+                }
+                let (level_db, clipped) =
+                    (self.worker.meter_level_db(), self.worker.meter_clipped());
+                let size = egui::vec2(ui.available_width(), 12.0);
+                if theme::draw_level_meter(ui, size, level_db, -60.0, clipped) {
+                    self.worker.clear_clip();
+                }
+            });
+            ui.add_space(4.0);
+
+            //
+            // Frequency-domain visualization window.
+            //
+            theme::draw_platinum_window(ui, "Frequency Domain", |ui| {
+                let title = if self.tuner_enabled {
+                    "Tuner"
+                } else {
+                    "Spectrogram"
+                };
+                ui.heading(title);
+
+                //
+                // The tuner takes the waterfall's place and size.
+                //
+                let reserved = if self.phase_enabled {
+                    WATERFALL_RESERVED_HEIGHT + PHASE_RESERVED_HEIGHT
+                } else {
+                    WATERFALL_RESERVED_HEIGHT
+                };
+                let display_height =
+                    (ui.available_height() - reserved).max(MIN_WATERFALL_DISPLAY_HEIGHT);
+                if self.tuner_enabled {
+                    egui::Frame::canvas(ui.style()).show(ui, |ui| {
+                        let (rect, _) = ui.allocate_exact_size(
+                            egui::vec2(ui.available_width(), display_height),
+                            egui::Sense::hover(),
+                        );
+                        self.draw_tuner(ui.painter(), rect);
+                    });
+                } else {
+                    //
+                    // Follow the width the image will be drawn at, in
+                    // physical pixels, if asked; otherwise keep one column
+                    // per bin.
+                    //
+                    let legend_width = 44.0;
+                    let columns = if self.waterfall_fit_width {
+                        (((ui.available_width() - legend_width) * ui.ctx().pixels_per_point())
+                            .round() as usize)
+                            .clamp(MIN_WATERFALL_COLUMNS, MAX_WATERFALL_COLUMNS)
+                    } else {
+                        self.frame.dft_size / 2
+                    };
+                    self.set_waterfall_width(columns);
+
+                    //
+                    // Upload waterfall buffer to texture each frame.
+                    //
+                    let width = self.waterfall_width;
+                    let height = self.waterfall_height;
+                    let image = egui::ColorImage::from_rgba_unmultiplied(
+                        [width, height],
+                        &self.waterfall_buf,
+                    );
+                    let filter = if self.waterfall_fit_width {
+                        egui::TextureOptions::LINEAR
+                    } else {
+                        egui::TextureOptions::NEAREST
+                    };
+
+                    if let Some(texture) = &mut self.texture {
+                        texture.set(image, filter);
+                    } else {
+                        self.texture = Some(ui.ctx().load_texture("waterfall", image, filter));
+                    }
+
+                    //
+                    // Draw waterfall texture with its color legend and overlay silence warning.
+                    // While paused, the mouse wheel over it scrolls back through
+                    // the history.
+                    //
+                    let mut wheel_rows = 0.0;
+                    if let Some(tex) = &self.texture {
+                        ui.horizontal(|ui| {
+                            let r = ui.image((
+                                tex.id(),
+                                egui::vec2(ui.available_width() - legend_width, display_height),
+                            ));
+                            if self.paused && r.hovered() {
+                                let rows_per_point = height as f32 / display_height;
+                                let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+                                wheel_rows = -scroll * rows_per_point;
+                            }
+                            let (contrast, brightness) = self.legend_intensity();
+                            theme::draw_color_legend(
+                                ui,
+                                egui::vec2(legend_width, display_height),
+                                &self.level_ticks(),
+                                self.colormap,
+                                contrast,
+                                brightness,
+                                self.heatmap_gamma(),
+                                self.heatmap_uniform,
+                            );
+
+                            //
+                            // Texture columns span the waterfall's band on
+                            // the active frequency scale.
+                            //
+                            if self.markers_on_waterfall {
+                                let range = self.waterfall_range();
+                                let rect = r.rect;
+                                self.draw_markers(&ui.painter().with_clip_rect(rect), rect, |hz| {
+                                    let bin = self.frame.hz_to_bin(hz);
+                                    rect.min.x + self.bin_to_fraction(bin, range) * rect.width()
+                                });
+                            }
+
+                            if self.frame.silent {
+                                let message = match self.audio_source.error() {
+                                    Some(err) => format!("NO SIGNAL\n{}", err),
+                                    None => NO_SIGNAL_HINT.to_string(),
+                                };
+                                ui.painter().text(
+                                    r.rect.center(),
+                                    egui::Align2::CENTER_CENTER,
+                                    message,
+                                    egui::FontId::proportional(20.0),
+                                    egui::Color32::RED,
+                                );
+                            }
+                        });
+                    }
+
+                    if wheel_rows != 0.0 {
+                        let rows = (self.waterfall_scroll as f32 + wheel_rows).round().max(0.0);
+                        self.scroll_waterfall(rows as usize);
+                    }
+
+                    //
+                    // History scrollbar while paused, 0 being the newest rows.
+                    //
+                    if self.paused && self.max_waterfall_scroll() > 0 {
+                        let mut rows = self.waterfall_scroll;
+                        ui.add(
+                            egui::Slider::new(&mut rows, 0..=self.max_waterfall_scroll())
+                                .text("Rows back"),
+                        );
+                        self.scroll_waterfall(rows);
+                    }
+                }
+
+                ui.separator();
+                ui.heading("Instantaneous");
+
+                //
+                // Draw instantaneous spectrum plot.
+                //
+                egui::Frame::canvas(ui.style()).show(ui, |ui| {
+                    let (rect, response) = ui.allocate_exact_size(
+                        egui::vec2(ui.available_width(), 100.0 + AXIS_BOTTOM_MARGIN),
+                        egui::Sense::click_and_drag(),
+                    );
+
+                    //
+                    // Leave room for the dB labels on the left and the
+                    // frequency labels underneath.
+                    //
+                    let plot = egui::Rect::from_min_max(
+                        rect.min + egui::vec2(AXIS_LEFT_MARGIN, 0.0),
+                        rect.max - egui::vec2(0.0, AXIS_BOTTOM_MARGIN),
+                    );
+
+                    //
+                    // Dragging across the plot selects a band to zoom to;
+                    // double-clicking returns to the full band.
+                    //
+                    if response.double_clicked() {
+                        self.reset_zoom();
+                    }
+
+                    //
+                    // Shift-click drops a frequency marker; right-click on
+                    // one removes it.
+                    //
+                    let shift = ui.input(|i| i.modifiers.shift);
+                    let pointer = response.interact_pointer_pos();
+                    if let Some(pos) = pointer.filter(|p| plot.contains(*p)) {
+                        if response.clicked() && shift {
+                            self.add_marker(self.x_to_hz(pos.x, plot));
+                        } else if response.secondary_clicked() {
+                            self.remove_marker_near(pos.x, plot);
+                        }
+                    }
+                    if response.drag_started() {
+                        self.zoom_drag = ui
+                            .input(|i| i.pointer.press_origin())
+                            .filter(|p| plot.contains(*p))
+                            .map(|p| self.x_to_hz(p.x, plot));
+                    }
+                    let drag_x =
+                        self.zoom_drag
+                            .zip(response.interact_pointer_pos())
+                            .map(|(start, pos)| {
+                                let start_x = self.hz_to_x(start, plot);
+                                (start_x, pos.x.clamp(plot.min.x, plot.max.x))
+                            });
+                    if response.drag_stopped() {
+                        if let Some((start_x, end_x)) = drag_x {
+                            if (end_x - start_x).abs() >= MIN_ZOOM_DRAG {
+                                let low = self.x_to_hz(start_x.min(end_x), plot);
+                                let high = self.x_to_hz(start_x.max(end_x), plot);
+                                self.set_zoom(low, high);
+                            }
+                        }
+                        self.zoom_drag = None;
+                    }
+
+                    ui.painter().rect_stroke(
+                        plot,
+                        egui::Rounding::ZERO,
+                        egui::Stroke::new(1.0, egui::Color32::GRAY),
+                    );
+                    self.draw_axes(ui.painter(), plot);
+                    self.draw_bin_ticks(ui.painter(), plot);
+
+                    //
+                    // Traces are clipped to the plot, since bins outside a
+                    // zoomed band map beyond its edges.
+                    //
+                    let painter = ui.painter().with_clip_rect(plot);
+
+                    //
+                    // Persistence trails, oldest first so newer ones draw on
+                    // top, fading out with age. The newest copy is the live
+                    // frame itself and is drawn below at full strength.
+                    //
+                    let trails = self.persistence.len();
+                    for (age, snapshot) in self.persistence.iter().enumerate().skip(1).rev() {
+                        let alpha = PERSISTENCE_ALPHA * (1.0 - age as f32 / trails as f32);
+                        let points: Vec<egui::Pos2> = snapshot
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &val)| {
+                                let x = self.bin_to_x(i as f32, plot);
+                                let y = plot.max.y - (val * plot.height());
+                                egui::Pos2::new(x, y)
+                            })
+                            .collect();
+                        painter.add(egui::Shape::line(
+                            points,
+                            egui::Stroke::new(1.0, egui::Color32::DARK_BLUE.gamma_multiply(alpha)),
+                        ));
+                    }
+
+                    //
+                    // Frozen snapshot behind the live spectrum.
+                    //
+                    if !self.snapshot_buf.is_empty() {
+                        let frozen: Vec<egui::Pos2> = self
+                            .snapshot_buf
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &val)| {
+                                let x = self.bin_to_x(i as f32, plot);
+                                let y = plot.max.y - (val * plot.height());
+                                egui::Pos2::new(x, y)
+                            })
+                            .collect();
+                        painter.add(egui::Shape::line(
+                            frozen,
+                            egui::Stroke::new(1.0, SNAPSHOT_COLOR),
+                        ));
+                    }
+
+                    if self.band_view.is_some() {
+                        self.draw_bands(&painter, plot);
+                    } else {
+                        let points: Vec<egui::Pos2> = self
+                            .freq_domain_buf
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &val)| {
+                                let x = self.bin_to_x(i as f32, plot);
+                                let y = plot.max.y - (val * plot.height());
+                                egui::Pos2::new(x, y)
+                            })
+                            .collect();
+                        painter.add(egui::Shape::line(
+                            points,
+                            egui::Stroke::new(1.0, egui::Color32::DARK_BLUE),
+                        ));
+                    }
+
+                    //
+                    // Right channel over the left in stereo mode.
+                    //
+                    if !self.overlay_buf.is_empty() && self.band_view.is_none() {
+                        let right: Vec<egui::Pos2> = self
+                            .overlay_buf
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &val)| {
+                                let x = self.bin_to_x(i as f32, plot);
+                                let y = plot.max.y - (val * plot.height());
+                                egui::Pos2::new(x, y)
+                            })
+                            .collect();
+                        painter.add(egui::Shape::line(
+                            right,
+                            egui::Stroke::new(1.0, RIGHT_CHANNEL_COLOR),
+                        ));
+                        let font = egui::FontId::proportional(10.0);
+                        let corner = plot.right_top() + egui::vec2(-4.0, 2.0);
+                        painter.text(
+                            corner,
+                            egui::Align2::RIGHT_TOP,
+                            "R",
+                            font.clone(),
+                            RIGHT_CHANNEL_COLOR,
+                        );
+                        painter.text(
+                            corner - egui::vec2(12.0, 0.0),
+                            egui::Align2::RIGHT_TOP,
+                            "L",
+                            font,
+                            egui::Color32::DARK_BLUE,
+                        );
+                    }
+
+                    //
+                    // Noise floor estimate under the peak-hold trace.
+                    //
+                    if self.noise_floor_enabled && !self.noise_floor_buf.is_empty() {
+                        let floor: Vec<egui::Pos2> = self
+                            .noise_floor_buf
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &val)| {
+                                let x = self.bin_to_x(i as f32, plot);
+                                let y = plot.max.y - (val * plot.height());
+                                egui::Pos2::new(x, y)
+                            })
+                            .collect();
+                        painter.extend(egui::Shape::dashed_line(
+                            &floor,
+                            egui::Stroke::new(1.0, egui::Color32::from_gray(90)),
+                            4.0,
+                            3.0,
+                        ));
+                    }
+
+                    //
+                    // Peak-hold trace on top of the live spectrum.
+                    //
+                    if self.peak_hold_enabled {
+                        let peaks: Vec<egui::Pos2> = self
+                            .peak_hold
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &val)| {
+                                let x = self.bin_to_x(i as f32, plot);
+                                let y = plot.max.y - (val * plot.height());
+                                egui::Pos2::new(x, y)
+                            })
+                            .collect();
+                        painter.add(egui::Shape::line(
+                            peaks,
+                            egui::Stroke::new(1.0, egui::Color32::from_rgb(200, 40, 40)),
+                        ));
+                    }
+                    if let Some((start_x, end_x)) = drag_x {
+                        painter.rect_filled(
+                            egui::Rect::from_x_y_ranges(
+                                start_x.min(end_x)..=start_x.max(end_x),
+                                plot.y_range(),
+                            ),
+                            egui::Rounding::ZERO,
+                            egui::Color32::from_rgba_unmultiplied(0, 0, 128, 40),
+                        );
+                    }
+                    self.draw_markers(&painter, plot, |hz| self.hz_to_x(hz, plot));
+                    self.draw_resolution_note(ui.painter(), plot);
+                    if drag_x.is_none() {
+                        if let Some(pos) = response.hover_pos().filter(|p| plot.contains(*p)) {
+                            self.draw_cursor(ui.painter(), plot, pos.x);
+                        }
+                    }
+                });
+
+                if self.phase_enabled {
+                    ui.heading("Phase");
+                    egui::Frame::canvas(ui.style()).show(ui, |ui| {
+                        let (rect, _) = ui.allocate_exact_size(
+                            egui::vec2(ui.available_width(), PHASE_PLOT_HEIGHT),
+                            egui::Sense::hover(),
+                        );
+                        let plot = egui::Rect::from_min_max(
+                            rect.min + egui::vec2(AXIS_LEFT_MARGIN, 0.0),
+                            rect.max,
+                        );
+                        self.draw_phase(ui.painter(), plot);
+                    });
+                }
             });
         });
+        self.draw_stats_window(ctx);
     }
 }
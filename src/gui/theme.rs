@@ -1,4 +1,5 @@
 use eframe::egui;
+use std::sync::OnceLock;
 
 pub const PLATINUM_BG: egui::Color32 = egui::Color32::from_rgb(212, 208, 200);
 pub const PLATINUM_DARK: egui::Color32 = egui::Color32::from_rgb(128, 128, 128);
@@ -24,7 +25,13 @@ pub fn setup_global_style(ctx: &egui::Context) {
 }
 
 /// Draws a simplified menu bar.
-pub fn draw_menu_bar(ui: &mut egui::Ui, algorithm_name: &str) {
+/// `menus` adds the application's menu buttons after the title.
+pub fn draw_menu_bar<F: FnOnce(&mut egui::Ui)>(
+    ui: &mut egui::Ui,
+    algorithm_name: &str,
+    status: &str,
+    menus: F,
+) {
     egui::TopBottomPanel::top("menubar").show_inside(ui, |ui| {
         ui.visuals_mut().widgets.noninteractive.bg_fill = PLATINUM_BG;
         ui.horizontal(|ui| {
@@ -33,11 +40,22 @@ pub fn draw_menu_bar(ui: &mut egui::Ui, algorithm_name: &str) {
             //
             ui.label(egui::RichText::new("fftanalyzer").strong());
 
+            //
+            // Application menus.
+            //
+            menus(ui);
+
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 //
                 // Algorithm information label.
                 //
                 ui.label(egui::RichText::new(algorithm_name).italics().size(10.0));
+                ui.separator();
+
+                //
+                // Live status readout, e.g. the dominant frequency.
+                //
+                ui.label(egui::RichText::new(status).monospace().size(10.0));
             });
         });
     });
@@ -101,8 +119,183 @@ pub fn draw_platinum_window<F: FnOnce(&mut egui::Ui)>(ui: &mut egui::Ui, title:
     });
 }
 
-/// Returns heatmap color (Black → Blue → Cyan → Green → Yellow → Red).
-pub fn get_heatmap_color(val: f32) -> (u8, u8, u8) {
+/// Color scales available for the waterfall and its legend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Colormap {
+    Jet,
+    Viridis,
+    Magma,
+    Grayscale,
+}
+
+// Colormap samples at evenly spaced levels, interpolated linearly.
+const VIRIDIS: [(u8, u8, u8); 9] = [
+    (68, 1, 84),
+    (71, 44, 122),
+    (59, 82, 139),
+    (44, 114, 142),
+    (33, 145, 140),
+    (40, 174, 128),
+    (94, 201, 98),
+    (173, 220, 48),
+    (253, 231, 37),
+];
+const MAGMA: [(u8, u8, u8); 9] = [
+    (0, 0, 4),
+    (28, 16, 68),
+    (79, 18, 123),
+    (129, 37, 129),
+    (181, 54, 122),
+    (229, 80, 100),
+    (251, 135, 97),
+    (254, 194, 135),
+    (252, 253, 191),
+];
+
+impl Colormap {
+    pub const ALL: [Colormap; 4] = [
+        Colormap::Jet,
+        Colormap::Viridis,
+        Colormap::Magma,
+        Colormap::Grayscale,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Colormap::Jet => "Jet",
+            Colormap::Viridis => "Viridis",
+            Colormap::Magma => "Magma",
+            Colormap::Grayscale => "Grayscale",
+        }
+    }
+
+    /// Gamma each colormap starts with. Magma and grayscale stay near
+    /// black over their lower third, so they are lifted to keep quiet
+    /// detail visible; Jet and Viridis are bright enough as they are.
+    pub fn default_gamma(self) -> f32 {
+        match self {
+            Colormap::Jet | Colormap::Viridis => 1.0,
+            Colormap::Magma => 0.8,
+            Colormap::Grayscale => 0.6,
+        }
+    }
+
+    /// Maps a normalized level in `0.0..=1.0` to a color.
+    pub fn color(&self, val: f32) -> (u8, u8, u8) {
+        match self {
+            Colormap::Jet => jet(val),
+            Colormap::Viridis => lookup(&VIRIDIS, val),
+            Colormap::Magma => lookup(&MAGMA, val),
+            Colormap::Grayscale => {
+                let v = (val.clamp(0.0, 1.0) * 255.0) as u8;
+                (v, v, v)
+            }
+        }
+    }
+}
+
+// Colormap samples taken to measure its perceptual spacing.
+const UNIFORM_SAMPLES: usize = 256;
+
+//
+// `ALL` lists every colormap in declaration order, so a colormap's
+// discriminant indexes tables sized by `ALL.len()`.
+//
+const _: () = {
+    let mut i = 0;
+    while i < Colormap::ALL.len() {
+        assert!(Colormap::ALL[i] as usize == i);
+        i += 1;
+    }
+};
+
+impl Colormap {
+    /// Position along the colormap at which the color has covered `val` of
+    /// the colormap's total perceptual change from its low end.
+    ///
+    /// Perceptual change is measured as CIE76 ΔE, the distance in CIELAB,
+    /// summed between `UNIFORM_SAMPLES` evenly spaced colors. Since levels
+    /// are linear in dB, equal dB steps then give roughly equal steps in
+    /// perceived color, even where a colormap like Jet changes hue quickly
+    /// or lingers near one color. The table is measured once per colormap.
+    pub fn uniform_position(self, val: f32) -> f32 {
+        static TABLES: [OnceLock<Vec<f32>>; Colormap::ALL.len()] =
+            [const { OnceLock::new() }; Colormap::ALL.len()];
+        let table = TABLES[self as usize].get_or_init(|| self.arc_length_table());
+
+        //
+        // Invert the cumulative distance, interpolating within the sample
+        // interval it falls in.
+        //
+        let val = val.clamp(0.0, 1.0);
+        let i = table
+            .partition_point(|&d| d < val)
+            .clamp(1, table.len() - 1);
+        let (lo, hi) = (table[i - 1], table[i]);
+        let t = if hi > lo { (val - lo) / (hi - lo) } else { 0.0 };
+        ((i - 1) as f32 + t.clamp(0.0, 1.0)) / (table.len() - 1) as f32
+    }
+
+    /// Cumulative ΔE from the low end at each sample, normalized to end at 1.
+    fn arc_length_table(self) -> Vec<f32> {
+        let mut table = Vec::with_capacity(UNIFORM_SAMPLES);
+        let mut total = 0.0;
+        let mut prev = srgb_to_lab(self.color(0.0));
+        for s in 0..UNIFORM_SAMPLES {
+            let lab = srgb_to_lab(self.color(s as f32 / (UNIFORM_SAMPLES - 1) as f32));
+            total += prev
+                .iter()
+                .zip(&lab)
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum::<f32>()
+                .sqrt();
+            table.push(total);
+            prev = lab;
+        }
+        if total > 0.0 {
+            table.iter_mut().for_each(|d| *d /= total);
+        }
+        table
+    }
+}
+
+/// Converts an sRGB color to CIELAB under the D65 white point.
+fn srgb_to_lab((r, g, b): (u8, u8, u8)) -> [f32; 3] {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let (r, g, b) = (linear(r), linear(g), linear(b));
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f32| {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// Linearly interpolates between evenly spaced entries of `table`.
+fn lookup(table: &[(u8, u8, u8)], val: f32) -> (u8, u8, u8) {
+    let pos = val.clamp(0.0, 1.0) * (table.len() - 1) as f32;
+    let i = (pos as usize).min(table.len() - 2);
+    let t = pos - i as f32;
+    let (a, b) = (table[i], table[i + 1]);
+    let mix = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * t).round() as u8;
+    (mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+}
+
+/// Jet-style gradient (Black → Blue → Cyan → Green → Yellow → Red).
+fn jet(val: f32) -> (u8, u8, u8) {
     if val < 0.2 {
         //
         // Black → Blue gradient.
@@ -133,3 +326,189 @@ pub fn get_heatmap_color(val: f32) -> (u8, u8, u8) {
     //
     (255, (255.0 - (val - 0.8) * 5.0 * 255.0) as u8, 0)
 }
+
+/// Raises a normalized level to `contrast` and scales it by `brightness`,
+/// clamped to `0.0..=1.0`.
+pub fn adjust_intensity(val: f32, contrast: f32, brightness: f32) -> f32 {
+    (val.clamp(0.0, 1.0).powf(contrast) * brightness).clamp(0.0, 1.0)
+}
+
+/// Maps a normalized level to a heatmap color: raised to the colormap's
+/// `gamma`, then through `Colormap::uniform_position` if `uniform`. Both
+/// the waterfall rows and the color legend go through this function.
+pub fn map_heatmap_color(colormap: Colormap, val: f32, gamma: f32, uniform: bool) -> (u8, u8, u8) {
+    let val = val.clamp(0.0, 1.0).powf(gamma);
+    if uniform {
+        colormap.color(colormap.uniform_position(val))
+    } else {
+        colormap.color(val)
+    }
+}
+
+/// Draws a vertical color scale for the waterfall, labelled with `ticks`
+/// given as (height in `0.0..=1.0`, label) pairs. Levels go through
+/// `adjust_intensity` with the `contrast` and `brightness` the described
+/// rows were written with, then `map_heatmap_color`.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_color_legend(
+    ui: &mut egui::Ui,
+    size: egui::Vec2,
+    ticks: &[(f32, String)],
+    colormap: Colormap,
+    contrast: f32,
+    brightness: f32,
+    gamma: f32,
+    uniform: bool,
+) {
+    let (rect, _response) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter();
+    let bar = egui::Rect::from_min_size(rect.min, egui::vec2(12.0, rect.height()));
+
+    //
+    // Gradient bar, bottom = floor, top = ceiling.
+    //
+    let steps = 64;
+    for s in 0..steps {
+        let y0 = bar.max.y - (s + 1) as f32 / steps as f32 * bar.height();
+        let y1 = bar.max.y - s as f32 / steps as f32 * bar.height();
+        let val = adjust_intensity((s as f32 + 0.5) / steps as f32, contrast, brightness);
+        let (r, g, b) = map_heatmap_color(colormap, val, gamma, uniform);
+        painter.rect_filled(
+            egui::Rect::from_min_max(egui::pos2(bar.min.x, y0), egui::pos2(bar.max.x, y1)),
+            0.0,
+            egui::Color32::from_rgb(r, g, b),
+        );
+    }
+    painter.rect_stroke(bar, 0.0, egui::Stroke::new(1.0, egui::Color32::BLACK));
+
+    //
+    // Tick marks and labels.
+    //
+    for (t, label) in ticks {
+        let y = bar.max.y - t * bar.height();
+        painter.line_segment(
+            [egui::pos2(bar.max.x, y), egui::pos2(bar.max.x + 4.0, y)],
+            egui::Stroke::new(1.0, egui::Color32::BLACK),
+        );
+        painter.text(
+            egui::pos2(bar.max.x + 6.0, y),
+            egui::Align2::LEFT_CENTER,
+            label,
+            egui::FontId::proportional(10.0),
+            egui::Color32::BLACK,
+        );
+    }
+}
+
+/// Draws a horizontal input level meter over `floor_db..=0` dBFS followed by
+/// a clip lamp. Returns `true` when the lamp is clicked to clear the latch.
+pub fn draw_level_meter(
+    ui: &mut egui::Ui,
+    size: egui::Vec2,
+    level_db: f32,
+    floor_db: f32,
+    clipped: bool,
+) -> bool {
+    let lamp_width = 28.0;
+    let (rect, _response) = ui.allocate_exact_size(
+        egui::vec2(size.x - lamp_width - 4.0, size.y),
+        egui::Sense::hover(),
+    );
+    let painter = ui.painter();
+
+    //
+    // Bar: green up to -6 dBFS, yellow to -3 dBFS, red above.
+    //
+    painter.rect_filled(rect, 0.0, egui::Color32::from_rgb(40, 40, 40));
+    let fraction = ((level_db - floor_db) / -floor_db).clamp(0.0, 1.0);
+    let bar =
+        egui::Rect::from_min_size(rect.min, egui::vec2(rect.width() * fraction, rect.height()));
+    let color = if level_db > -3.0 {
+        egui::Color32::from_rgb(220, 40, 40)
+    } else if level_db > -6.0 {
+        egui::Color32::from_rgb(230, 200, 40)
+    } else {
+        egui::Color32::from_rgb(40, 190, 70)
+    };
+    painter.rect_filled(bar, 0.0, color);
+    painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::BLACK));
+
+    //
+    // Clip lamp, latched until clicked.
+    //
+    let (lamp, response) =
+        ui.allocate_exact_size(egui::vec2(lamp_width, size.y), egui::Sense::click());
+    let fill = if clipped {
+        egui::Color32::RED
+    } else {
+        egui::Color32::from_rgb(90, 20, 20)
+    };
+    ui.painter().rect_filled(lamp, 0.0, fill);
+    ui.painter()
+        .rect_stroke(lamp, 0.0, egui::Stroke::new(1.0, egui::Color32::BLACK));
+    ui.painter().text(
+        lamp.center(),
+        egui::Align2::CENTER_CENTER,
+        "CLIP",
+        egui::FontId::proportional(9.0),
+        egui::Color32::WHITE,
+    );
+    response.on_hover_text("Click to reset").clicked()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_position_spans_the_colormap_in_order() {
+        for colormap in Colormap::ALL {
+            assert_eq!(colormap.uniform_position(0.0), 0.0, "{:?}", colormap);
+            assert_eq!(colormap.uniform_position(1.0), 1.0, "{:?}", colormap);
+            let mut prev = 0.0;
+            for s in 1..=1000 {
+                let pos = colormap.uniform_position(s as f32 / 1000.0);
+                assert!(pos >= prev, "{:?} at {}: {} < {}", colormap, s, pos, prev);
+                prev = pos;
+            }
+        }
+    }
+
+    #[test]
+    fn gamma_is_applied_before_the_colormap() {
+        for colormap in Colormap::ALL {
+            for uniform in [false, true] {
+                let at = |val, gamma| map_heatmap_color(colormap, val, gamma, uniform);
+                assert_eq!(at(0.25, 0.5), at(0.5, 1.0), "{:?}", colormap);
+                assert_eq!(at(0.0, 0.6), at(0.0, 1.0), "{:?}", colormap);
+                assert_eq!(at(1.0, 0.6), at(1.0, 1.0), "{:?}", colormap);
+            }
+        }
+    }
+
+    #[test]
+    fn srgb_to_lab_matches_reference_values() {
+        //
+        // CIELAB of the sRGB primaries, white and black under D65.
+        //
+        let cases = [
+            ((255, 255, 255), [100.0, 0.0, 0.0]),
+            ((0, 0, 0), [0.0, 0.0, 0.0]),
+            ((255, 0, 0), [53.24, 80.09, 67.20]),
+            ((0, 255, 0), [87.73, -86.18, 83.18]),
+            ((0, 0, 255), [32.30, 79.19, -107.86]),
+        ];
+        for (rgb, expected) in cases {
+            let lab = srgb_to_lab(rgb);
+            for (got, want) in lab.iter().zip(expected) {
+                assert!(
+                    (got - want).abs() < 0.1,
+                    "{:?}: {:?} vs {:?}",
+                    rgb,
+                    lab,
+                    expected
+                );
+            }
+        }
+    }
+}
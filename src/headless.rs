@@ -0,0 +1,227 @@
+use fftanalyzer::audio::{self, ChannelMode, HostBackend, PcmFormat};
+use fftanalyzer::dsp::engine::SpectrumEngine;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+//
+// How often live input is drained, and how long past the requested
+// duration to wait for a device that stops delivering.
+//
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+const CAPTURE_GRACE: Duration = Duration::from_secs(5);
+
+/// Where a headless run reads its audio.
+pub enum Input<'a> {
+    /// A WAV file, analyzed as fast as it decodes.
+    File(&'a Path),
+    /// Raw PCM on stdin, read to its end before analysis.
+    Pipe {
+        format: PcmFormat,
+        sample_rate: u32,
+        channels: usize,
+    },
+    /// Live capture for a fixed number of seconds of input.
+    Capture {
+        host: HostBackend,
+        sample_rate: Option<u32>,
+        latency: Option<u32>,
+        seconds: f32,
+    },
+}
+
+/// Runs the engine over `input` with `dft_size`-point frames and writes the
+/// mean power spectrum of every channel to `output` as CSV.
+pub fn dump_spectrum(
+    input: Input,
+    channel_mode: ChannelMode,
+    dft_size: usize,
+    output: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let mean = match input {
+        Input::File(path) => {
+            let (sample_rate, consumers) = audio::read_file_source(path, channel_mode)?;
+            let mut engine = SpectrumEngine::with_channels(consumers, sample_rate, dft_size);
+            let mut mean = MeanSpectrum::new(&engine);
+            while engine.next_frame() {
+                mean.add(&engine);
+            }
+            mean
+        }
+        Input::Pipe {
+            format,
+            sample_rate,
+            channels,
+        } => {
+            let consumers =
+                audio::read_pcm_source(io::stdin().lock(), format, channels, channel_mode)?;
+            let mut engine = SpectrumEngine::with_channels(consumers, sample_rate, dft_size);
+            let mut mean = MeanSpectrum::new(&engine);
+            while engine.next_frame() {
+                mean.add(&engine);
+            }
+            mean
+        }
+        Input::Capture {
+            host,
+            sample_rate,
+            latency,
+            seconds,
+        } => {
+            let (capture, consumers) = audio::start_capture(audio::CaptureOptions {
+                host,
+                channels: channel_mode,
+                sample_rate,
+                latency_hint: latency,
+                ..audio::CaptureOptions::new(dft_size)
+            })?;
+            let mut engine =
+                SpectrumEngine::with_channels(consumers, capture.sample_rate(), dft_size);
+            let mut mean = MeanSpectrum::new(&engine);
+
+            //
+            // Count the duration in captured samples rather than wall time,
+            // so a late-starting device still delivers all of it.
+            //
+            let wanted = (seconds.max(0.0) as f64 * capture.sample_rate() as f64) as usize;
+            let deadline =
+                Instant::now() + Duration::from_secs_f32(seconds.max(0.0)) + CAPTURE_GRACE;
+            log::info!("Capturing {:.1}s of input...", seconds);
+            while mean.samples < wanted {
+                if Instant::now() > deadline {
+                    return Err("audio capture stopped delivering input".into());
+                }
+                thread::sleep(POLL_INTERVAL);
+                while mean.samples < wanted && engine.next_frame() {
+                    mean.add(&engine);
+                }
+            }
+            if capture.dropped_samples() > 0 {
+                log::warn!("{} samples were dropped", capture.dropped_samples());
+            }
+            mean
+        }
+    };
+
+    if mean.frames == 0 {
+        return Err(format!("input is shorter than one {}-point frame", dft_size).into());
+    }
+    mean.write_csv(BufWriter::new(File::create(output)?))?;
+    log::info!(
+        "Wrote the mean of {} frames to {}",
+        mean.frames,
+        output.display()
+    );
+    Ok(())
+}
+
+/// Per-channel mean of the engine's power spectra, in linear power.
+struct MeanSpectrum {
+    sums: Vec<Vec<f64>>,
+    frames: usize,
+    bin_width_hz: f32,
+    dft_size: usize,
+    hop: usize,
+
+    //
+    // Input consumed so far, one hop per frame seen.
+    //
+    samples: usize,
+}
+
+impl MeanSpectrum {
+    fn new(engine: &SpectrumEngine) -> Self {
+        Self {
+            sums: vec![vec![0.0; engine.power().len()]; engine.channel_count()],
+            frames: 0,
+            bin_width_hz: engine.bin_width_hz(),
+            dft_size: engine.dft_size(),
+            hop: engine.hop(),
+            samples: 0,
+        }
+    }
+
+    /// Adds the engine's latest frame. Frames whose history still holds the
+    /// engine's initial zeros are counted as input but left out of the mean.
+    fn add(&mut self, engine: &SpectrumEngine) {
+        self.samples += self.hop;
+        if self.samples < self.dft_size {
+            return;
+        }
+        for (index, sums) in self.sums.iter_mut().enumerate() {
+            for (sum, &p) in sums.iter_mut().zip(engine.channel_power(index)) {
+                *sum += p as f64;
+            }
+        }
+        self.frames += 1;
+    }
+
+    /// One row per bin: its frequency and the mean level of each channel in
+    /// dB relative to full scale.
+    fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        match self.sums.len() {
+            1 => writeln!(writer, "frequency_hz,level_db")?,
+            _ => writeln!(writer, "frequency_hz,left_db,right_db")?,
+        }
+        for bin in 0..self.sums[0].len() {
+            write!(writer, "{:.2}", bin as f32 * self.bin_width_hz)?;
+            for sums in &self.sums {
+                let power = sums[bin] / self.frames as f64;
+                write!(writer, ",{:.2}", 10.0 * power.max(1e-18).log10())?;
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ringbuf::HeapRb;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn mean_spectrum_of_a_tone_is_written_as_csv() {
+        //
+        // A half-scale tone on bin 32 of a 256-point frame, 1 kHz at
+        // 8 kHz, reads -6 dBFS in its bin.
+        //
+        let n = 256;
+        let (mut producer, consumer) = HeapRb::<f32>::new(16 * n).split();
+        for i in 0..16 * n {
+            let _ = producer.push(0.5 * (2.0 * PI * (32 * i) as f32 / n as f32).sin());
+        }
+        let mut engine = SpectrumEngine::new(consumer, 8000, n);
+        let mut mean = MeanSpectrum::new(&engine);
+        while engine.next_frame() {
+            mean.add(&engine);
+        }
+
+        //
+        // 32 hops, less the first, whose frame still holds the initial
+        // zeros.
+        //
+        assert_eq!(mean.samples, 16 * n);
+        assert_eq!(mean.frames, 31);
+
+        let mut csv = Vec::new();
+        mean.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("frequency_hz,level_db"));
+        let rows: Vec<(f32, f32)> = lines
+            .map(|line| {
+                let (hz, db) = line.split_once(',').unwrap();
+                (hz.parse().unwrap(), db.parse().unwrap())
+            })
+            .collect();
+        assert_eq!(rows.len(), n / 2);
+        assert_eq!(rows[32].0, 1000.0);
+        assert!((rows[32].1 + 6.02).abs() < 0.05, "{}", rows[32].1);
+        assert!(rows.iter().all(|&(_, db)| db <= rows[32].1));
+    }
+}
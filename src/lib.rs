@@ -0,0 +1,30 @@
+//! SIMD-accelerated FFT engine and real-time spectrum analysis building blocks.
+//!
+//! The transform planner (`find_dft`) selects a strategy per size: SIMD
+//! leaf kernels, Cooley-Tukey, Rader or Bluestein. Audio capture and the
+//! DSP helpers used by the analyzer GUI are exposed alongside it, without
+//! depending on the GUI toolkit.
+#![feature(portable_simd)]
+
+pub mod audio;
+pub mod dsp;
+pub mod fft;
+
+pub use audio::{
+    read_file_source, read_pcm_source, start_capture, start_file_source, start_file_source_with,
+    start_generator, start_stdin_source, AudioSource, CaptureError, CaptureOptions, CaptureSource,
+    ChannelMode, DeviceSelector, DownmixMode, FilePlayer, GeneratorSettings, HostBackend,
+    LiveCapture, PcmFormat, PcmSource, SignalGenerator, Waveform,
+};
+pub use dsp::engine::{DspStats, EngineSettings, SpectrumEngine};
+pub use dsp::streaming::StreamingFft;
+pub use dsp::welch::welch_psd;
+pub use dsp::worker::{SpectrumFrame, SpectrumWorker};
+pub use fft::improved::{
+    BatchComplex, DFTImproved, DftKernel, Kernel1, Kernel10, Kernel12, Kernel15, Kernel16, Kernel2,
+    Kernel3, Kernel4, Kernel5, Kernel6, Kernel7, Kernel8, Kernel9,
+};
+pub use fft::{
+    export_wisdom, find_dft, find_dft_2d, find_dft_f64, find_dft_generic, find_dft_pooled,
+    find_rdft, import_wisdom, ChirpZ, Convolver, DFTBase, Direction, FftError, FftFloat, RDFTBase,
+};
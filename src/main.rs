@@ -1,34 +1,259 @@
-#![feature(portable_simd)]
-mod audio;
-mod fft;
 mod gui;
+mod headless;
 
-use fft::find_dft;
+use clap::Parser;
+use fftanalyzer::audio::{self, ChannelMode, DownmixMode, HostBackend, PcmFormat};
+use fftanalyzer::dsp::engine::SpectrumEngine;
 use gui::AnalyzerApp;
+use std::path::{Path, PathBuf};
 
 // Configuration constants.
-const SAMPLE_RATE: u32 = 44100; // Standard audio sample rate.
+const DEFAULT_SAMPLE_RATE: u32 = 44100; // Assumed when no source is available.
 const DFT_SIZE: usize = 2048; // FFT size balancing resolution and latency.
+const MAX_DFT_SIZE: usize = 8192; // Largest FFT size selectable at runtime.
+const WISDOM_PATH: &str = "fftanalyzer.wisdom"; // Saved FFTW planner state.
+
+/// Command-line options. Without `--dump` the GUI starts; with it the
+/// spectrum is written to a file and the program exits.
+#[derive(Parser)]
+#[command(version, about = "Real-time audio spectrum analyzer")]
+struct Args {
+    /// WAV file to analyze instead of live input.
+    #[arg(value_name = "FILE", conflicts_with = "input")]
+    path: Option<PathBuf>,
+
+    /// Same as FILE.
+    #[arg(long, value_name = "FILE")]
+    input: Option<PathBuf>,
+
+    /// Read headerless PCM in this format from stdin: f32le, f32be, s16le,
+    /// s16be, s24le, s32le or u8. Needs --rate.
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_parser = parse_raw_format,
+        conflicts_with_all = ["path", "input"],
+        requires = "rate"
+    )]
+    raw: Option<PcmFormat>,
+
+    /// Interleaved channels in the --raw stream.
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "raw")]
+    raw_channels: usize,
+
+    /// Analyze left and right separately.
+    #[arg(long, conflicts_with = "channel")]
+    stereo: bool,
+
+    /// Analyze only this channel (0-based).
+    #[arg(long, value_name = "N")]
+    channel: Option<usize>,
+
+    /// Mono downmix: average, sum, energy or average-all.
+    #[arg(long, value_name = "MODE", value_parser = parse_downmix, default_value = "average")]
+    downmix: DownmixMode,
+
+    /// Sample rate to ask the input device for, or the rate of --raw input.
+    #[arg(long, value_name = "HZ")]
+    rate: Option<u32>,
+
+    /// Frames per device buffer to ask the input device for.
+    #[arg(long, value_name = "FRAMES")]
+    latency: Option<u32>,
+
+    /// cpal host to capture through.
+    #[arg(long, value_name = "NAME", value_parser = parse_host, default_value = "default")]
+    host: HostBackend,
+
+    /// FFT size in samples.
+    #[arg(long, value_name = "N", default_value_t = DFT_SIZE, value_parser = parse_fft_size)]
+    fft_size: usize,
+
+    /// Write the mean spectrum as CSV to this file instead of starting the GUI.
+    #[arg(long, value_name = "CSV")]
+    dump: Option<PathBuf>,
+
+    /// Seconds of live input to average for --dump.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 10.0,
+        requires = "dump"
+    )]
+    seconds: f32,
+}
+
+impl Args {
+    /// `--stereo` analyzes left and right separately, `--channel` a single
+    /// channel, and otherwise the input is downmixed to mono.
+    fn channel_mode(&self) -> ChannelMode {
+        match (self.stereo, self.channel) {
+            (true, _) => ChannelMode::Stereo,
+            (false, Some(index)) => ChannelMode::Channel(index),
+            (false, None) => ChannelMode::Downmix(self.downmix),
+        }
+    }
+}
+
+fn parse_downmix(name: &str) -> Result<DownmixMode, String> {
+    DownmixMode::from_name(name).ok_or_else(|| {
+        format!(
+            "expected one of {:?}",
+            DownmixMode::ALL.map(DownmixMode::name)
+        )
+    })
+}
+
+fn parse_raw_format(name: &str) -> Result<PcmFormat, String> {
+    PcmFormat::from_name(name)
+        .ok_or_else(|| format!("expected one of {:?}", PcmFormat::ALL.map(PcmFormat::name)))
+}
+
+fn parse_fft_size(text: &str) -> Result<usize, String> {
+    match text.parse() {
+        Ok(n) if n >= 2 => Ok(n),
+        _ => Err(String::from("expected a size of at least 2 samples")),
+    }
+}
+
+fn parse_host(name: &str) -> Result<HostBackend, String> {
+    HostBackend::from_name(name)
+        .ok_or_else(|| format!("this build supports {:?}", HostBackend::names()))
+}
 
 fn main() -> Result<(), eframe::Error> {
     //
     // Initialize logging with default filter set to "info".
     //
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let args = Args::parse();
 
     log::info!("Starting real-time audio spectrum analyzer...");
 
     //
-    // Initialize FFT/DSP plan.
+    // Reuse planner measurements from earlier runs; a missing file is normal
+    // on first start.
     //
-    log::info!("Initializing FFT plan for N={}", DFT_SIZE);
-    let fft_plan = find_dft(DFT_SIZE);
+    if let Err(err) = fftanalyzer::import_wisdom(Path::new(WISDOM_PATH)) {
+        log::info!("No FFT wisdom loaded: {}", err);
+    }
+
+    let channel_mode = args.channel_mode();
+    let Args {
+        path,
+        input,
+        raw,
+        raw_channels,
+        rate: sample_rate,
+        latency,
+        host,
+        fft_size,
+        dump,
+        seconds,
+        ..
+    } = args;
+    let path = path.or(input);
 
     //
-    // Initialize audio capture subsystem.
+    // Headless runs analyze the input once and exit without opening a
+    // window.
     //
-    log::info!("Initializing audio apture...");
-    let (audio_stream, audio_consumer) = audio::start_capture(DFT_SIZE);
+    if let Some(output) = dump {
+        let input = match (&path, raw) {
+            (_, Some(format)) => headless::Input::Pipe {
+                format,
+                sample_rate: sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE),
+                channels: raw_channels,
+            },
+            (Some(path), None) => headless::Input::File(path),
+            (None, None) => headless::Input::Capture {
+                host,
+                sample_rate,
+                latency,
+                seconds,
+            },
+        };
+        let result = headless::dump_spectrum(input, channel_mode, fft_size, &output);
+        if let Err(err) = fftanalyzer::export_wisdom(Path::new(WISDOM_PATH)) {
+            log::warn!("Failed to save FFT wisdom: {}", err);
+        }
+        if let Err(err) = result {
+            log::error!("Spectrum dump failed: {}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    //
+    // Read PCM from stdin with --raw, play a WAV file if one is given on
+    // the command line, otherwise initialize the audio capture subsystem.
+    // The ring buffer is sized for the largest FFT so switching sizes at
+    // runtime never starves it.
+    //
+    let buffer_size = MAX_DFT_SIZE.max(fft_size);
+    let (audio_source, audio_consumers): (audio::AudioSource, _) = match (path, raw) {
+        (_, Some(format)) => {
+            log::info!("Reading PCM from stdin...");
+            let (pipe, consumers) = audio::start_stdin_source(
+                format,
+                sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE),
+                raw_channels,
+                channel_mode,
+                buffer_size,
+            );
+            (pipe.into(), consumers)
+        }
+        (Some(path), None) => {
+            log::info!("Opening audio file...");
+            if sample_rate.is_some() {
+                log::warn!("Ignoring --rate: files play at their own rate");
+            }
+            if latency.is_some() {
+                log::warn!("Ignoring --latency: it applies to live capture only");
+            }
+            match audio::start_file_source_with(&path, channel_mode, buffer_size) {
+                Ok((player, consumers)) => (player.into(), consumers),
+                Err(err) => {
+                    log::error!("Failed to open {:?}: {}", path, err);
+                    let (source, consumer) =
+                        audio::AudioSource::unavailable(format!("Cannot open file: {}", err));
+                    (source, vec![consumer])
+                }
+            }
+        }
+        (None, None) => {
+            log::info!("Initializing audio apture...");
+            match audio::start_capture(audio::CaptureOptions {
+                host,
+                channels: channel_mode,
+                sample_rate,
+                latency_hint: latency,
+                ..audio::CaptureOptions::new(buffer_size)
+            }) {
+                Ok((capture, consumers)) => (capture.into(), consumers),
+                Err(err) => {
+                    //
+                    // Keep the window up and explain the problem there.
+                    //
+                    log::error!("Audio capture failed: {}", err);
+                    let (source, consumer) = audio::AudioSource::unavailable(err);
+                    (source, vec![consumer])
+                }
+            }
+        }
+    };
+
+    //
+    // Initialize FFT/DSP engine over the selected source, at the rate the
+    // source actually delivers so every frequency shown matches the input.
+    //
+    let sample_rate = audio_source.sample_rate().unwrap_or(DEFAULT_SAMPLE_RATE);
+    log::info!(
+        "Initializing FFT plan for N={} at {}Hz",
+        fft_size,
+        sample_rate
+    );
+    let engine = SpectrumEngine::with_channels(audio_consumers, sample_rate, fft_size);
 
     //
     // Initialize GUI configuration.
@@ -45,7 +270,7 @@ fn main() -> Result<(), eframe::Error> {
     //
     // Launch GUI application.
     //
-    eframe::run_native(
+    let result = eframe::run_native(
         "fftanalyzer",
         options,
         Box::new(move |cc| {
@@ -54,13 +279,15 @@ fn main() -> Result<(), eframe::Error> {
             //
             // Construct and return the analyzer application instance.
             //
-            Ok(Box::new(AnalyzerApp::new(
-                cc,
-                audio_consumer,
-                audio_stream,
-                fft_plan,
-                DFT_SIZE,
-            )))
+            Ok(Box::new(AnalyzerApp::new(cc, audio_source, engine)))
         }),
-    )
+    );
+
+    //
+    // Save planner state, including plans for FFT sizes picked at runtime.
+    //
+    if let Err(err) = fftanalyzer::export_wisdom(Path::new(WISDOM_PATH)) {
+        log::warn!("Failed to save FFT wisdom: {}", err);
+    }
+    result
 }
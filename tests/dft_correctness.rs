@@ -0,0 +1,381 @@
+//
+// Every plan strategy compared against a naive O(n²) DFT evaluated in f64.
+//
+use fftanalyzer::fft::iterative::DFTIterativeP2;
+use fftanalyzer::fft::orig::{DFTBluestein, DFTRader, DFTRadix};
+use fftanalyzer::fft::prime_power::DFTPrimePower;
+use fftanalyzer::fft::split_radix::DFTSplitRadix;
+use fftanalyzer::{find_dft, find_dft_f64, DFTBase, Direction};
+use num_complex::{Complex32, Complex64};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Small deterministic LCG so failures reproduce exactly.
+fn signal(n: usize, seed: u64) -> Vec<Complex64> {
+    let mut state = seed;
+    let mut next = || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((state >> 33) as f64 / (1u64 << 31) as f64) * 2.0 - 1.0
+    };
+    (0..n).map(|_| Complex64::new(next(), next())).collect()
+}
+
+fn naive_dft(input: &[Complex64], direction: Direction) -> Vec<Complex64> {
+    let n = input.len();
+    let sign = direction.sign() as f64;
+    (0..n)
+        .map(|k| {
+            input
+                .iter()
+                .enumerate()
+                .map(|(j, &x)| {
+                    //
+                    // Reduce j*k mod n first so large sizes keep full precision.
+                    //
+                    let phase = sign * 2.0 * PI * ((j * k) % n) as f64 / n as f64;
+                    x * Complex64::from_polar(1.0, phase)
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Allowed max-abs error: unit-amplitude input gives outputs of order √n,
+/// and rounding grows roughly with log n passes over the data.
+fn tolerance(n: usize, eps: f64) -> f64 {
+    let n = n as f64;
+    eps * 16.0 * n.sqrt() * n.log2().max(1.0)
+}
+
+fn check_f32(plan: &dyn DFTBase, direction: Direction) {
+    let n = plan.size();
+    let input = signal(n, n as u64);
+    let expected = naive_dft(&input, direction);
+
+    let input32: Vec<Complex32> = input
+        .iter()
+        .map(|c| Complex32::new(c.re as f32, c.im as f32))
+        .collect();
+    let mut output = vec![Complex32::default(); n];
+    plan.xform(&input32, &mut output);
+
+    let err = output
+        .iter()
+        .zip(&expected)
+        .map(|(o, e)| (Complex64::new(o.re as f64, o.im as f64) - e).norm())
+        .fold(0.0, f64::max);
+    let tol = tolerance(n, f32::EPSILON as f64);
+    assert!(
+        err <= tol,
+        "{} (n={}): max error {:e} exceeds {:e}",
+        plan.name(),
+        n,
+        err,
+        tol
+    );
+}
+
+fn check_f64(plan: &dyn DFTBase<f64>, direction: Direction) {
+    let n = plan.size();
+    let input = signal(n, n as u64);
+    let expected = naive_dft(&input, direction);
+
+    let mut output = vec![Complex64::default(); n];
+    plan.xform(&input, &mut output);
+
+    let err = output
+        .iter()
+        .zip(&expected)
+        .map(|(o, e)| (o - e).norm())
+        .fold(0.0, f64::max);
+    let tol = tolerance(n, f64::EPSILON);
+    assert!(
+        err <= tol,
+        "{} (n={}): max error {:e} exceeds {:e}",
+        plan.name(),
+        n,
+        err,
+        tol
+    );
+}
+
+/// Checks `xform_inverse` against the naive opposite-direction DFT scaled
+/// by 1/n, then that it undoes `xform`.
+fn check_inverse_f64(plan: &dyn DFTBase<f64>) {
+    let n = plan.size();
+    let input = signal(n, n as u64);
+    let expected: Vec<Complex64> = naive_dft(&input, plan.direction().opposite())
+        .iter()
+        .map(|&x| x / n as f64)
+        .collect();
+    let mut output = vec![Complex64::default(); n];
+    plan.xform_inverse(&input, &mut output);
+
+    let mut spectrum = vec![Complex64::default(); n];
+    let mut round_trip = vec![Complex64::default(); n];
+    plan.xform(&input, &mut spectrum);
+    plan.xform_inverse(&spectrum, &mut round_trip);
+
+    let tol = tolerance(n, f64::EPSILON);
+    for (what, got, want) in [
+        ("inverse", &output, &expected),
+        ("round trip", &round_trip, &input),
+    ] {
+        let err = got
+            .iter()
+            .zip(want)
+            .map(|(o, e)| (o - e).norm())
+            .fold(0.0, f64::max);
+        assert!(
+            err <= tol,
+            "{} {} (n={}): max error {:e} exceeds {:e}",
+            plan.name(),
+            what,
+            n,
+            err,
+            tol
+        );
+    }
+}
+
+//
+// Every SIMD kernel size, primes (7, 13, 4099), prime powers (9, 27, 125),
+// mixed radices and large composites.
+//
+const SIZES: [usize; 30] = [
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 18, 27, 30, 49, 64, 97, 100, 125, 210,
+    243, 1000, 1024, 2048, 4099,
+];
+
+#[test]
+fn planner_matches_naive_dft() {
+    for &n in &SIZES {
+        for direction in [Direction::Forward, Direction::Inverse] {
+            check_f32(&*find_dft(n, direction), direction);
+            check_f64(&*find_dft_f64(n, direction), direction);
+        }
+    }
+}
+
+#[test]
+fn radix_matches_naive_dft() {
+    for n in [4, 6, 12, 18, 27, 36, 60, 64, 81, 256, 360, 1000, 1024] {
+        for direction in [Direction::Forward, Direction::Inverse] {
+            check_f32(&DFTRadix::new(n, direction), direction);
+            check_f64(&DFTRadix::<f64>::new(n, direction), direction);
+        }
+    }
+
+    //
+    // Factors of two are taken eight or four at a time.
+    //
+    let p = |n| DFTRadix::<f32>::new(n, Direction::Forward).name();
+    assert_eq!(p(1024), "RadixP<8>(1024)");
+    assert_eq!(p(12), "RadixP<4>(12)");
+    assert_eq!(p(18), "RadixP<2>(18)");
+    assert_eq!(p(45), "RadixP<3>(45)");
+}
+
+#[test]
+fn prime_power_matches_naive_dft() {
+    for n in [4, 9, 25, 27, 49, 81, 121, 125, 243, 343, 729, 2187] {
+        for direction in [Direction::Forward, Direction::Inverse] {
+            check_f32(&DFTPrimePower::new(n, direction), direction);
+            check_f64(&DFTPrimePower::<f64>::new(n, direction), direction);
+        }
+    }
+
+    //
+    // The planner takes odd prime powers above the kernel sizes here,
+    // unless FFTW plans every size.
+    //
+    #[cfg(not(feature = "use_fftw"))]
+    {
+        let name = |n| find_dft(n, Direction::Forward).name();
+        assert_eq!(name(27), "PrimePower<3>(27)");
+        assert_eq!(name(125), "PrimePower<5>(125)");
+        assert_eq!(name(45), "RadixP<3>(45)");
+    }
+}
+
+#[test]
+fn split_radix_matches_naive_dft() {
+    for n in [8, 16, 32, 64, 128, 512, 4096] {
+        for direction in [Direction::Forward, Direction::Inverse] {
+            check_f32(&DFTSplitRadix::new(n, direction), direction);
+            check_f64(&DFTSplitRadix::<f64>::new(n, direction), direction);
+        }
+    }
+}
+
+#[test]
+fn iterative_p2_matches_naive_dft() {
+    for n in [1, 2, 4, 8, 32, 128, 1024, 4096] {
+        for direction in [Direction::Forward, Direction::Inverse] {
+            check_f32(&DFTIterativeP2::new(n, direction), direction);
+            check_f64(&DFTIterativeP2::<f64>::new(n, direction), direction);
+        }
+    }
+}
+
+#[test]
+fn rader_matches_naive_dft() {
+    for n in [3, 5, 7, 11, 13, 17, 31, 97, 257] {
+        for direction in [Direction::Forward, Direction::Inverse] {
+            check_f32(&DFTRader::new(n, direction), direction);
+            check_f64(&DFTRader::<f64>::new(n, direction), direction);
+        }
+    }
+}
+
+#[test]
+fn rader_and_bluestein_invert_themselves() {
+    for direction in [Direction::Forward, Direction::Inverse] {
+        for n in [5, 13, 101, 1009] {
+            check_inverse_f64(&DFTRader::<f64>::new(n, direction));
+        }
+        for n in [13, 100, 1009] {
+            let nb = DFTBluestein::<f64>::convolution_size(n);
+            check_inverse_f64(&DFTBluestein::<f64>::new(n, nb, direction));
+        }
+    }
+
+    //
+    // f32 round trip through a strided batch of prime-size Rader inverses.
+    //
+    let (n, count) = (257, 4);
+    let plan = DFTRader::<f32>::new(n, Direction::Forward);
+    let input: Vec<Complex32> = signal(n * count, 3)
+        .iter()
+        .map(|c| Complex32::new(c.re as f32, c.im as f32))
+        .collect();
+    let mut spectrum = vec![Complex32::default(); n * count];
+    let mut output = vec![Complex32::default(); n * count];
+    plan.xform_many(&input, &mut spectrum, count, 1, count, 1, count);
+    plan.xform_many_inverse(&spectrum, &mut output, count, 1, count, 1, count);
+    let err = output
+        .iter()
+        .zip(&input)
+        .map(|(o, e)| (o - e).norm())
+        .fold(0.0, f32::max);
+    assert!(err < 1e-5, "max round-trip error {:e}", err);
+}
+
+#[test]
+fn rader_strided_batches_match_naive_dft() {
+    //
+    // Three transforms read interleaved and written to padded, strided
+    // blocks, as a prime step inside a larger composite plan sees them.
+    //
+    let count = 3;
+    for n in [11, 13] {
+        for direction in [Direction::Forward, Direction::Inverse] {
+            let plan = DFTRader::<f64>::new(n, direction);
+            let blocks: Vec<Vec<Complex64>> =
+                (0..count).map(|i| signal(n, (n + i) as u64)).collect();
+
+            let mut input = vec![Complex64::default(); n * count];
+            for (i, block) in blocks.iter().enumerate() {
+                for (j, &x) in block.iter().enumerate() {
+                    input[j * count + i] = x;
+                }
+            }
+            let (ostep, ostep2) = (2, 2 * n + 1);
+            let sentinel = Complex64::new(f64::NAN, f64::NAN);
+            let mut output = vec![sentinel; ostep2 * count];
+            plan.xform_many(&input, &mut output, count, 1, ostep, ostep2, count);
+
+            for (i, block) in blocks.iter().enumerate() {
+                let expected = naive_dft(block, direction);
+                for (k, e) in expected.iter().enumerate() {
+                    let o = output[i * ostep2 + k * ostep];
+                    assert!(
+                        (o - e).norm() <= tolerance(n, f64::EPSILON),
+                        "{} transform {} bin {}: got {} want {}",
+                        plan.name(),
+                        i,
+                        k,
+                        o,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    //
+    // The same steps batched by a composite plan.
+    //
+    for direction in [Direction::Forward, Direction::Inverse] {
+        let plan = DFTRadix::<f64>::with_sub_plans(
+            143,
+            direction,
+            Arc::new(DFTRader::new(11, direction)),
+            Arc::new(DFTRader::new(13, direction)),
+        );
+        check_f64(&plan, direction);
+    }
+}
+
+#[test]
+fn bluestein_matches_naive_dft() {
+    //
+    // Bluestein accepts any size, so force it on composites as well as the
+    // primes the planner would send there anyway.
+    //
+    for n in [2usize, 7, 13, 20, 27, 100, 509, 1000] {
+        let nb = (2 * n - 1).next_power_of_two();
+        for direction in [Direction::Forward, Direction::Inverse] {
+            check_f32(&DFTBluestein::new(n, nb, direction), direction);
+            check_f64(&DFTBluestein::<f64>::new(n, nb, direction), direction);
+        }
+    }
+}
+
+#[test]
+fn bluestein_with_a_smooth_convolution_matches_naive_dft() {
+    //
+    // Just past a power of two, the planner's convolution is 7-smooth
+    // rather than twice as long.
+    //
+    for n in [17usize, 33, 2053] {
+        let nb = DFTBluestein::<f32>::convolution_size(n);
+        assert!(nb < (2 * n - 1).next_power_of_two(), "n={} nb={}", n, nb);
+        for direction in [Direction::Forward, Direction::Inverse] {
+            check_f32(&DFTBluestein::new(n, nb, direction), direction);
+            check_f64(&DFTBluestein::<f64>::new(n, nb, direction), direction);
+        }
+    }
+    assert_eq!(DFTBluestein::<f32>::convolution_size(1009), 2048);
+}
+
+#[test]
+fn bluestein_scratch_reuse_keeps_results_stable() {
+    //
+    // Plans keep their working buffer between calls. A large batch leaves
+    // non-zero data past each block's padding boundary, which a following
+    // single transform must not pick up.
+    //
+    let n = 100;
+    let plan = DFTBluestein::new(n, (2 * n - 1).next_power_of_two(), Direction::Forward);
+    let input: Vec<Complex32> = signal(n, 7)
+        .iter()
+        .map(|c| Complex32::new(c.re as f32, c.im as f32))
+        .collect();
+
+    let mut first = vec![Complex32::default(); n];
+    plan.xform(&input, &mut first);
+
+    let batch: Vec<Complex32> = (0..8).flat_map(|_| input.iter().copied()).collect();
+    let mut batch_out = vec![Complex32::default(); 8 * n];
+    plan.xform_many(&batch, &mut batch_out, 1, n, 1, n, 8);
+
+    let mut again = vec![Complex32::default(); n];
+    plan.xform(&input, &mut again);
+    assert_eq!(first, again);
+    for block in batch_out.chunks_exact(n) {
+        assert_eq!(block, &first[..]);
+    }
+}